@@ -0,0 +1,91 @@
+use std::{env, fs, path::PathBuf, process::ExitCode};
+
+use chip8_interpreter::{assemble, Program, Symbol};
+
+const USAGE: &str =
+  "Usage: chip8-asm <source.8o> [-o <image.ch8>] [-s <symbols.txt>] [-l <lines.txt>]";
+
+struct Args {
+  source_path: PathBuf,
+  image_path: PathBuf,
+  symbols_path: Option<PathBuf>,
+  lines_path: Option<PathBuf>,
+}
+
+fn parse_args() -> Result<Args, String> {
+  let mut source_path = None;
+  let mut image_path = None;
+  let mut symbols_path = None;
+  let mut lines_path = None;
+
+  let mut args = env::args().skip(1);
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "-o" => image_path = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+      "-s" => symbols_path = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+      "-l" => lines_path = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+      "-h" | "--help" => return Err(String::from(USAGE)),
+      _ if source_path.is_none() && !arg.starts_with('-') => source_path = Some(PathBuf::from(arg)),
+      _ => return Err(format!("Unexpected argument: {arg}\n{USAGE}")),
+    }
+  }
+
+  let source_path = source_path.ok_or(USAGE)?;
+  Ok(Args {
+    image_path: image_path.unwrap_or_else(|| source_path.with_extension("ch8")),
+    source_path,
+    symbols_path,
+    lines_path,
+  })
+}
+
+fn format_symbols(program: &Program) -> String {
+  program
+    .get_symbols()
+    .iter()
+    .map(|(name, symbol)| match symbol {
+      Symbol::Label(address) => format!("label\t{name}\t0x{:04X}\n", address.as_u16()),
+      Symbol::Const(value) => format!("const\t{name}\t{value}\n"),
+      Symbol::Alias(register) => format!("alias\t{name}\tv{:x}\n", register.as_u8()),
+    })
+    .collect()
+}
+
+fn format_source_lines(program: &Program) -> String {
+  program
+    .get_source_lines()
+    .iter()
+    .map(|(address, line)| format!("{line}\t0x{:04X}\n", address.as_u16()))
+    .collect()
+}
+
+fn run(args: &Args) -> Result<(), String> {
+  let source_name = args.source_path.display();
+  let source =
+    fs::read_to_string(&args.source_path).map_err(|err| format!("{source_name}: {err}"))?;
+  let program = assemble(&source)
+    .map_err(|err| format!("{source_name}:{}: {}", err.get_line(), err.get_kind()))?;
+
+  fs::write(&args.image_path, program.get_image())
+    .map_err(|err| format!("{}: {err}", args.image_path.display()))?;
+  if let Some(symbols_path) = &args.symbols_path {
+    fs::write(symbols_path, format_symbols(&program))
+      .map_err(|err| format!("{}: {err}", symbols_path.display()))?;
+  }
+  if let Some(lines_path) = &args.lines_path {
+    fs::write(lines_path, format_source_lines(&program))
+      .map_err(|err| format!("{}: {err}", lines_path.display()))?;
+  }
+  Ok(())
+}
+
+fn main() -> ExitCode {
+  let res = parse_args().and_then(|args| run(&args));
+  match res {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(message) => {
+      eprintln!("{message}");
+      ExitCode::FAILURE
+    }
+  }
+}
@@ -0,0 +1,103 @@
+use std::{iter::Peekable, str::Chars};
+
+use chip8_interpreter::{Address, InterpreterState, Nibble};
+
+/// Evaluates sums and differences of registers (`v0`..`vF`, `I`, `PC`, `DT`, `ST`, `SP`),
+/// numbers and memory bytes in square brackets, e.g. `[i + v1] - 2`.
+pub fn evaluate(expression: &str, state: &InterpreterState) -> Result<i64, String> {
+  let mut evaluator = Evaluator {
+    chars: expression.chars().peekable(),
+    state,
+  };
+  let value = evaluator.parse_sum()?;
+  evaluator.skip_whitespace();
+  match evaluator.chars.next() {
+    Some(char) => Err(format!("Unexpected character: {char}")),
+    None => Ok(value),
+  }
+}
+
+struct Evaluator<'a, 'b> {
+  chars: Peekable<Chars<'a>>,
+  state: &'a InterpreterState<'b>,
+}
+
+impl Evaluator<'_, '_> {
+  fn skip_whitespace(&mut self) {
+    while self.chars.next_if(|char| char.is_whitespace()).is_some() {}
+  }
+
+  fn parse_sum(&mut self) -> Result<i64, String> {
+    let mut value = self.parse_term()?;
+    loop {
+      self.skip_whitespace();
+      let sum = match self.chars.next_if(|&char| char == '+' || char == '-') {
+        Some('+') => value.checked_add(self.parse_term()?),
+        Some(_) => value.checked_sub(self.parse_term()?),
+        None => return Ok(value),
+      };
+      value = sum.ok_or(String::from("Overflow"))?;
+    }
+  }
+
+  fn parse_term(&mut self) -> Result<i64, String> {
+    self.skip_whitespace();
+    if self.chars.next_if_eq(&'[').is_some() {
+      let address = self.parse_sum()?;
+      self.skip_whitespace();
+      if self.chars.next_if_eq(&']').is_none() {
+        return Err(String::from("Expected ]"));
+      }
+      return self.read_memory(address);
+    }
+
+    let mut word = String::new();
+    while let Some(char) = self.chars.next_if(|char| char.is_ascii_alphanumeric()) {
+      word.push(char);
+    }
+    match word.is_empty() {
+      true => Err(String::from("Expected a register, a number or [")),
+      false => self.resolve_word(&word),
+    }
+  }
+
+  fn read_memory(&self, address: i64) -> Result<i64, String> {
+    let address = u16::try_from(address).map_err(|_| format!("Invalid address: {address}"))?;
+    self
+      .state
+      .get_memory_slice(Address::from(address), 1)
+      .map(|bytes| bytes[0] as i64)
+      .ok_or(format!("Invalid address: 0x{address:X}"))
+  }
+
+  fn resolve_word(&self, word: &str) -> Result<i64, String> {
+    let lowercase = word.to_ascii_lowercase();
+    let value = match lowercase.as_str() {
+      "i" => self.state.get_index_register().as_u16() as i64,
+      "pc" => self.state.get_instruction_address().as_u16() as i64,
+      "dt" => self.state.get_delay_timer() as i64,
+      "st" => self.state.get_sound_timer() as i64,
+      "sp" => self.state.get_stack_depth() as i64,
+      _ => {
+        let register = lowercase
+          .strip_prefix('v')
+          .filter(|index| index.len() == 1)
+          .and_then(|index| u8::from_str_radix(index, 16).ok())
+          .and_then(|index| Nibble::try_from(index).ok());
+        match register {
+          Some(index) => self.state.get_register(index) as i64,
+          None => parse_number(&lowercase).ok_or(format!("Unknown identifier: {word}"))?,
+        }
+      }
+    };
+    Ok(value)
+  }
+}
+
+fn parse_number(text: &str) -> Option<i64> {
+  match (text.strip_prefix("0x"), text.strip_prefix("0b")) {
+    (Some(hex), _) => i64::from_str_radix(hex, 16).ok(),
+    (_, Some(binary)) => i64::from_str_radix(binary, 2).ok(),
+    _ => text.parse().ok(),
+  }
+}
@@ -0,0 +1,61 @@
+mod evaluator;
+mod protocol;
+mod session;
+
+use std::{
+  io::{self, BufReader},
+  process::ExitCode,
+  sync::mpsc::{self, TryRecvError},
+  thread,
+};
+
+use protocol::{read_message, Sender};
+use session::Session;
+
+/// Serves one debugging session over stdin and stdout.
+fn run() -> io::Result<()> {
+  let (requests_sender, requests) = mpsc::channel();
+  thread::spawn(move || {
+    let mut reader = BufReader::new(io::stdin());
+    while let Ok(Some(request)) = read_message(&mut reader) {
+      if requests_sender.send(request).is_err() {
+        break;
+      }
+    }
+  });
+
+  let mut sender = Sender::new(io::stdout());
+  let mut session = Session::new();
+  loop {
+    let request = match session.is_running() {
+      true => match requests.try_recv() {
+        Ok(request) => Some(request),
+        Err(TryRecvError::Empty) => None,
+        Err(TryRecvError::Disconnected) => return Ok(()),
+      },
+      false => match requests.recv() {
+        Ok(request) => Some(request),
+        Err(_) => return Ok(()),
+      },
+    };
+
+    match request {
+      Some(request) => {
+        if !session.handle(&request, &mut sender)? {
+          return Ok(());
+        }
+      }
+      None => session.run_frame(&mut sender)?,
+    }
+  }
+}
+
+fn main() -> ExitCode {
+  match run() {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(err) => {
+      eprintln!("{err}");
+      ExitCode::FAILURE
+    }
+  }
+}
@@ -0,0 +1,77 @@
+use std::io::{self, BufRead, Write};
+
+use serde_json::{json, Value};
+
+/// Reads the next message framed with a `Content-Length` header.
+pub fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Value>> {
+  let mut content_length = None;
+  loop {
+    let mut header = String::new();
+    if reader.read_line(&mut header)? == 0 {
+      return Ok(None);
+    }
+    let header = header.trim_end();
+    if header.is_empty() {
+      break;
+    }
+    if let Some((name, value)) = header.split_once(':') {
+      if name.eq_ignore_ascii_case("Content-Length") {
+        content_length = value.trim().parse::<usize>().ok();
+      }
+    }
+  }
+
+  let content_length = content_length
+    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Missing Content-Length"))?;
+  let mut content = vec![0; content_length];
+  reader.read_exact(&mut content)?;
+  serde_json::from_slice(&content)
+    .map(Some)
+    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Writes responses and events with increasing sequence numbers.
+pub struct Sender<W: Write> {
+  writer: W,
+  seq: u64,
+}
+
+impl<W: Write> Sender<W> {
+  pub fn new(writer: W) -> Self {
+    Self { writer, seq: 0 }
+  }
+
+  fn send(&mut self, mut message: Value) -> io::Result<()> {
+    self.seq += 1;
+    message["seq"] = json!(self.seq);
+    let content = message.to_string();
+    write!(
+      self.writer,
+      "Content-Length: {}\r\n\r\n{content}",
+      content.len()
+    )?;
+    self.writer.flush()
+  }
+
+  pub fn respond(&mut self, request: &Value, res: Result<Value, String>) -> io::Result<()> {
+    let mut response = json!({
+      "type": "response",
+      "request_seq": request["seq"],
+      "command": request["command"],
+      "success": res.is_ok(),
+    });
+    match res {
+      Ok(body) => response["body"] = body,
+      Err(message) => response["message"] = json!(message),
+    }
+    self.send(response)
+  }
+
+  pub fn event(&mut self, event: &str, body: Value) -> io::Result<()> {
+    self.send(json!({
+      "type": "event",
+      "event": event,
+      "body": body,
+    }))
+  }
+}
@@ -0,0 +1,710 @@
+use std::{
+  collections::BTreeMap,
+  fs,
+  io::{self, Write},
+  path::Path,
+  thread,
+  time::{Duration, Instant},
+};
+
+use chip8_interpreter::{
+  Address, BaseExecutable, BasePlatform, Breakpoint, ControlledInterpreter, Font, Interpreter,
+  Mode, Nibble, Platform, Quirks, StopReason, DEFAULT_DELAY_TIMER_DURATION,
+  DEFAULT_INSTRUCTION_DURATION, DEFAULT_SOUND_TIMER_DURATION,
+};
+use serde_json::{json, Value};
+
+use crate::{evaluator::evaluate, protocol::Sender};
+
+const THREAD_ID: u64 = 1;
+const REGISTERS_REFERENCE: u64 = 1;
+const STACK_REFERENCE: u64 = 2;
+
+type Chip8 = ControlledInterpreter<BasePlatform<fn() -> u8>>;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum RunMode {
+  Stopped,
+  Continue,
+  StepIn,
+  StepOver { depth: usize },
+  StepOut { depth: usize },
+}
+
+pub struct Session {
+  interpreter: Option<Chip8>,
+  source: Option<Value>,
+  source_lines: BTreeMap<Address, usize>,
+  line_breakpoints: Vec<Address>,
+  instruction_breakpoints: Vec<Address>,
+  run_mode: RunMode,
+  run_started: Option<Instant>,
+  run_started_elapsed: Duration,
+  is_stop_on_entry: bool,
+}
+
+impl Session {
+  pub fn new() -> Self {
+    Self {
+      interpreter: None,
+      source: None,
+      source_lines: BTreeMap::new(),
+      line_breakpoints: Vec::new(),
+      instruction_breakpoints: Vec::new(),
+      run_mode: RunMode::Stopped,
+      run_started: None,
+      run_started_elapsed: Duration::ZERO,
+      is_stop_on_entry: false,
+    }
+  }
+
+  pub fn is_running(&self) -> bool {
+    self.run_mode != RunMode::Stopped
+  }
+
+  /// Handles one request. Returns `false` once the client disconnected.
+  pub fn handle<W: Write>(&mut self, request: &Value, sender: &mut Sender<W>) -> io::Result<bool> {
+    let command = request["command"].as_str().unwrap_or_default();
+    let args = &request["arguments"];
+    let res = match command {
+      "initialize" => Ok(json!({
+        "supportsConfigurationDoneRequest": true,
+        "supportsInstructionBreakpoints": true,
+        "supportsSetVariable": true,
+        "supportsReadMemoryRequest": true,
+        "supportsEvaluateForHovers": true,
+      })),
+      "launch" => self.launch(args),
+      "disconnect" | "terminate" => {
+        sender.respond(request, Ok(json!({})))?;
+        return Ok(false);
+      }
+      _ if self.interpreter.is_none() => Err(String::from("No program is launched")),
+      "setBreakpoints" => self.set_breakpoints(args),
+      "setInstructionBreakpoints" => self.set_instruction_breakpoints(args),
+      "configurationDone" => {
+        sender.respond(request, Ok(json!({})))?;
+        match self.is_stop_on_entry {
+          true => self.stop("entry", None, sender)?,
+          false => self.resume(RunMode::Continue),
+        }
+        return Ok(true);
+      }
+      "threads" => Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "CHIP-8" }] })),
+      "stackTrace" => Ok(self.get_stack_trace()),
+      "scopes" => Ok(json!({
+        "scopes": [
+          { "name": "Registers", "variablesReference": REGISTERS_REFERENCE, "expensive": false },
+          { "name": "Stack", "variablesReference": STACK_REFERENCE, "expensive": false },
+        ]
+      })),
+      "variables" => Ok(self.get_variables(args)),
+      "setVariable" => self.set_variable(args),
+      "readMemory" => self.read_memory(args),
+      "evaluate" => self.evaluate(args),
+      "continue" => {
+        self.resume(RunMode::Continue);
+        Ok(json!({ "allThreadsContinued": true }))
+      }
+      "stepIn" | "next" | "stepOut" => {
+        let depth = self.get_interpreter().get_state().get_stack_depth();
+        self.resume(match command {
+          "stepIn" => RunMode::StepIn,
+          "next" => RunMode::StepOver { depth },
+          _ => RunMode::StepOut { depth },
+        });
+        Ok(json!({}))
+      }
+      "pause" => {
+        sender.respond(request, Ok(json!({})))?;
+        self.stop("pause", None, sender)?;
+        return Ok(true);
+      }
+      _ => Err(format!("Unsupported request: {command}")),
+    };
+
+    sender.respond(request, res)?;
+    if command == "launch" && self.interpreter.is_some() {
+      sender.event("initialized", json!({}))?;
+    }
+    Ok(true)
+  }
+
+  fn get_interpreter(&self) -> &Chip8 {
+    self.interpreter.as_ref().unwrap()
+  }
+
+  fn get_interpreter_mut(&mut self) -> &mut Chip8 {
+    self.interpreter.as_mut().unwrap()
+  }
+
+  fn launch(&mut self, args: &Value) -> Result<Value, String> {
+    let program = args["program"]
+      .as_str()
+      .ok_or("Missing \"program\" in launch arguments")?;
+    let image = fs::read(program).map_err(|err| format!("{program}: {err}"))?;
+    let mode = match args["mode"].as_str().unwrap_or("chip8") {
+      "chip8" => Mode::Chip8,
+      "schip" => Mode::SuperChip,
+      "xochip" => Mode::XoChip,
+      mode => return Err(format!("Unknown mode: {mode}")),
+    };
+    let quirks = match mode {
+      Mode::Chip8 => Quirks::COSMAC_VIP,
+      Mode::SuperChip => Quirks::SUPER_CHIP_1_1,
+      Mode::XoChip => Quirks::XO_CHIP,
+    };
+
+    if let Some(line_map) = args["lineMap"].as_str() {
+      let text = fs::read_to_string(line_map).map_err(|err| format!("{line_map}: {err}"))?;
+      self.source_lines = parse_line_map(&text).ok_or(format!("{line_map}: Invalid line map"))?;
+    }
+    self.source = args["source"].as_str().map(|path| {
+      let name = Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned());
+      json!({ "name": name, "path": path })
+    });
+    self.is_stop_on_entry = args["stopOnEntry"].as_bool().unwrap_or(false);
+
+    let platform = BasePlatform::new(rand::random as fn() -> u8);
+    let executable = BaseExecutable::new(&image, Address::new::<0x200>());
+    let interpreter = Interpreter::new(platform, executable, mode, Font::default(), quirks)
+      .map_err(|err| format!("{program}: {err}"))?;
+    self.interpreter = Some(ControlledInterpreter::new(
+      interpreter,
+      DEFAULT_INSTRUCTION_DURATION,
+      DEFAULT_DELAY_TIMER_DURATION,
+      DEFAULT_SOUND_TIMER_DURATION,
+    ));
+    Ok(json!({}))
+  }
+
+  /// First instruction at or after the source line.
+  fn resolve_line(&self, line: usize) -> Option<(Address, usize)> {
+    self
+      .source_lines
+      .iter()
+      .filter(|(_, &address_line)| address_line >= line)
+      .min_by_key(|(&address, &address_line)| (address_line, address))
+      .map(|(&address, &address_line)| (address, address_line))
+  }
+
+  fn set_breakpoints(&mut self, args: &Value) -> Result<Value, String> {
+    let requested = args["breakpoints"].as_array().cloned().unwrap_or_default();
+    let resolved = requested
+      .iter()
+      .map(|breakpoint| {
+        let line = breakpoint["line"].as_u64().unwrap_or_default() as usize;
+        (line, self.resolve_line(line))
+      })
+      .collect::<Vec<_>>();
+
+    self.line_breakpoints = resolved
+      .iter()
+      .filter_map(|(_, resolved)| resolved.map(|(address, _)| address))
+      .collect();
+    self.apply_breakpoints();
+
+    let breakpoints = resolved
+      .iter()
+      .map(|&(line, resolved)| match resolved {
+        Some((address, line)) => json!({
+          "verified": true,
+          "line": line,
+          "instructionReference": format_address(address),
+        }),
+        None => json!({
+          "verified": false,
+          "line": line,
+          "message": "No code at this line",
+        }),
+      })
+      .collect::<Vec<_>>();
+    Ok(json!({ "breakpoints": breakpoints }))
+  }
+
+  fn set_instruction_breakpoints(&mut self, args: &Value) -> Result<Value, String> {
+    let requested = args["breakpoints"].as_array().cloned().unwrap_or_default();
+    let resolved = requested
+      .iter()
+      .map(|breakpoint| {
+        let reference = breakpoint["instructionReference"].as_str()?;
+        let offset = breakpoint["offset"].as_i64().unwrap_or_default();
+        let address = parse_address(reference)? as i64 + offset;
+        u16::try_from(address).ok().map(Address::from)
+      })
+      .collect::<Vec<_>>();
+
+    self.instruction_breakpoints = resolved.iter().flatten().copied().collect();
+    self.apply_breakpoints();
+
+    let breakpoints = resolved
+      .iter()
+      .map(|address| match address {
+        Some(address) => json!({
+          "verified": true,
+          "instructionReference": format_address(*address),
+        }),
+        None => json!({ "verified": false, "message": "Invalid address" }),
+      })
+      .collect::<Vec<_>>();
+    Ok(json!({ "breakpoints": breakpoints }))
+  }
+
+  fn apply_breakpoints(&mut self) {
+    let mut addresses = [
+      self.line_breakpoints.as_slice(),
+      &self.instruction_breakpoints,
+    ]
+    .concat();
+    addresses.sort();
+    addresses.dedup();
+
+    let interpreter = self.get_interpreter_mut();
+    interpreter.clear_breakpoints();
+    addresses
+      .into_iter()
+      .for_each(|address| interpreter.add_breakpoint(Breakpoint::Address(address)));
+  }
+
+  fn create_frame(&self, id: usize, address: Address) -> Value {
+    let mut frame = json!({
+      "id": id,
+      "name": format_address(address),
+      "line": 0,
+      "column": 0,
+      "instructionPointerReference": format_address(address),
+    });
+    if let (Some(source), Some(line)) = (&self.source, self.source_lines.get(&address)) {
+      frame["source"] = source.clone();
+      frame["line"] = json!(line);
+      frame["column"] = json!(1);
+    }
+    frame
+  }
+
+  fn get_stack_trace(&self) -> Value {
+    let state = self.get_interpreter().get_state();
+    let call_addresses = state
+      .get_stack()
+      .iter()
+      .rev()
+      .map(|&return_address| return_address + -2);
+    let frames = std::iter::once(state.get_instruction_address())
+      .chain(call_addresses)
+      .enumerate()
+      .map(|(id, address)| self.create_frame(id, address))
+      .collect::<Vec<_>>();
+    json!({ "stackFrames": frames, "totalFrames": frames.len() })
+  }
+
+  fn get_variables(&self, args: &Value) -> Value {
+    let state = self.get_interpreter().get_state();
+    let variable = |name: String, value: String| json!({ "name": name, "value": value, "variablesReference": 0 });
+    let variables = match args["variablesReference"].as_u64() {
+      Some(REGISTERS_REFERENCE) => {
+        let mut variables = state
+          .get_registers()
+          .iter()
+          .enumerate()
+          .map(|(index, value)| variable(format!("v{index:X}"), format!("0x{value:02X}")))
+          .collect::<Vec<_>>();
+        let index_register = format_address(state.get_index_register());
+        variables.push(json!({
+          "name": "I",
+          "value": index_register,
+          "variablesReference": 0,
+          "memoryReference": index_register,
+        }));
+        variables.extend([
+          variable(
+            String::from("PC"),
+            format_address(state.get_instruction_address()),
+          ),
+          variable(
+            String::from("DT"),
+            format!("0x{:02X}", state.get_delay_timer()),
+          ),
+          variable(
+            String::from("ST"),
+            format!("0x{:02X}", state.get_sound_timer()),
+          ),
+        ]);
+        variables
+      }
+      Some(STACK_REFERENCE) => state
+        .get_stack()
+        .iter()
+        .enumerate()
+        .map(|(index, &address)| variable(format!("[{index}]"), format_address(address)))
+        .collect(),
+      _ => Vec::new(),
+    };
+    json!({ "variables": variables })
+  }
+
+  fn set_variable(&mut self, args: &Value) -> Result<Value, String> {
+    if args["variablesReference"].as_u64() != Some(REGISTERS_REFERENCE) {
+      return Err(String::from("Only registers can be changed"));
+    }
+    let name = args["name"].as_str().unwrap_or_default();
+    let value = evaluate(
+      args["value"].as_str().unwrap_or_default(),
+      &self.get_interpreter().get_state(),
+    )?;
+
+    let interpreter = self.get_interpreter_mut();
+    let formatted = match name {
+      "I" => {
+        interpreter
+          .get_interpreter_mut()
+          .set_index_register(Address::from(value as u16));
+        format_address(Address::from(value as u16))
+      }
+      "PC" => {
+        interpreter
+          .get_interpreter_mut()
+          .set_instruction_address(Address::from(value as u16));
+        format_address(Address::from(value as u16))
+      }
+      "DT" => {
+        interpreter.get_platform_mut().set_delay_timer(value as u8);
+        format!("0x{:02X}", value as u8)
+      }
+      "ST" => {
+        interpreter.get_platform_mut().set_sound_timer(value as u8);
+        format!("0x{:02X}", value as u8)
+      }
+      _ => {
+        let index = name
+          .strip_prefix('v')
+          .and_then(|index| u8::from_str_radix(index, 16).ok())
+          .and_then(|index| Nibble::try_from(index).ok())
+          .ok_or(format!("Unknown register: {name}"))?;
+        interpreter
+          .get_interpreter_mut()
+          .set_register(index, value as u8);
+        format!("0x{:02X}", value as u8)
+      }
+    };
+    Ok(json!({ "value": formatted }))
+  }
+
+  fn read_memory(&self, args: &Value) -> Result<Value, String> {
+    let reference = args["memoryReference"].as_str().unwrap_or_default();
+    let requested_start = parse_address(reference).ok_or(format!("Invalid address: {reference}"))?
+      as i128
+      + args["offset"].as_i64().unwrap_or_default() as i128;
+    let count = args["count"].as_u64().unwrap_or_default();
+
+    // Bytes before the start and past the end of the memory are both unreadable.
+    let memory = self.get_interpreter().get_state().get_memory();
+    let start = requested_start.clamp(0, memory.len() as i128);
+    let end = (requested_start + count as i128).clamp(start, memory.len() as i128);
+    Ok(json!({
+      "address": format!("0x{start:04X}"),
+      "data": encode_base64(&memory.as_slice()[start as usize..end as usize]),
+      "unreadableBytes": count - (end - start) as u64,
+    }))
+  }
+
+  fn evaluate(&self, args: &Value) -> Result<Value, String> {
+    let expression = args["expression"].as_str().unwrap_or_default();
+    let value = evaluate(expression, &self.get_interpreter().get_state())?;
+    Ok(json!({
+      "result": format!("0x{value:X} ({value})"),
+      "variablesReference": 0,
+    }))
+  }
+
+  fn resume(&mut self, run_mode: RunMode) {
+    self.run_mode = run_mode;
+    self.run_started = Some(Instant::now());
+    self.run_started_elapsed = self.get_interpreter().get_elapsed();
+  }
+
+  fn stop<W: Write>(
+    &mut self,
+    reason: &str,
+    text: Option<String>,
+    sender: &mut Sender<W>,
+  ) -> io::Result<()> {
+    self.run_mode = RunMode::Stopped;
+    self.run_started = None;
+    let mut body = json!({
+      "reason": reason,
+      "threadId": THREAD_ID,
+      "allThreadsStopped": true,
+    });
+    if let Some(text) = text {
+      body["text"] = json!(text);
+    }
+    sender.event("stopped", body)
+  }
+
+  fn is_step_finished(&self) -> bool {
+    let depth = self.get_interpreter().get_state().get_stack_depth();
+    match self.run_mode {
+      RunMode::Stopped | RunMode::StepIn => true,
+      RunMode::Continue => false,
+      RunMode::StepOver { depth: start_depth } => depth <= start_depth,
+      RunMode::StepOut { depth: start_depth } => depth < start_depth,
+    }
+  }
+
+  /// Runs the program for one frame or until it stops.
+  pub fn run_frame<W: Write>(&mut self, sender: &mut Sender<W>) -> io::Result<()> {
+    let interpreter = self.get_interpreter();
+    let frame_end = interpreter.get_elapsed() + interpreter.get_frame_duration();
+
+    while self.get_interpreter().get_elapsed() < frame_end {
+      let interpreter = self.get_interpreter_mut();
+      let executed_count = interpreter.get_state().get_executed_count();
+      match interpreter.simulate_one_instruction() {
+        Ok(StopReason::BreakpointHit(_)) => return self.stop("breakpoint", None, sender),
+        Ok(_) => {}
+        Err(err) => return self.stop("exception", Some(err.to_string()), sender),
+      }
+
+      let state = self.get_interpreter().get_state();
+      if state.is_exited() {
+        self.run_mode = RunMode::Stopped;
+        sender.event("exited", json!({ "exitCode": 0 }))?;
+        return sender.event("terminated", json!({}));
+      }
+      if state.get_executed_count() != executed_count && self.is_step_finished() {
+        return self.stop("step", None, sender);
+      }
+    }
+
+    if let (RunMode::Continue, Some(run_started)) = (self.run_mode, self.run_started) {
+      let simulated = self.get_interpreter().get_elapsed() - self.run_started_elapsed;
+      if let Some(ahead) = simulated.checked_sub(run_started.elapsed()) {
+        thread::sleep(ahead);
+      }
+    }
+    Ok(())
+  }
+}
+
+fn format_address(address: Address) -> String {
+  format!("0x{:04X}", address.as_u16())
+}
+
+fn parse_address(text: &str) -> Option<u16> {
+  match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+    Some(hex) => u16::from_str_radix(hex, 16).ok(),
+    None => text.parse().ok(),
+  }
+}
+
+/// Parses lines of `chip8-asm -l` output: a source line and an address separated by a tab.
+fn parse_line_map(text: &str) -> Option<BTreeMap<Address, usize>> {
+  text
+    .lines()
+    .filter(|line| !line.trim().is_empty())
+    .map(|line| {
+      let (line, address) = line.split_once('\t')?;
+      Some((Address::from(parse_address(address)?), line.parse().ok()?))
+    })
+    .collect()
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+  const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+  bytes
+    .chunks(3)
+    .flat_map(|chunk| {
+      let value = chunk
+        .iter()
+        .enumerate()
+        .fold(0u32, |value, (index, &byte)| {
+          value | (byte as u32) << (16 - 8 * index)
+        });
+      (0..4).map(move |index| match index <= chunk.len() {
+        true => ALPHABET[(value >> (18 - 6 * index) & 0x3F) as usize] as char,
+        false => '=',
+      })
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{env, process};
+
+  use serde_json::json;
+
+  use super::*;
+  use crate::protocol::read_message;
+
+  /// `v0 := 5`, a call of a subroutine at `0x208` and an endless loop after it.
+  const PROGRAM: [u8; 12] = [
+    0x60, 0x05, 0x22, 0x08, 0x70, 0x01, 0x12, 0x06, 0x71, 0x02, 0x00, 0xEE,
+  ];
+  const LINE_MAP: &str = "1\t0x200\n2\t0x202\n3\t0x204\n4\t0x206\n6\t0x208\n7\t0x20A\n";
+
+  fn send(session: &mut Session, command: &str, arguments: Value) -> Vec<Value> {
+    let request = json!({
+      "seq": 1,
+      "type": "request",
+      "command": command,
+      "arguments": arguments,
+    });
+    let mut output = Vec::new();
+    assert!(session
+      .handle(&request, &mut Sender::new(&mut output))
+      .unwrap());
+    read_messages(&output)
+  }
+
+  /// Runs frames until the program stops and returns the events sent meanwhile.
+  fn run(session: &mut Session) -> Vec<Value> {
+    let mut output = Vec::new();
+    let mut sender = Sender::new(&mut output);
+    while session.is_running() {
+      session.run_frame(&mut sender).unwrap();
+    }
+    read_messages(&output)
+  }
+
+  fn read_messages(mut output: &[u8]) -> Vec<Value> {
+    std::iter::from_fn(|| read_message(&mut output).unwrap()).collect()
+  }
+
+  fn launch(session: &mut Session, name: &str) {
+    let directory = env::temp_dir();
+    let program = directory.join(format!("chip8-dap-{name}-{}.ch8", process::id()));
+    let line_map = directory.join(format!("chip8-dap-{name}-{}.txt", process::id()));
+    fs::write(&program, PROGRAM).unwrap();
+    fs::write(&line_map, LINE_MAP).unwrap();
+
+    let messages = send(
+      session,
+      "launch",
+      json!({
+        "program": program,
+        "lineMap": line_map,
+        "source": "program.8o",
+        "stopOnEntry": true,
+      }),
+    );
+    fs::remove_file(program).unwrap();
+    fs::remove_file(line_map).unwrap();
+    assert_eq!(messages[0]["success"], true);
+    assert_eq!(messages[1]["event"], "initialized");
+  }
+
+  fn get_instruction_pointer(session: &mut Session) -> Value {
+    let messages = send(session, "stackTrace", json!({ "threadId": THREAD_ID }));
+    messages[0]["body"]["stackFrames"][0]["instructionPointerReference"].clone()
+  }
+
+  #[test]
+  fn test_initialize() {
+    let mut session = Session::new();
+    let messages = send(&mut session, "initialize", json!({}));
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0]["type"], "response");
+    assert_eq!(messages[0]["success"], true);
+    assert_eq!(messages[0]["body"]["supportsReadMemoryRequest"], true);
+
+    let messages = send(&mut session, "threads", json!({}));
+    assert_eq!(messages[0]["success"], false);
+    assert_eq!(messages[0]["message"], "No program is launched");
+  }
+
+  #[test]
+  fn test_stepping() {
+    let mut session = Session::new();
+    launch(&mut session, "stepping");
+
+    let messages = send(
+      &mut session,
+      "setBreakpoints",
+      json!({ "breakpoints": [{ "line": 5 }, { "line": 100 }] }),
+    );
+    assert_eq!(
+      messages[0]["body"]["breakpoints"],
+      json!([
+        { "verified": true, "line": 6, "instructionReference": "0x0208" },
+        { "verified": false, "line": 100, "message": "No code at this line" },
+      ])
+    );
+
+    let messages = send(&mut session, "configurationDone", json!({}));
+    assert_eq!(messages[1]["event"], "stopped");
+    assert_eq!(messages[1]["body"]["reason"], "entry");
+    assert!(!session.is_running());
+
+    send(&mut session, "stepIn", json!({ "threadId": THREAD_ID }));
+    assert_eq!(run(&mut session)[0]["body"]["reason"], "step");
+    assert_eq!(get_instruction_pointer(&mut session), "0x0202");
+
+    // Stepping over the call still stops at the breakpoint inside it.
+    send(&mut session, "next", json!({ "threadId": THREAD_ID }));
+    assert_eq!(run(&mut session)[0]["body"]["reason"], "breakpoint");
+    assert_eq!(get_instruction_pointer(&mut session), "0x0208");
+
+    send(&mut session, "stepIn", json!({ "threadId": THREAD_ID }));
+    assert_eq!(run(&mut session)[0]["body"]["reason"], "step");
+    assert_eq!(get_instruction_pointer(&mut session), "0x020A");
+
+    let messages = send(&mut session, "stackTrace", json!({ "threadId": THREAD_ID }));
+    let frames = &messages[0]["body"]["stackFrames"];
+    assert_eq!(frames[0]["line"], 7);
+    assert_eq!(frames[0]["source"]["name"], "program.8o");
+    assert_eq!(frames[1]["instructionPointerReference"], "0x0202");
+
+    send(&mut session, "next", json!({ "threadId": THREAD_ID }));
+    assert_eq!(run(&mut session)[0]["body"]["reason"], "step");
+    assert_eq!(get_instruction_pointer(&mut session), "0x0204");
+
+    let messages = send(&mut session, "evaluate", json!({ "expression": "v1" }));
+    assert_eq!(messages[0]["body"]["result"], "0x2 (2)");
+
+    let expression = "0x7FFFFFFFFFFFFFFF + v1";
+    let messages = send(
+      &mut session,
+      "evaluate",
+      json!({ "expression": expression }),
+    );
+    assert_eq!(messages[0]["success"], false);
+    assert_eq!(messages[0]["message"], "Overflow");
+  }
+
+  #[test]
+  fn test_read_memory() {
+    let mut session = Session::new();
+    launch(&mut session, "read-memory");
+    let mut read_memory =
+      |arguments| send(&mut session, "readMemory", arguments)[0]["body"].clone();
+
+    assert_eq!(
+      read_memory(json!({ "memoryReference": "0x200", "count": 4 })),
+      json!({ "address": "0x0200", "data": "YAUiCA==", "unreadableBytes": 0 })
+    );
+    assert_eq!(
+      read_memory(json!({ "memoryReference": "0x200", "offset": 8, "count": 2 })),
+      json!({ "address": "0x0208", "data": "cQI=", "unreadableBytes": 0 })
+    );
+    assert_eq!(
+      read_memory(json!({ "memoryReference": "0xFFE", "count": 4 })),
+      json!({ "address": "0x0FFE", "data": "AAA=", "unreadableBytes": 2 })
+    );
+    assert_eq!(
+      read_memory(json!({ "memoryReference": "0xFFE", "count": u64::MAX })),
+      json!({ "address": "0x0FFE", "data": "AAA=", "unreadableBytes": u64::MAX - 2 })
+    );
+    assert_eq!(
+      read_memory(json!({ "memoryReference": "0x10", "offset": -0x20, "count": 1 })),
+      json!({ "address": "0x0000", "data": "", "unreadableBytes": 1 })
+    );
+    assert_eq!(
+      read_memory(json!({ "memoryReference": "0x10", "offset": -0x20, "count": 0x12 })),
+      json!({ "address": "0x0000", "data": "AAA=", "unreadableBytes": 0x10 })
+    );
+  }
+}
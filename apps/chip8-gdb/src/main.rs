@@ -0,0 +1,92 @@
+mod server;
+
+use std::{env, fs, path::PathBuf, process::ExitCode};
+
+use chip8_interpreter::{
+  Address, BaseExecutable, BasePlatform, ControlledInterpreter, Font, Interpreter, Mode, Quirks,
+  DEFAULT_DELAY_TIMER_DURATION, DEFAULT_INSTRUCTION_DURATION, DEFAULT_SOUND_TIMER_DURATION,
+};
+use server::GdbServer;
+
+const USAGE: &str = "Usage: chip8-gdb <image.ch8> [-p <port>] [-m chip8|schip|xochip]";
+const DEFAULT_PORT: u16 = 1234;
+
+struct Args {
+  image_path: PathBuf,
+  port: u16,
+  mode: Mode,
+}
+
+fn parse_args() -> Result<Args, String> {
+  let mut image_path = None;
+  let mut port = DEFAULT_PORT;
+  let mut mode = Mode::Chip8;
+
+  let mut args = env::args().skip(1);
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "-p" => {
+        let value = args.next().ok_or(USAGE)?;
+        port = value
+          .parse()
+          .map_err(|_| format!("Invalid port: {value}"))?;
+      }
+      "-m" => {
+        mode = match args.next().ok_or(USAGE)?.as_str() {
+          "chip8" => Mode::Chip8,
+          "schip" => Mode::SuperChip,
+          "xochip" => Mode::XoChip,
+          value => return Err(format!("Unknown mode: {value}\n{USAGE}")),
+        }
+      }
+      "-h" | "--help" => return Err(String::from(USAGE)),
+      _ if image_path.is_none() && !arg.starts_with('-') => image_path = Some(PathBuf::from(arg)),
+      _ => return Err(format!("Unexpected argument: {arg}\n{USAGE}")),
+    }
+  }
+
+  Ok(Args {
+    image_path: image_path.ok_or(USAGE)?,
+    port,
+    mode,
+  })
+}
+
+fn run(args: &Args) -> Result<(), String> {
+  let image =
+    fs::read(&args.image_path).map_err(|err| format!("{}: {err}", args.image_path.display()))?;
+  let quirks = match args.mode {
+    Mode::Chip8 => Quirks::COSMAC_VIP,
+    Mode::SuperChip => Quirks::SUPER_CHIP_1_1,
+    Mode::XoChip => Quirks::XO_CHIP,
+  };
+
+  let platform = BasePlatform::new(rand::random as fn() -> u8);
+  let executable = BaseExecutable::new(&image, Address::new::<0x200>());
+  let interpreter = Interpreter::new(platform, executable, args.mode, Font::default(), quirks)
+    .map_err(|err| format!("{}: {err}", args.image_path.display()))?;
+  let mut interpreter = ControlledInterpreter::new(
+    interpreter,
+    DEFAULT_INSTRUCTION_DURATION,
+    DEFAULT_DELAY_TIMER_DURATION,
+    DEFAULT_SOUND_TIMER_DURATION,
+  );
+
+  let server = GdbServer::bind(("127.0.0.1", args.port)).map_err(|err| err.to_string())?;
+  let address = server.get_local_address().map_err(|err| err.to_string())?;
+  eprintln!("Waiting for GDB on {address}");
+  server
+    .serve(&mut interpreter)
+    .map_err(|err| err.to_string())
+}
+
+fn main() -> ExitCode {
+  let res = parse_args().and_then(|args| run(&args));
+  match res {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(message) => {
+      eprintln!("{message}");
+      ExitCode::FAILURE
+    }
+  }
+}
@@ -0,0 +1,734 @@
+//! GDB remote serial protocol server.
+//!
+//! Registers are numbered as in [`GDB_TARGET_XML`]: `v0`..`vF` are `0`..`15`, then `I`, `PC`,
+//! the delay timer and the sound timer. Register values are sent in little endian order.
+//! Memory packets address the whole memory of the machine. Software breakpoints (`Z0`) map
+//! onto [`Breakpoint::Address`] and write watchpoints (`Z2`) onto [`Watchpoint::Memory`], `s`
+//! runs one instruction and `c` runs until a breakpoint, a watchpoint, an error or an interrupt
+//! from the client. `?` repeats the reply of the last stop: `SIGILL` for an unknown opcode and
+//! `SIGSEGV` for the other crashes.
+
+use std::{
+  collections::VecDeque,
+  io::{self, BufRead, BufReader, Read, Write},
+  net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+};
+
+use chip8_interpreter::{
+  Address, Breakpoint, ControlledInterpreter, InterpreterError, Nibble, Platform, StopReason,
+  Watchpoint,
+};
+
+pub const GDB_TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.chip8.core">
+    <reg name="v0" bitsize="8" type="uint8" regnum="0"/>
+    <reg name="v1" bitsize="8" type="uint8"/>
+    <reg name="v2" bitsize="8" type="uint8"/>
+    <reg name="v3" bitsize="8" type="uint8"/>
+    <reg name="v4" bitsize="8" type="uint8"/>
+    <reg name="v5" bitsize="8" type="uint8"/>
+    <reg name="v6" bitsize="8" type="uint8"/>
+    <reg name="v7" bitsize="8" type="uint8"/>
+    <reg name="v8" bitsize="8" type="uint8"/>
+    <reg name="v9" bitsize="8" type="uint8"/>
+    <reg name="va" bitsize="8" type="uint8"/>
+    <reg name="vb" bitsize="8" type="uint8"/>
+    <reg name="vc" bitsize="8" type="uint8"/>
+    <reg name="vd" bitsize="8" type="uint8"/>
+    <reg name="ve" bitsize="8" type="uint8"/>
+    <reg name="vf" bitsize="8" type="uint8"/>
+    <reg name="i" bitsize="16" type="data_ptr"/>
+    <reg name="pc" bitsize="16" type="code_ptr"/>
+    <reg name="dt" bitsize="8" type="uint8"/>
+    <reg name="st" bitsize="8" type="uint8"/>
+  </feature>
+</target>
+"#;
+
+const INDEX_REGISTER: usize = Nibble::SIZE;
+const INSTRUCTION_ADDRESS_REGISTER: usize = Nibble::SIZE + 1;
+const DELAY_TIMER_REGISTER: usize = Nibble::SIZE + 2;
+const SOUND_TIMER_REGISTER: usize = Nibble::SIZE + 3;
+const REGISTERS_COUNT: usize = Nibble::SIZE + 4;
+
+const INTERRUPT: u8 = 0x03;
+const MAX_PACKET_SIZE: usize = 0x1000;
+
+const SIGINT: u8 = 0x02;
+const SIGILL: u8 = 0x04;
+const SIGTRAP: u8 = 0x05;
+const SIGABRT: u8 = 0x06;
+const SIGSEGV: u8 = 0x0B;
+
+/// Listens for GDB clients and serves one session at a time.
+pub struct GdbServer {
+  listener: TcpListener,
+}
+
+impl GdbServer {
+  pub fn bind(address: impl ToSocketAddrs) -> io::Result<Self> {
+    Ok(Self {
+      listener: TcpListener::bind(address)?,
+    })
+  }
+
+  pub fn get_local_address(&self) -> io::Result<SocketAddr> {
+    self.listener.local_addr()
+  }
+
+  /// Waits for a client and serves it until it detaches, kills the target or disconnects.
+  pub fn serve<P: Platform>(&self, interpreter: &mut ControlledInterpreter<P>) -> io::Result<()> {
+    let (stream, _) = self.listener.accept()?;
+    GdbSession::new(stream, interpreter)?.run()
+  }
+}
+
+enum Reply {
+  Packet(String),
+  Close(Option<String>),
+}
+
+struct GdbSession<'a, P: Platform> {
+  reader: BufReader<TcpStream>,
+  writer: TcpStream,
+  /// Bytes that arrived while the target was running and are not interrupts.
+  pending: VecDeque<u8>,
+  interpreter: &'a mut ControlledInterpreter<P>,
+  is_ack_enabled: bool,
+  stop_reply: String,
+}
+
+impl<'a, P: Platform> GdbSession<'a, P> {
+  fn new(stream: TcpStream, interpreter: &'a mut ControlledInterpreter<P>) -> io::Result<Self> {
+    stream.set_nodelay(true)?;
+    Ok(Self {
+      reader: BufReader::new(stream.try_clone()?),
+      writer: stream,
+      pending: VecDeque::new(),
+      interpreter,
+      is_ack_enabled: true,
+      stop_reply: format!("S{SIGTRAP:02x}"),
+    })
+  }
+
+  fn run(&mut self) -> io::Result<()> {
+    while let Some(packet) = self.read_packet()? {
+      match self.process_packet(&packet)? {
+        Reply::Packet(reply) => self.write_packet(&reply)?,
+        Reply::Close(reply) => {
+          if let Some(reply) = reply {
+            self.write_packet(&reply)?;
+          }
+          return Ok(());
+        }
+      }
+    }
+    Ok(())
+  }
+
+  fn read_byte(&mut self) -> io::Result<Option<u8>> {
+    if let Some(byte) = self.pending.pop_front() {
+      return Ok(Some(byte));
+    }
+    let mut byte = [0];
+    match self.reader.read(&mut byte)? {
+      0 => Ok(None),
+      _ => Ok(Some(byte[0])),
+    }
+  }
+
+  /// Reads the next packet, skipping acknowledgements and interrupts between packets.
+  fn read_packet(&mut self) -> io::Result<Option<String>> {
+    loop {
+      match self.read_byte()? {
+        None => return Ok(None),
+        Some(b'$') => {}
+        Some(_) => continue,
+      }
+
+      let mut data = Vec::new();
+      loop {
+        match self.read_byte()? {
+          None => return Ok(None),
+          Some(b'#') => break,
+          Some(byte) if data.len() < MAX_PACKET_SIZE => data.push(byte),
+          Some(_) => {}
+        }
+      }
+      let mut checksum = [0; 2];
+      for digit in &mut checksum {
+        match self.read_byte()? {
+          None => return Ok(None),
+          Some(byte) => *digit = byte,
+        }
+      }
+
+      let is_valid = std::str::from_utf8(&checksum)
+        .ok()
+        .and_then(|checksum| u8::from_str_radix(checksum, 16).ok())
+        == Some(calculate_checksum(&data));
+      if self.is_ack_enabled {
+        self.writer.write_all(match is_valid {
+          true => b"+",
+          false => b"-",
+        })?;
+      }
+      if is_valid {
+        return Ok(Some(String::from_utf8_lossy(&data).into_owned()));
+      }
+    }
+  }
+
+  fn write_packet(&mut self, data: &str) -> io::Result<()> {
+    let packet = format!("${data}#{:02x}", calculate_checksum(data.as_bytes()));
+    loop {
+      self.writer.write_all(packet.as_bytes())?;
+      if !self.is_ack_enabled {
+        return Ok(());
+      }
+      match self.read_byte()? {
+        Some(b'-') => continue,
+        _ => return Ok(()),
+      }
+    }
+  }
+
+  fn process_packet(&mut self, packet: &str) -> io::Result<Reply> {
+    let (command, args) = packet.split_at(packet.len().min(1));
+    let reply = match command {
+      "?" => self
+        .check_finished()
+        .unwrap_or_else(|| self.stop_reply.clone()),
+      "g" => self.read_registers(),
+      "G" => self.write_registers(args),
+      "p" => self.read_register(args),
+      "P" => self.write_register(args),
+      "m" => self.read_memory(args),
+      "M" => self.write_memory(args),
+      "Z" => self.change_breakpoint(args, true),
+      "z" => self.change_breakpoint(args, false),
+      "s" => {
+        self.stop_reply = self.step()?;
+        self.stop_reply.clone()
+      }
+      "c" => {
+        self.stop_reply = self.resume()?;
+        self.stop_reply.clone()
+      }
+      "H" => String::from("OK"),
+      "D" => return Ok(Reply::Close(Some(String::from("OK")))),
+      "k" => return Ok(Reply::Close(None)),
+      "q" | "Q" => self.process_query(packet),
+      _ => String::new(),
+    };
+    Ok(Reply::Packet(reply))
+  }
+
+  fn process_query(&mut self, packet: &str) -> String {
+    if packet.starts_with("qSupported") {
+      return format!(
+        "PacketSize={MAX_PACKET_SIZE:x};qXfer:features:read+;swbreak+;QStartNoAckMode+"
+      );
+    }
+    if let Some(args) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+      return read_chunk(GDB_TARGET_XML, args);
+    }
+    match packet {
+      "QStartNoAckMode" => {
+        self.is_ack_enabled = false;
+        String::from("OK")
+      }
+      "qAttached" => String::from("1"),
+      "qC" => String::from("QC1"),
+      "qfThreadInfo" => String::from("m1"),
+      "qsThreadInfo" => String::from("l"),
+      _ => String::new(),
+    }
+  }
+
+  fn get_register_value(&self, index: usize) -> Vec<u8> {
+    let state = self.interpreter.get_state();
+    match index {
+      INDEX_REGISTER => state.get_index_register().as_u16().to_le_bytes().to_vec(),
+      INSTRUCTION_ADDRESS_REGISTER => state
+        .get_instruction_address()
+        .as_u16()
+        .to_le_bytes()
+        .to_vec(),
+      DELAY_TIMER_REGISTER => vec![state.get_delay_timer()],
+      SOUND_TIMER_REGISTER => vec![state.get_sound_timer()],
+      _ => vec![state.get_registers()[index]],
+    }
+  }
+
+  fn get_register_size(index: usize) -> usize {
+    match index {
+      INDEX_REGISTER | INSTRUCTION_ADDRESS_REGISTER => 2,
+      _ => 1,
+    }
+  }
+
+  fn set_register_value(&mut self, index: usize, bytes: &[u8]) {
+    let word = || Address::from(u16::from_le_bytes([bytes[0], bytes[1]]));
+    match index {
+      INDEX_REGISTER => self
+        .interpreter
+        .get_interpreter_mut()
+        .set_index_register(word()),
+      INSTRUCTION_ADDRESS_REGISTER => self
+        .interpreter
+        .get_interpreter_mut()
+        .set_instruction_address(word()),
+      DELAY_TIMER_REGISTER => self
+        .interpreter
+        .get_platform_mut()
+        .set_delay_timer(bytes[0]),
+      SOUND_TIMER_REGISTER => self
+        .interpreter
+        .get_platform_mut()
+        .set_sound_timer(bytes[0]),
+      _ => self
+        .interpreter
+        .get_interpreter_mut()
+        .set_register(Nibble::try_from(index as u8).unwrap(), bytes[0]),
+    }
+  }
+
+  fn read_registers(&self) -> String {
+    let bytes = (0..REGISTERS_COUNT)
+      .flat_map(|index| self.get_register_value(index))
+      .collect::<Vec<_>>();
+    encode_hex(&bytes)
+  }
+
+  fn write_registers(&mut self, args: &str) -> String {
+    let Some(bytes) = decode_hex(args) else {
+      return error_reply();
+    };
+    let size = (0..REGISTERS_COUNT).map(Self::get_register_size).sum();
+    if bytes.len() != size {
+      return error_reply();
+    }
+
+    let mut offset = 0;
+    for index in 0..REGISTERS_COUNT {
+      let size = Self::get_register_size(index);
+      self.set_register_value(index, &bytes[offset..offset + size]);
+      offset += size;
+    }
+    String::from("OK")
+  }
+
+  fn read_register(&self, args: &str) -> String {
+    match usize::from_str_radix(args, 16) {
+      Ok(index) if index < REGISTERS_COUNT => encode_hex(&self.get_register_value(index)),
+      _ => error_reply(),
+    }
+  }
+
+  fn write_register(&mut self, args: &str) -> String {
+    let Some((index, value)) = args.split_once('=') else {
+      return error_reply();
+    };
+    let index = usize::from_str_radix(index, 16).ok();
+    match (index, decode_hex(value)) {
+      (Some(index), Some(bytes))
+        if index < REGISTERS_COUNT && bytes.len() == Self::get_register_size(index) =>
+      {
+        self.set_register_value(index, &bytes);
+        String::from("OK")
+      }
+      _ => error_reply(),
+    }
+  }
+
+  fn read_memory(&self, args: &str) -> String {
+    let state = self.interpreter.get_state();
+    parse_memory_range(args)
+      .and_then(|(start, len)| state.get_memory_slice(start, len))
+      .map_or_else(error_reply, encode_hex)
+  }
+
+  fn write_memory(&mut self, args: &str) -> String {
+    let Some((range, data)) = args.split_once(':') else {
+      return error_reply();
+    };
+    let state = self.interpreter.get_state();
+    match (parse_memory_range(range), decode_hex(data)) {
+      (Some((start, len)), Some(bytes))
+        if bytes.len() == len && state.get_memory_slice(start, len).is_some() =>
+      {
+        self
+          .interpreter
+          .get_interpreter_mut()
+          .write_memory(start, &bytes);
+        String::from("OK")
+      }
+      _ => error_reply(),
+    }
+  }
+
+  fn change_breakpoint(&mut self, args: &str, is_insert: bool) -> String {
+    let Some((kind, range)) = args.split_once(',') else {
+      return error_reply();
+    };
+    if !matches!(kind, "0" | "1" | "2") {
+      return String::new();
+    }
+    let Some((address, len)) = parse_memory_range(range) else {
+      return error_reply();
+    };
+
+    match kind {
+      "2" => self.change_watchpoint(
+        Watchpoint::Memory {
+          start: address,
+          len,
+        },
+        is_insert,
+      ),
+      _ => {
+        let breakpoint = Breakpoint::Address(address);
+        match is_insert {
+          true => {
+            if !self.interpreter.get_breakpoints().contains(&breakpoint) {
+              self.interpreter.add_breakpoint(breakpoint);
+            }
+          }
+          false => {
+            self.interpreter.remove_breakpoint(breakpoint);
+          }
+        }
+      }
+    }
+    String::from("OK")
+  }
+
+  fn change_watchpoint(&mut self, watchpoint: Watchpoint, is_insert: bool) {
+    match is_insert {
+      true => {
+        if !self.interpreter.get_watchpoints().contains(&watchpoint) {
+          self.interpreter.add_watchpoint(watchpoint);
+        }
+      }
+      false => {
+        self.interpreter.remove_watchpoint(watchpoint);
+      }
+    }
+  }
+
+  fn step(&mut self) -> io::Result<String> {
+    let executed_count = self.interpreter.get_state().get_executed_count();
+    while self.interpreter.get_state().get_executed_count() == executed_count {
+      if let Some(reply) = self.check_finished() {
+        return Ok(reply);
+      }
+      if self.is_interrupted()? {
+        return Ok(format!("S{SIGINT:02x}"));
+      }
+      if let Err(err) = self.interpreter.simulate_one_instruction() {
+        return Ok(format!("S{:02x}", get_crash_signal(&err)));
+      }
+    }
+    Ok(format!("S{SIGTRAP:02x}"))
+  }
+
+  fn resume(&mut self) -> io::Result<String> {
+    loop {
+      if let Some(reply) = self.check_finished() {
+        return Ok(reply);
+      }
+      if self.is_interrupted()? {
+        return Ok(format!("S{SIGINT:02x}"));
+      }
+      let frame_duration = self.interpreter.get_frame_duration();
+      match self.interpreter.simulate_duration(frame_duration) {
+        Ok(StopReason::TimeElapsed) => {}
+        Ok(StopReason::BreakpointHit(_)) => return Ok(format!("T{SIGTRAP:02x}swbreak:;")),
+        Ok(StopReason::WatchpointHit(Watchpoint::Memory { start, .. })) => {
+          return Ok(format!("T{SIGTRAP:02x}watch:{:x};", start.as_u16()))
+        }
+        Ok(StopReason::WatchpointHit(_)) => return Ok(format!("S{SIGTRAP:02x}")),
+        Err(err) => return Ok(format!("S{:02x}", get_crash_signal(&err))),
+      }
+    }
+  }
+
+  fn check_finished(&self) -> Option<String> {
+    let state = self.interpreter.get_state();
+    let crash = self.interpreter.get_interpreter().get_crash();
+    match (state.is_exited(), crash) {
+      (true, _) => Some(String::from("W00")),
+      (_, Some(err)) => Some(format!("S{:02x}", get_crash_signal(err))),
+      _ => None,
+    }
+  }
+
+  /// Checks without blocking whether the client sent an interrupt. Other bytes that arrived
+  /// meanwhile are kept for the packet reader.
+  fn is_interrupted(&mut self) -> io::Result<bool> {
+    let buffered = self.reader.buffer().len();
+    self.pending.extend(self.reader.buffer());
+    self.reader.consume(buffered);
+
+    if buffered == 0 {
+      self.writer.set_nonblocking(true)?;
+      let mut bytes = [0; MAX_PACKET_SIZE];
+      let res = self.writer.read(&mut bytes);
+      self.writer.set_nonblocking(false)?;
+      match res {
+        Ok(0) => return Ok(true),
+        Ok(len) => self.pending.extend(&bytes[..len]),
+        Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+        Err(err) => return Err(err),
+      }
+    }
+
+    match self.pending.iter().position(|&byte| byte == INTERRUPT) {
+      Some(index) => {
+        self.pending.remove(index);
+        Ok(true)
+      }
+      None => Ok(false),
+    }
+  }
+}
+
+fn get_crash_signal(err: &InterpreterError) -> u8 {
+  match err {
+    InterpreterError::Crashed(cause) | InterpreterError::Fault { cause, .. } => {
+      get_crash_signal(cause)
+    }
+    InterpreterError::UnknownOpCode(_) | InterpreterError::NibbleOverflow => SIGILL,
+    InterpreterError::StackOverflow
+    | InterpreterError::StackUnderflow
+    | InterpreterError::AddressOverflow(_) => SIGSEGV,
+    InterpreterError::UnknownCause => SIGABRT,
+  }
+}
+
+fn calculate_checksum(data: &[u8]) -> u8 {
+  data.iter().fold(0, |sum, &byte| sum.wrapping_add(byte))
+}
+
+fn error_reply() -> String {
+  String::from("E01")
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+  if !text.len().is_multiple_of(2) {
+    return None;
+  }
+  (0..text.len())
+    .step_by(2)
+    .map(|index| u8::from_str_radix(text.get(index..index + 2)?, 16).ok())
+    .collect()
+}
+
+fn parse_memory_range(args: &str) -> Option<(Address, usize)> {
+  let (start, len) = args.split_once(',')?;
+  Some((
+    Address::from(u16::from_str_radix(start, 16).ok()?),
+    usize::from_str_radix(len, 16).ok()?,
+  ))
+}
+
+/// Answers a `qXfer` read of `offset,length` from `document`.
+fn read_chunk(document: &str, args: &str) -> String {
+  let Some((offset, len)) = args.split_once(',') else {
+    return error_reply();
+  };
+  let (Ok(offset), Ok(len)) = (
+    usize::from_str_radix(offset, 16),
+    usize::from_str_radix(len, 16),
+  ) else {
+    return error_reply();
+  };
+
+  let chunk = document
+    .get(offset.min(document.len())..)
+    .unwrap_or_default();
+  match chunk.len() > len {
+    true => format!("m{}", &chunk[..len]),
+    false => format!("l{chunk}"),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::thread;
+
+  use chip8_interpreter::{
+    BaseExecutable, BasePlatform, Font, Instruction, Interpreter, Mode, Quirks,
+    DEFAULT_DELAY_TIMER_DURATION, DEFAULT_INSTRUCTION_DURATION, DEFAULT_SOUND_TIMER_DURATION,
+  };
+
+  use super::*;
+
+  fn create_interpreter(
+    instructions: &[Instruction],
+  ) -> ControlledInterpreter<BasePlatform<fn() -> u8>> {
+    let image = instructions
+      .iter()
+      .flat_map(|instruction| instruction.encode().unwrap())
+      .collect::<Vec<_>>();
+    let platform = BasePlatform::new((|| 0) as fn() -> u8);
+    let executable = BaseExecutable::new(&image, Address::new::<0x200>());
+    let interpreter = Interpreter::new(
+      platform,
+      executable,
+      Mode::Chip8,
+      Font::default(),
+      Quirks::COSMAC_VIP,
+    )
+    .unwrap();
+    ControlledInterpreter::new(
+      interpreter,
+      DEFAULT_INSTRUCTION_DURATION,
+      DEFAULT_DELAY_TIMER_DURATION,
+      DEFAULT_SOUND_TIMER_DURATION,
+    )
+  }
+
+  fn write_gdb_packet(stream: &mut TcpStream, packet: &str) {
+    write!(
+      stream,
+      "${packet}#{:02x}",
+      calculate_checksum(packet.as_bytes())
+    )
+    .unwrap();
+  }
+
+  fn read_gdb_packet(stream: &mut TcpStream) -> String {
+    let mut reply = Vec::new();
+    let mut byte = [0];
+    loop {
+      stream.read_exact(&mut byte).unwrap();
+      match byte[0] {
+        b'$' => {}
+        b'#' => break,
+        _ => reply.push(byte[0]),
+      }
+    }
+    stream.read_exact(&mut [0; 2]).unwrap();
+    String::from_utf8(reply).unwrap()
+  }
+
+  fn send_gdb_packet(stream: &mut TcpStream, packet: &str) -> String {
+    write_gdb_packet(stream, packet);
+    let mut ack = [0];
+    stream.read_exact(&mut ack).unwrap();
+    assert_eq!(ack[0], b'+');
+    let reply = read_gdb_packet(stream);
+    stream.write_all(b"+").unwrap();
+    reply
+  }
+
+  #[test]
+  fn test_gdb_server() {
+    let v0 = Nibble::new::<0>();
+    let mut interpreter = create_interpreter(&[
+      Instruction::SetRegister(v0, 5),
+      Instruction::AddRegister(v0, 1),
+      Instruction::Jump(Address::new::<0x202>()),
+    ]);
+
+    let server = GdbServer::bind("127.0.0.1:0").unwrap();
+    let address = server.get_local_address().unwrap();
+    let client = thread::spawn(move || {
+      let mut stream = TcpStream::connect(address).unwrap();
+      stream.set_nodelay(true).unwrap();
+      let mut send = |packet: &str| send_gdb_packet(&mut stream, packet);
+
+      assert!(send("qSupported:swbreak+").contains("qXfer:features:read+"));
+      assert_eq!(
+        send("qXfer:features:read:target.xml:0,fff"),
+        format!("l{GDB_TARGET_XML}")
+      );
+      assert_eq!(send("?"), "S05");
+      assert_eq!(send("g"), format!("{}000000020000", "00".repeat(16)));
+
+      assert_eq!(send("s"), "S05");
+      assert_eq!(send("p11"), "0202");
+      assert_eq!(send("p0"), "05");
+
+      assert_eq!(send("Z0,204,2"), "OK");
+      assert_eq!(send("c"), "T05swbreak:;");
+      assert_eq!(send("p11"), "0402");
+      assert_eq!(send("p0"), "06");
+      assert_eq!(send("c"), "T05swbreak:;");
+      assert_eq!(send("p0"), "07");
+
+      assert_eq!(send("P0=2a"), "OK");
+      assert_eq!(send("p0"), "2a");
+      assert_eq!(send("p10"), "0000");
+      assert_eq!(send("p14"), "E01");
+
+      assert_eq!(send("m200,4"), "60057001");
+      assert_eq!(send("M300,2:abcd"), "OK");
+      assert_eq!(send("m300,2"), "abcd");
+      assert_eq!(send("mfff,2"), "E01");
+
+      assert_eq!(send("z0,204,2"), "OK");
+      assert_eq!(send("D"), "OK");
+    });
+
+    server.serve(&mut interpreter).unwrap();
+    client.join().unwrap();
+
+    let state = interpreter.get_state();
+    assert!(interpreter.get_breakpoints().is_empty());
+    assert_eq!(state.get_register(v0), 0x2A);
+    assert_eq!(
+      state.get_memory_slice(Address::new::<0x300>(), 2),
+      Some(&[0xAB, 0xCD][..])
+    );
+  }
+
+  #[test]
+  fn test_gdb_stop_reply() {
+    let v0 = Nibble::new::<0>();
+    let mut interpreter = create_interpreter(&[
+      Instruction::SetRegister(v0, 5),
+      Instruction::SetAddressRegister(Address::new::<0x300>()),
+      Instruction::WriteRegistersToMem(v0),
+      Instruction::Return,
+    ]);
+
+    let server = GdbServer::bind("127.0.0.1:0").unwrap();
+    let address = server.get_local_address().unwrap();
+    let client = thread::spawn(move || {
+      let mut stream = TcpStream::connect(address).unwrap();
+      stream.set_nodelay(true).unwrap();
+
+      assert_eq!(send_gdb_packet(&mut stream, "Z2,300,1"), "OK");
+      assert_eq!(send_gdb_packet(&mut stream, "c"), "T05watch:300;");
+      assert_eq!(send_gdb_packet(&mut stream, "?"), "T05watch:300;");
+      assert_eq!(send_gdb_packet(&mut stream, "z2,300,1"), "OK");
+      assert_eq!(send_gdb_packet(&mut stream, "QStartNoAckMode"), "OK");
+
+      // The packet sent while the target runs is answered after the stop reply.
+      write_gdb_packet(&mut stream, "c");
+      write_gdb_packet(&mut stream, "p0");
+      assert_eq!(read_gdb_packet(&mut stream), "S0b");
+      assert_eq!(read_gdb_packet(&mut stream), "05");
+
+      write_gdb_packet(&mut stream, "?");
+      assert_eq!(read_gdb_packet(&mut stream), "S0b");
+      write_gdb_packet(&mut stream, "D");
+      assert_eq!(read_gdb_packet(&mut stream), "OK");
+    });
+
+    server.serve(&mut interpreter).unwrap();
+    client.join().unwrap();
+
+    assert!(interpreter.get_state().is_crashed());
+    assert!(interpreter.get_watchpoints().is_empty());
+  }
+}
@@ -0,0 +1,170 @@
+use std::time::{Duration, Instant};
+
+use chip8_interpreter::Nibble;
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+/// QWERTY keys of the hex keys `0`..`F`, laid out as the COSMAC VIP keypad:
+///
+/// ```text
+/// 1 2 3 4      1 2 3 C
+/// q w e r      4 5 6 D
+/// a s d f  ->  7 8 9 E
+/// z x c v      A 0 B F
+/// ```
+pub const DEFAULT_KEYMAP: &str = "x123qweasdzc4rfv";
+
+/// Terminals without key release events repeat a held key, so a key is released when it was
+/// not repeated for this long.
+const KEY_HOLD_DURATION: Duration = Duration::from_millis(500);
+
+pub fn parse_keymap(text: &str) -> Option<[char; Nibble::SIZE]> {
+  let chars = text.chars().map(|char| char.to_ascii_lowercase());
+  chars.collect::<Vec<_>>().try_into().ok()
+}
+
+/// `true` for Esc and Ctrl+C.
+pub fn is_quit_event(event: &KeyEvent) -> bool {
+  let is_ctrl_c =
+    event.code == KeyCode::Char('c') && event.modifiers.contains(KeyModifiers::CONTROL);
+  event.code == KeyCode::Esc || is_ctrl_c
+}
+
+/// Tracks which hex keys are held down.
+pub struct KeyTracker {
+  keymap: [char; Nibble::SIZE],
+  has_release_events: bool,
+  pressed_at: [Option<Instant>; Nibble::SIZE],
+}
+
+impl KeyTracker {
+  pub fn new(keymap: [char; Nibble::SIZE], has_release_events: bool) -> Self {
+    Self {
+      keymap,
+      has_release_events,
+      pressed_at: [None; Nibble::SIZE],
+    }
+  }
+
+  /// Returns the hex key and its new state if the event that came at `now` changes it.
+  pub fn process_event(&mut self, event: KeyEvent, now: Instant) -> Option<(Nibble, bool)> {
+    let KeyCode::Char(char) = event.code else {
+      return None;
+    };
+    let index = self
+      .keymap
+      .iter()
+      .position(|&key| key == char.to_ascii_lowercase())?;
+    let key = Nibble::try_from(index as u8).unwrap();
+
+    let was_down = self.pressed_at[index].is_some();
+    match event.kind {
+      KeyEventKind::Release => {
+        self.pressed_at[index] = None;
+        was_down.then_some((key, false))
+      }
+      KeyEventKind::Press | KeyEventKind::Repeat => {
+        self.pressed_at[index] = Some(now);
+        (!was_down).then_some((key, true))
+      }
+    }
+  }
+
+  /// Releases keys that were not repeated in time on terminals without key release events.
+  pub fn release_expired(&mut self, now: Instant) -> Vec<Nibble> {
+    if self.has_release_events {
+      return Vec::new();
+    }
+
+    (0..Nibble::SIZE)
+      .filter(|&index| {
+        let is_expired = self.pressed_at[index]
+          .is_some_and(|pressed_at| now.duration_since(pressed_at) >= KEY_HOLD_DURATION);
+        if is_expired {
+          self.pressed_at[index] = None;
+        }
+        is_expired
+      })
+      .map(|index| Nibble::try_from(index as u8).unwrap())
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn create_event(char: char, kind: KeyEventKind) -> KeyEvent {
+    KeyEvent::new_with_kind(KeyCode::Char(char), KeyModifiers::NONE, kind)
+  }
+
+  #[test]
+  fn test_parse_keymap() {
+    let keymap = parse_keymap(DEFAULT_KEYMAP).unwrap();
+    assert_eq!(keymap[0x0], 'x');
+    assert_eq!(keymap[0x1], '1');
+    assert_eq!(keymap[0xC], '4');
+    assert_eq!(keymap[0xF], 'v');
+    assert_eq!(parse_keymap("X123QWEASDZC4RFV"), Some(keymap));
+    assert_eq!(parse_keymap("x123"), None);
+  }
+
+  #[test]
+  fn test_quit_event() {
+    assert!(is_quit_event(&KeyEvent::from(KeyCode::Esc)));
+    assert!(is_quit_event(&KeyEvent::new(
+      KeyCode::Char('c'),
+      KeyModifiers::CONTROL
+    )));
+    assert!(!is_quit_event(&create_event('c', KeyEventKind::Press)));
+  }
+
+  #[test]
+  fn test_release_events() {
+    let mut tracker = KeyTracker::new(parse_keymap(DEFAULT_KEYMAP).unwrap(), true);
+    let now = Instant::now();
+    let key = Nibble::new::<0xA>();
+
+    assert_eq!(
+      tracker.process_event(create_event('Z', KeyEventKind::Press), now),
+      Some((key, true))
+    );
+    assert_eq!(
+      tracker.process_event(create_event('z', KeyEventKind::Repeat), now),
+      None
+    );
+    assert!(tracker.release_expired(now + KEY_HOLD_DURATION).is_empty());
+    assert_eq!(
+      tracker.process_event(create_event('z', KeyEventKind::Release), now),
+      Some((key, false))
+    );
+    assert_eq!(
+      tracker.process_event(create_event('p', KeyEventKind::Press), now),
+      None
+    );
+  }
+
+  #[test]
+  fn test_repeated_keys() {
+    let mut tracker = KeyTracker::new(parse_keymap(DEFAULT_KEYMAP).unwrap(), false);
+    let now = Instant::now();
+    let key = Nibble::new::<0x5>();
+
+    assert_eq!(
+      tracker.process_event(create_event('w', KeyEventKind::Press), now),
+      Some((key, true))
+    );
+    let repeated = now + KEY_HOLD_DURATION / 2;
+    assert_eq!(
+      tracker.process_event(create_event('w', KeyEventKind::Press), repeated),
+      None
+    );
+    assert!(tracker.release_expired(now + KEY_HOLD_DURATION).is_empty());
+    assert_eq!(
+      tracker.release_expired(repeated + KEY_HOLD_DURATION),
+      vec![key]
+    );
+    assert!(tracker
+      .release_expired(repeated + KEY_HOLD_DURATION * 2)
+      .is_empty());
+  }
+}
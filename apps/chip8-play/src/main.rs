@@ -0,0 +1,240 @@
+mod input;
+mod pacing;
+mod render;
+
+use std::{
+  env, fs,
+  io::{self, Write},
+  path::PathBuf,
+  process::ExitCode,
+  time::{Duration, Instant},
+};
+
+use chip8_interpreter::{
+  Address, BaseExecutable, BasePlatform, ControlledInterpreter, Font, Interpreter, LoaderError,
+  Mode, Nibble, Platform, Quirks, ScreenFrame, DEFAULT_DELAY_TIMER_DURATION,
+  DEFAULT_INSTRUCTION_DURATION, DEFAULT_SOUND_TIMER_DURATION,
+};
+use crossterm::{
+  cursor::{Hide, Show},
+  event::{
+    self, Event, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags,
+    PushKeyboardEnhancementFlags,
+  },
+  execute,
+  terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use input::{is_quit_event, parse_keymap, KeyTracker, DEFAULT_KEYMAP};
+use pacing::FramePacer;
+use render::{parse_palette, render, Palette, Renderer, DEFAULT_PALETTE};
+
+const USAGE: &str = "Usage: chip8-play <image.ch8> [-m chip8|schip|xochip] [-r half|braille] \
+                     [-c <bg,fg[,plane2,both]>|none] [-k <keymap>] [-s bell|flash|none]";
+
+const FRAME_DURATION: Duration = DEFAULT_DELAY_TIMER_DURATION;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Sound {
+  Bell,
+  Flash,
+  None,
+}
+
+struct Args {
+  image_path: PathBuf,
+  mode: Mode,
+  renderer: Renderer,
+  palette: Palette,
+  keymap: [char; Nibble::SIZE],
+  sound: Sound,
+}
+
+fn parse_args() -> Result<Args, String> {
+  let mut image_path = None;
+  let mut mode = Mode::Chip8;
+  let mut renderer = Renderer::HalfBlock;
+  let mut palette = Some(DEFAULT_PALETTE);
+  let mut keymap = parse_keymap(DEFAULT_KEYMAP).unwrap();
+  let mut sound = Sound::Bell;
+
+  let mut args = env::args().skip(1);
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "-m" => {
+        mode = match args.next().ok_or(USAGE)?.as_str() {
+          "chip8" => Mode::Chip8,
+          "schip" => Mode::SuperChip,
+          "xochip" => Mode::XoChip,
+          value => return Err(format!("Unknown mode: {value}\n{USAGE}")),
+        }
+      }
+      "-r" => {
+        renderer = match args.next().ok_or(USAGE)?.as_str() {
+          "half" => Renderer::HalfBlock,
+          "braille" => Renderer::Braille,
+          value => return Err(format!("Unknown renderer: {value}\n{USAGE}")),
+        }
+      }
+      "-c" => {
+        let value = args.next().ok_or(USAGE)?;
+        palette = parse_palette(&value).ok_or(format!("Invalid colors: {value}\n{USAGE}"))?;
+      }
+      "-k" => {
+        let value = args.next().ok_or(USAGE)?;
+        keymap = parse_keymap(&value).ok_or(format!(
+          "Keymap must list 16 keys for 0..F, e.g. {DEFAULT_KEYMAP}"
+        ))?;
+      }
+      "-s" => {
+        sound = match args.next().ok_or(USAGE)?.as_str() {
+          "bell" => Sound::Bell,
+          "flash" => Sound::Flash,
+          "none" => Sound::None,
+          value => return Err(format!("Unknown sound: {value}\n{USAGE}")),
+        }
+      }
+      "-h" | "--help" => return Err(String::from(USAGE)),
+      _ if image_path.is_none() && !arg.starts_with('-') => image_path = Some(PathBuf::from(arg)),
+      _ => return Err(format!("Unexpected argument: {arg}\n{USAGE}")),
+    }
+  }
+
+  Ok(Args {
+    image_path: image_path.ok_or(USAGE)?,
+    mode,
+    renderer,
+    palette,
+    keymap,
+    sound,
+  })
+}
+
+type Chip8 = ControlledInterpreter<BasePlatform<fn() -> u8>>;
+
+fn create_interpreter(image: &[u8], mode: Mode) -> Result<Chip8, LoaderError> {
+  let quirks = match mode {
+    Mode::Chip8 => Quirks::COSMAC_VIP,
+    Mode::SuperChip => Quirks::SUPER_CHIP_1_1,
+    Mode::XoChip => Quirks::XO_CHIP,
+  };
+  let platform = BasePlatform::new(rand::random as fn() -> u8);
+  let executable = BaseExecutable::new(image, Address::new::<0x200>());
+  let interpreter = Interpreter::new(platform, executable, mode, Font::default(), quirks)?;
+  Ok(ControlledInterpreter::new(
+    interpreter,
+    DEFAULT_INSTRUCTION_DURATION,
+    DEFAULT_DELAY_TIMER_DURATION,
+    DEFAULT_SOUND_TIMER_DURATION,
+  ))
+}
+
+/// Runs the program in real time until Esc, Ctrl+C, exit or an error.
+fn play(interpreter: &mut Chip8, args: &Args, has_release_events: bool) -> Result<(), String> {
+  let mut stdout = io::stdout();
+  let mut key_tracker = KeyTracker::new(args.keymap, has_release_events);
+  let mut last_screen_frame = None::<ScreenFrame>;
+  let mut was_sounding = false;
+  let mut pacer = FramePacer::new(FRAME_DURATION, Instant::now());
+
+  loop {
+    while event::poll(pacer.get_wait_duration(Instant::now())).map_err(to_string)? {
+      let Event::Key(key_event) = event::read().map_err(to_string)? else {
+        continue;
+      };
+      if is_quit_event(&key_event) {
+        return Ok(());
+      }
+      if let Some((key, is_down)) = key_tracker.process_event(key_event, Instant::now()) {
+        interpreter
+          .get_platform_mut()
+          .change_keyboard_state(key, is_down);
+      }
+    }
+    for key in key_tracker.release_expired(Instant::now()) {
+      interpreter
+        .get_platform_mut()
+        .change_keyboard_state(key, false);
+    }
+
+    interpreter
+      .simulate_duration(pacer.tick(Instant::now()))
+      .map_err(to_string)?;
+    if interpreter.get_state().is_exited() {
+      return Ok(());
+    }
+
+    let is_sounding = interpreter.get_platform().get_sound_timer() > 0;
+    if args.sound == Sound::Bell && is_sounding && !was_sounding {
+      write!(stdout, "\x07").map_err(to_string)?;
+    }
+    let screen_frame = *interpreter.get_platform().get_screen_frame();
+    let is_flashing = args.sound == Sound::Flash && is_sounding;
+    if last_screen_frame != Some(screen_frame) || is_sounding != was_sounding {
+      let is_resized = last_screen_frame
+        .is_some_and(|last| last.is_high_resolution() != screen_frame.is_high_resolution());
+      if is_resized {
+        execute!(stdout, Clear(ClearType::All)).map_err(to_string)?;
+      }
+      render(
+        &mut stdout,
+        &screen_frame,
+        args.renderer,
+        args.palette,
+        is_flashing,
+      )
+      .map_err(to_string)?;
+      last_screen_frame = Some(screen_frame);
+    }
+    was_sounding = is_sounding;
+  }
+}
+
+fn to_string(err: impl ToString) -> String {
+  err.to_string()
+}
+
+fn run(args: &Args) -> Result<(), String> {
+  let image =
+    fs::read(&args.image_path).map_err(|err| format!("{}: {err}", args.image_path.display()))?;
+  let mut interpreter = create_interpreter(&image, args.mode)
+    .map_err(|err| format!("{}: {err}", args.image_path.display()))?;
+
+  let mut stdout = io::stdout();
+  let has_release_events = terminal::supports_keyboard_enhancement().unwrap_or(false);
+  terminal::enable_raw_mode().map_err(to_string)?;
+  execute!(stdout, EnterAlternateScreen, Hide, Clear(ClearType::All)).map_err(to_string)?;
+  if has_release_events {
+    execute!(
+      stdout,
+      PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+    )
+    .map_err(to_string)?;
+  }
+
+  let res = play(&mut interpreter, args, has_release_events);
+
+  if has_release_events {
+    let _ = execute!(stdout, PopKeyboardEnhancementFlags);
+  }
+  let _ = execute!(stdout, Show, LeaveAlternateScreen);
+  let _ = terminal::disable_raw_mode();
+
+  res.map_err(|err| {
+    let state = interpreter.get_state();
+    format!(
+      "{err} at 0x{:04X}",
+      state.get_instruction_address().as_u16()
+    )
+  })
+}
+
+fn main() -> ExitCode {
+  let res = parse_args().and_then(|args| run(&args));
+  match res {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(message) => {
+      eprintln!("{message}");
+      ExitCode::FAILURE
+    }
+  }
+}
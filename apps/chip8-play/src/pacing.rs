@@ -0,0 +1,73 @@
+use std::time::{Duration, Instant};
+
+/// Frames a late tick catches up at most, so a suspended terminal doesn't fast forward the game.
+const MAX_CATCH_UP_FRAMES: u32 = 4;
+
+/// Paces the program by the wall clock: input is polled until the next frame is due, then the
+/// machine catches up with the real time that passed since the previous frame.
+pub struct FramePacer {
+  frame_duration: Duration,
+  last_tick: Instant,
+}
+
+impl FramePacer {
+  pub fn new(frame_duration: Duration, now: Instant) -> Self {
+    Self {
+      frame_duration,
+      last_tick: now,
+    }
+  }
+
+  /// How long to wait for input before the next frame is due.
+  pub fn get_wait_duration(&self, now: Instant) -> Duration {
+    self
+      .frame_duration
+      .saturating_sub(now.saturating_duration_since(self.last_tick))
+  }
+
+  /// Starts the next frame and returns the real time to simulate since the previous one, up to
+  /// a few frames.
+  pub fn tick(&mut self, now: Instant) -> Duration {
+    let elapsed = now.saturating_duration_since(self.last_tick);
+    self.last_tick = self.last_tick.max(now);
+    elapsed.min(self.frame_duration * MAX_CATCH_UP_FRAMES)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_frame_pacer() {
+    let frame_duration = Duration::from_millis(16);
+    let start = Instant::now();
+    let mut pacer = FramePacer::new(frame_duration, start);
+
+    assert_eq!(pacer.get_wait_duration(start), frame_duration);
+    assert_eq!(
+      pacer.get_wait_duration(start + Duration::from_millis(10)),
+      Duration::from_millis(6)
+    );
+    assert_eq!(
+      pacer.get_wait_duration(start + frame_duration * 2),
+      Duration::ZERO
+    );
+
+    // A late frame simulates all the time that passed.
+    let late = start + Duration::from_millis(40);
+    assert_eq!(pacer.tick(late), Duration::from_millis(40));
+    assert_eq!(pacer.get_wait_duration(late), frame_duration);
+    assert_eq!(pacer.tick(late), Duration::ZERO);
+    assert_eq!(pacer.tick(start), Duration::ZERO);
+    assert_eq!(
+      pacer.tick(late + Duration::from_millis(16)),
+      Duration::from_millis(16)
+    );
+
+    // A long pause, e.g. a suspended terminal, catches up only a few frames.
+    let resumed = late + Duration::from_secs(10);
+    assert_eq!(pacer.tick(resumed), frame_duration * MAX_CATCH_UP_FRAMES);
+    assert_eq!(pacer.get_wait_duration(resumed), frame_duration);
+  }
+}
@@ -0,0 +1,150 @@
+use std::io::{self, Write};
+
+use chip8_interpreter::ScreenFrame;
+use crossterm::{
+  cursor::MoveTo,
+  queue,
+  style::{
+    Attribute, Color, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor,
+  },
+};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Renderer {
+  /// One cell shows two pixels stacked vertically.
+  HalfBlock,
+  /// One cell shows a block of 2x4 pixels.
+  Braille,
+}
+
+/// Colours of the pixel values: background, first plane, second plane and both planes.
+/// `None` keeps the terminal colours and draws monochrome.
+pub type Palette = Option<[Color; 4]>;
+
+pub const DEFAULT_PALETTE: [Color; 4] = [
+  Color::Rgb { r: 0, g: 0, b: 0 },
+  Color::Rgb {
+    r: 255,
+    g: 255,
+    b: 255,
+  },
+  Color::Rgb {
+    r: 170,
+    g: 170,
+    b: 170,
+  },
+  Color::Rgb {
+    r: 85,
+    g: 85,
+    b: 85,
+  },
+];
+
+const BRAILLE_BASE: u32 = 0x2800;
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+pub fn parse_palette(text: &str) -> Option<Palette> {
+  if text == "none" {
+    return Some(None);
+  }
+
+  let colors = text
+    .split(',')
+    .map(|color| {
+      let value = u32::from_str_radix(color.trim_start_matches('#'), 16).ok()?;
+      (color.trim_start_matches('#').len() == 6).then_some(Color::Rgb {
+        r: (value >> 16) as u8,
+        g: (value >> 8) as u8,
+        b: value as u8,
+      })
+    })
+    .collect::<Option<Vec<_>>>()?;
+  match colors.len() {
+    2 => Some(Some([colors[0], colors[1], colors[1], colors[1]])),
+    4 => Some(Some([colors[0], colors[1], colors[2], colors[3]])),
+    _ => None,
+  }
+}
+
+pub fn render(
+  writer: &mut impl Write,
+  screen_frame: &ScreenFrame,
+  renderer: Renderer,
+  palette: Palette,
+  is_flashing: bool,
+) -> io::Result<()> {
+  let palette = palette.map(|mut palette| {
+    if is_flashing {
+      palette.swap(0, 1);
+    }
+    palette
+  });
+  let (cell_width, cell_height) = match renderer {
+    Renderer::HalfBlock => (1, 2),
+    Renderer::Braille => (2, 4),
+  };
+  let pixel =
+    |x: usize, y: usize| match x < screen_frame.get_width() && y < screen_frame.get_height() {
+      true => screen_frame.get_pixel(x, y) as usize % 4,
+      false => 0,
+    };
+
+  for row in 0..screen_frame.get_height().div_ceil(cell_height) {
+    queue!(writer, MoveTo(0, row as u16))?;
+    if palette.is_none() && is_flashing {
+      queue!(writer, SetAttribute(Attribute::Reverse))?;
+    }
+    for column in 0..screen_frame.get_width().div_ceil(cell_width) {
+      let (x, y) = (column * cell_width, row * cell_height);
+      match renderer {
+        Renderer::HalfBlock => render_half_block(writer, pixel(x, y), pixel(x, y + 1), palette)?,
+        Renderer::Braille => {
+          let dots = (0..4)
+            .flat_map(|dy| (0..2).map(move |dx| (dx, dy)))
+            .filter(|&(dx, dy)| pixel(x + dx, y + dy) != 0)
+            .fold(0, |dots, (dx, dy)| dots | BRAILLE_DOTS[dy][dx]);
+          let color = (0..8)
+            .map(|index| pixel(x + index % 2, y + index / 2))
+            .max()
+            .unwrap_or_default();
+          if let Some(palette) = palette {
+            queue!(
+              writer,
+              SetForegroundColor(palette[color.max(1)]),
+              SetBackgroundColor(palette[0])
+            )?;
+          }
+          let char = char::from_u32(BRAILLE_BASE + dots).unwrap_or(' ');
+          queue!(writer, Print(char))?;
+        }
+      }
+    }
+    queue!(writer, ResetColor, SetAttribute(Attribute::Reset))?;
+  }
+  writer.flush()
+}
+
+fn render_half_block(
+  writer: &mut impl Write,
+  top: usize,
+  bottom: usize,
+  palette: Palette,
+) -> io::Result<()> {
+  match palette {
+    Some(palette) => queue!(
+      writer,
+      SetForegroundColor(palette[top]),
+      SetBackgroundColor(palette[bottom]),
+      Print('▀')
+    ),
+    None => {
+      let char = match (top != 0, bottom != 0) {
+        (false, false) => ' ',
+        (true, false) => '▀',
+        (false, true) => '▄',
+        (true, true) => '█',
+      };
+      queue!(writer, Print(char))
+    }
+  }
+}
@@ -0,0 +1,53 @@
+use std::io::{self, Write};
+
+use chip8_interpreter::{InterpreterState, ScreenFrame};
+use serde_json::json;
+
+const TRUE_PIXEL: char = '@';
+const FALSE_PIXEL: char = '.';
+
+/// Screen rows with `@` for lit and `.` for dark pixels.
+pub fn format_screen_frame(screen_frame: &ScreenFrame) -> Vec<String> {
+  screen_frame
+    .iter_rows()
+    .map(|row| {
+      row
+        .iter()
+        .map(|&value| match value {
+          0 => FALSE_PIXEL,
+          _ => TRUE_PIXEL,
+        })
+        .collect()
+    })
+    .collect()
+}
+
+pub fn write_text(writer: &mut impl Write, screen_frame: &ScreenFrame) -> io::Result<()> {
+  format_screen_frame(screen_frame)
+    .iter()
+    .try_for_each(|row| writeln!(writer, "{row}"))
+}
+
+pub fn write_json(
+  writer: &mut impl Write,
+  state: &InterpreterState,
+  screen_frame: &ScreenFrame,
+  elapsed_ms: u128,
+) -> io::Result<()> {
+  let value = json!({
+    "pc": state.get_instruction_address().as_u16(),
+    "i": state.get_index_register().as_u16(),
+    "registers": state.get_registers(),
+    "stack": state.get_stack().iter().map(|address| address.as_u16()).collect::<Vec<_>>(),
+    "delay_timer": state.get_delay_timer(),
+    "sound_timer": state.get_sound_timer(),
+    "executed_count": state.get_executed_count(),
+    "elapsed_ms": elapsed_ms as u64,
+    "is_exited": state.is_exited(),
+    "is_crashed": state.is_crashed(),
+    "is_high_resolution": screen_frame.is_high_resolution(),
+    "screen": format_screen_frame(screen_frame),
+  });
+  serde_json::to_writer_pretty(&mut *writer, &value)?;
+  writeln!(writer)
+}
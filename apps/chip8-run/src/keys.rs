@@ -0,0 +1,47 @@
+use std::{collections::VecDeque, time::Duration};
+
+use chip8_interpreter::Nibble;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct KeyEvent {
+  pub time: Duration,
+  pub key: Nibble,
+  pub is_down: bool,
+}
+
+/// Parses a key timeline with one `<time in ms> <hex key> down|up` event per line.
+/// Empty lines and lines starting with `#` are skipped.
+pub fn parse_key_timeline(text: &str) -> Result<VecDeque<KeyEvent>, String> {
+  let mut events = text
+    .lines()
+    .enumerate()
+    .map(|(index, line)| (index + 1, line.trim()))
+    .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+    .map(|(line_number, line)| {
+      let error = || format!("Line {line_number}: expected `<time in ms> <hex key> down|up`");
+      let parts = line.split_whitespace().collect::<Vec<_>>();
+      let [time, key, state] = parts[..] else {
+        return Err(error());
+      };
+
+      let time = time.parse::<u64>().map_err(|_| error())?;
+      let key = u8::from_str_radix(key, 16)
+        .ok()
+        .and_then(|key| Nibble::try_from(key).ok())
+        .ok_or_else(error)?;
+      let is_down = match state {
+        "down" => true,
+        "up" => false,
+        _ => return Err(error()),
+      };
+      Ok(KeyEvent {
+        time: Duration::from_millis(time),
+        key,
+        is_down,
+      })
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+
+  events.sort_by_key(|event| event.time);
+  Ok(events.into())
+}
@@ -0,0 +1,323 @@
+mod dump;
+mod keys;
+
+use std::{
+  collections::VecDeque,
+  env,
+  fs::{self, File},
+  io::{self, BufWriter, Write},
+  path::PathBuf,
+  process::ExitCode,
+  time::Duration,
+};
+
+use chip8_interpreter::{
+  get_frame_start, get_vip_frame_duration, write_pbm, write_png, write_wav, Address, AudioConfig,
+  BaseExecutable, BasePlatform, ControlledInterpreter, Font, Interpreter, InterpreterError, Mode,
+  Quirks, RandomGenerator, SeededRandom, Timing, DEFAULT_DELAY_TIMER_DURATION,
+  DEFAULT_INSTRUCTION_DURATION, DEFAULT_PALETTE, DEFAULT_SOUND_TIMER_DURATION,
+};
+use keys::{parse_key_timeline, KeyEvent};
+
+const USAGE: &str = "Usage: chip8-run <image.ch8> (-n <instructions> | -f <frames> | -t <ms>)
+  [-m chip8|schip|xochip] [-q vip|chip48|schip|xochip] [--ips <instructions per second>|vip]
+  [--ipf <instructions per frame>] [--seed <n>]
+  [-k <keys.txt>] [-d text|png|pbm|json] [-o <path>] [--scale <n>] [-w <sound.wav>]";
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Limit {
+  Instructions(u64),
+  Duration(Duration),
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum DumpFormat {
+  Text,
+  Png,
+  Pbm,
+  Json,
+}
+
+struct Args {
+  image_path: PathBuf,
+  limit: Limit,
+  mode: Mode,
+  quirks: Option<Quirks>,
+  instruction_duration: Duration,
+  timing: Timing,
+  seed: Option<u64>,
+  keys_path: Option<PathBuf>,
+  dump_format: Option<DumpFormat>,
+  output_path: Option<PathBuf>,
+  scale: usize,
+  wav_path: Option<PathBuf>,
+}
+
+fn parse_number<T: std::str::FromStr>(value: Option<String>, name: &str) -> Result<T, String> {
+  let value = value.ok_or(USAGE)?;
+  value
+    .parse()
+    .map_err(|_| format!("Invalid {name}: {value}\n{USAGE}"))
+}
+
+fn parse_args() -> Result<Args, String> {
+  let mut image_path = None;
+  let mut limit = None;
+  let mut frames = None;
+  let mut mode = Mode::Chip8;
+  let mut quirks = None;
+  let mut instruction_duration = DEFAULT_INSTRUCTION_DURATION;
+  let mut timing = Timing::Fixed;
+  let mut seed = None;
+  let mut keys_path = None;
+  let mut dump_format = None;
+  let mut output_path = None;
+  let mut scale = 1;
+  let mut wav_path = None;
+
+  let mut args = env::args().skip(1);
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "-n" => limit = Some(Limit::Instructions(parse_number(args.next(), "count")?)),
+      "-f" => {
+        frames = Some(parse_number::<u32>(args.next(), "count")?);
+        limit = None;
+      }
+      "-t" => {
+        let millis = parse_number(args.next(), "duration")?;
+        limit = Some(Limit::Duration(Duration::from_millis(millis)));
+      }
+      "-m" => {
+        mode = match args.next().ok_or(USAGE)?.as_str() {
+          "chip8" => Mode::Chip8,
+          "schip" => Mode::SuperChip,
+          "xochip" => Mode::XoChip,
+          value => return Err(format!("Unknown mode: {value}\n{USAGE}")),
+        }
+      }
+      "-q" => {
+        quirks = Some(match args.next().ok_or(USAGE)?.as_str() {
+          "vip" => Quirks::COSMAC_VIP,
+          "chip48" => Quirks::CHIP_48,
+          "schip" => Quirks::SUPER_CHIP_1_1,
+          "xochip" => Quirks::XO_CHIP,
+          value => return Err(format!("Unknown quirks: {value}\n{USAGE}")),
+        })
+      }
+      // The last of `--ips` and `--ipf` selects the timing.
+      "--ips" => match args.next().as_deref() {
+        Some("vip") => timing = Timing::CosmacVip,
+        value => {
+          let ips = parse_number::<u32>(value.map(String::from), "speed")?;
+          if ips == 0 {
+            return Err(format!("Invalid speed: 0\n{USAGE}"));
+          }
+          instruction_duration = Duration::from_secs(1) / ips;
+          timing = Timing::Fixed;
+        }
+      },
+      "--ipf" => {
+        let instructions_per_frame = parse_number::<u32>(args.next(), "speed")?;
+        if instructions_per_frame == 0 {
+          return Err(format!("Invalid speed: 0\n{USAGE}"));
+        }
+        timing = Timing::FrameBased {
+          instructions_per_frame,
+        };
+      }
+      "--seed" => seed = Some(parse_number(args.next(), "seed")?),
+      "-k" => keys_path = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+      "-d" => {
+        dump_format = Some(match args.next().ok_or(USAGE)?.as_str() {
+          "text" => DumpFormat::Text,
+          "png" => DumpFormat::Png,
+          "pbm" => DumpFormat::Pbm,
+          "json" => DumpFormat::Json,
+          value => return Err(format!("Unknown dump format: {value}\n{USAGE}")),
+        })
+      }
+      "-o" => output_path = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+      "--scale" => scale = parse_number::<usize>(args.next(), "scale")?.max(1),
+      "-w" => wav_path = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+      "-h" | "--help" => return Err(String::from(USAGE)),
+      _ if image_path.is_none() && !arg.starts_with('-') => image_path = Some(PathBuf::from(arg)),
+      _ => return Err(format!("Unexpected argument: {arg}\n{USAGE}")),
+    }
+  }
+
+  // Frames are counted in ticks of the delay timer, which depend on the timing.
+  if let Some(frames) = frames.filter(|_| limit.is_none()) {
+    let duration = match timing {
+      Timing::Fixed => DEFAULT_DELAY_TIMER_DURATION * frames,
+      Timing::CosmacVip => get_vip_frame_duration() * frames,
+      Timing::FrameBased { .. } => get_frame_start(frames as u64),
+    };
+    limit = Some(Limit::Duration(duration));
+  }
+  let is_image = matches!(dump_format, Some(DumpFormat::Png | DumpFormat::Pbm));
+  if is_image && output_path.is_none() {
+    return Err(format!("Image dump needs an output path\n{USAGE}"));
+  }
+  Ok(Args {
+    image_path: image_path.ok_or(USAGE)?,
+    limit: limit.ok_or(USAGE)?,
+    mode,
+    quirks,
+    instruction_duration,
+    timing,
+    seed,
+    keys_path,
+    dump_format,
+    output_path,
+    scale,
+    wav_path,
+  })
+}
+
+type Chip8 = ControlledInterpreter<BasePlatform<Box<dyn FnMut() -> u8>>>;
+
+fn apply_key_events(interpreter: &mut Chip8, key_events: &mut VecDeque<KeyEvent>) {
+  while let Some(event) = key_events.front() {
+    if event.time > interpreter.get_elapsed() {
+      break;
+    }
+    interpreter
+      .get_platform_mut()
+      .change_keyboard_state(event.key, event.is_down);
+    key_events.pop_front();
+  }
+}
+
+/// Runs the program until the limit, exit or an error.
+fn simulate(
+  interpreter: &mut Chip8,
+  limit: Limit,
+  mut key_events: VecDeque<KeyEvent>,
+) -> Result<(), InterpreterError> {
+  loop {
+    apply_key_events(interpreter, &mut key_events);
+    let state = interpreter.get_state();
+    if state.is_exited() {
+      return Ok(());
+    }
+
+    match limit {
+      Limit::Instructions(count) => {
+        let executed_count = state.get_executed_count();
+        if executed_count >= count {
+          return Ok(());
+        }
+        interpreter.simulate_one_instruction()?;
+
+        let state = interpreter.get_state();
+        let is_stuck = state.get_executed_count() == executed_count
+          && state.get_expecting_key().is_some()
+          && key_events.is_empty();
+        if is_stuck {
+          return Ok(());
+        }
+      }
+      Limit::Duration(end) => {
+        let elapsed = interpreter.get_elapsed();
+        if elapsed >= end {
+          return Ok(());
+        }
+        let next_stop = key_events.front().map_or(end, |event| event.time.min(end));
+        interpreter.simulate_duration(next_stop - elapsed)?;
+      }
+    }
+  }
+}
+
+fn dump(args: &Args, format: DumpFormat, interpreter: &Chip8) -> io::Result<()> {
+  let mut writer: Box<dyn Write> = match &args.output_path {
+    Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+    None => Box::new(io::stdout().lock()),
+  };
+  let screen_frame = interpreter.get_platform().get_screen_frame();
+  match format {
+    DumpFormat::Text => dump::write_text(&mut writer, screen_frame)?,
+    DumpFormat::Png => write_png(&mut writer, screen_frame, args.scale, &DEFAULT_PALETTE)
+      .map_err(io::Error::other)?,
+    DumpFormat::Pbm => write_pbm(&mut writer, screen_frame, args.scale, &DEFAULT_PALETTE)?,
+    DumpFormat::Json => dump::write_json(
+      &mut writer,
+      &interpreter.get_state(),
+      screen_frame,
+      interpreter.get_elapsed().as_millis(),
+    )?,
+  }
+  writer.flush()
+}
+
+fn run(args: &Args) -> Result<(), String> {
+  let image =
+    fs::read(&args.image_path).map_err(|err| format!("{}: {err}", args.image_path.display()))?;
+  let key_events = match &args.keys_path {
+    Some(path) => {
+      let text = fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+      parse_key_timeline(&text).map_err(|err| format!("{}: {err}", path.display()))?
+    }
+    None => VecDeque::new(),
+  };
+  let quirks = args.quirks.unwrap_or(match args.mode {
+    Mode::Chip8 => Quirks::COSMAC_VIP,
+    Mode::SuperChip => Quirks::SUPER_CHIP_1_1,
+    Mode::XoChip => Quirks::XO_CHIP,
+  });
+
+  let rand: Box<dyn FnMut() -> u8> = match args.seed {
+    Some(seed) => {
+      let mut rand = SeededRandom::new(seed);
+      Box::new(move || rand.get_random_byte())
+    }
+    None => Box::new(rand::random),
+  };
+  let platform = BasePlatform::new(rand);
+  let executable = BaseExecutable::new(&image, Address::new::<0x200>());
+  let interpreter = Interpreter::new(platform, executable, args.mode, Font::default(), quirks)
+    .map_err(|err| format!("{}: {err}", args.image_path.display()))?;
+  let mut interpreter = match args.timing {
+    Timing::Fixed => ControlledInterpreter::new(
+      interpreter,
+      args.instruction_duration,
+      DEFAULT_DELAY_TIMER_DURATION,
+      DEFAULT_SOUND_TIMER_DURATION,
+    ),
+    Timing::CosmacVip => ControlledInterpreter::new_cosmac_vip(interpreter),
+    Timing::FrameBased {
+      instructions_per_frame,
+    } => ControlledInterpreter::new_frame_based(interpreter, instructions_per_frame),
+  };
+
+  if args.wav_path.is_some() {
+    interpreter.enable_audio(AudioConfig {
+      buffer_duration: Duration::MAX,
+      ..AudioConfig::default()
+    });
+  }
+
+  let res = simulate(&mut interpreter, args.limit, key_events);
+  if let Some(format) = args.dump_format {
+    dump(args, format, &interpreter).map_err(|err| err.to_string())?;
+  }
+  if let (Some(path), Some(audio)) = (&args.wav_path, interpreter.get_audio_mut()) {
+    let sample_rate = audio.get_config().sample_rate;
+    let samples = audio.get_buffer_mut().drain();
+    File::create(path)
+      .and_then(|file| write_wav(BufWriter::new(file), sample_rate, &samples))
+      .map_err(|err| format!("{}: {err}", path.display()))?;
+  }
+  res.map_err(|err| format!("Error: {err}"))
+}
+
+fn main() -> ExitCode {
+  let res = parse_args().and_then(|args| run(&args));
+  match res {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(message) => {
+      eprintln!("{message}");
+      ExitCode::FAILURE
+    }
+  }
+}
@@ -0,0 +1,98 @@
+use std::{
+  env, fs,
+  path::PathBuf,
+  process::{self, Command, Output},
+};
+
+const IMAGES_PATH: &str = concat!(
+  env!("CARGO_MANIFEST_DIR"),
+  "/../../libs/chip8-interpreter/tests/images"
+);
+
+fn run_chip8_image(image_path: &str, args: &[&str]) -> Output {
+  Command::new(env!("CARGO_BIN_EXE_chip8-run"))
+    .arg(image_path)
+    .args(args)
+    .output()
+    .unwrap()
+}
+
+fn run_chip8(image: &str, args: &[&str]) -> String {
+  let output = run_chip8_image(&format!("{IMAGES_PATH}/{image}"), args);
+  assert!(
+    output.status.success(),
+    "{}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+  String::from_utf8(output.stdout).unwrap()
+}
+
+fn write_temp_file(name: &str, contents: &[u8]) -> PathBuf {
+  let path = env::temp_dir().join(format!("chip8-run-{}-{name}", process::id()));
+  fs::write(&path, contents).unwrap();
+  path
+}
+
+#[test]
+fn test_text_dump() {
+  assert_eq!(
+    run_chip8("ibm-logo.ch8", &["-f", "60", "-d", "text"]),
+    include_str!("./results/ibm-logo.txt")
+  );
+}
+
+#[test]
+fn test_json_dump() {
+  assert_eq!(
+    run_chip8("flags.ch8", &["-f", "300", "-d", "json"]),
+    include_str!("./results/flags.json")
+  );
+}
+
+#[test]
+fn test_key_timeline() {
+  let keys = "# Keypad test menu\n0 3 down\n1000 3 up\n2000 0 down\n3000 0 up\n";
+  let keys_path = write_temp_file("keys.txt", keys.as_bytes());
+  let dump = run_chip8(
+    "keypad.ch8",
+    &[
+      "-t",
+      "4000",
+      "-k",
+      keys_path.to_str().unwrap(),
+      "-d",
+      "text",
+    ],
+  );
+  fs::remove_file(keys_path).unwrap();
+  assert_eq!(dump, include_str!("./results/keypad.txt"));
+}
+
+#[test]
+fn test_crash() {
+  let image_path = write_temp_file("crash.ch8", &[0xFF, 0xFF]);
+  let output = run_chip8_image(image_path.to_str().unwrap(), &["-f", "10"]);
+  fs::remove_file(image_path).unwrap();
+  assert!(!output.status.success());
+
+  let stderr = String::from_utf8(output.stderr).unwrap();
+  let lines = stderr.lines().collect::<Vec<_>>();
+  assert_eq!(lines[0], "Error: Unknown opcode: FFFF");
+  assert!(lines.iter().any(|line| line.starts_with("PC:0200 OP:FFFF")));
+  assert!(lines.iter().any(|line| line.starts_with("Backtrace:")));
+}
+
+#[test]
+fn test_timing_flags() {
+  // Sprites wait for the next frame, so 50 instructions take 125 ms at 1000 per second and 149 ms
+  // at 10 per frame.
+  let run = |args: &[&str]| {
+    run_chip8(
+      "ibm-logo.ch8",
+      &[&["-n", "50", "-d", "json"], args].concat(),
+    )
+  };
+  assert!(run(&["--ipf", "10", "--ips", "1000"]).contains("\"elapsed_ms\": 125,"));
+  assert!(run(&["--ips", "vip", "--ips", "1000"]).contains("\"elapsed_ms\": 125,"));
+  assert!(run(&["--ips", "1000", "--ipf", "10"]).contains("\"elapsed_ms\": 149,"));
+}
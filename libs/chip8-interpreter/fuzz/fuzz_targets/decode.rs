@@ -0,0 +1,35 @@
+#![no_main]
+
+use chip8_interpreter::{
+  format_instruction, Address, Disassembly, Instruction, Mode, OpCode, Syntax,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+  let read_op_code = |offset: usize| {
+    OpCode::from_bytes(
+      data.get(offset).copied().unwrap_or_default(),
+      data.get(offset + 1).copied().unwrap_or_default(),
+    )
+  };
+
+  // Every decoded instruction encodes back to the same instruction.
+  if let Ok(instruction) = Instruction::decode(read_op_code(0), read_op_code(2)) {
+    format_instruction(instruction, Syntax::Octo);
+    format_instruction(instruction, Syntax::Cowgod);
+    let bytes = instruction.encode().unwrap();
+    let op_code = OpCode::from_bytes(bytes[0], bytes[1]);
+    let next_op_code = match bytes.len() {
+      4 => OpCode::from_bytes(bytes[2], bytes[3]),
+      _ => read_op_code(2),
+    };
+    assert_eq!(
+      Instruction::decode(op_code, next_op_code).unwrap(),
+      instruction
+    );
+  }
+
+  for mode in [Mode::Chip8, Mode::SuperChip, Mode::XoChip] {
+    Disassembly::new(data, Address::new::<0x200>(), Address::new::<0x200>(), mode);
+  }
+});
@@ -0,0 +1,60 @@
+#![no_main]
+
+use std::time::Duration;
+
+use chip8_interpreter::{
+  Address, BaseExecutable, BasePlatform, ControlledInterpreter, Font, Interpreter, Mode, Nibble,
+  Quirks, SeededRandom, DEFAULT_DELAY_TIMER_DURATION, DEFAULT_INSTRUCTION_DURATION,
+  DEFAULT_SOUND_TIMER_DURATION,
+};
+use libfuzzer_sys::fuzz_target;
+
+const INSTRUCTIONS_COUNT: u64 = 10_000;
+
+fuzz_target!(|data: &[u8]| {
+  let Some((&config, image)) = data.split_first() else {
+    return;
+  };
+  let mode = match config % 3 {
+    0 => Mode::Chip8,
+    1 => Mode::SuperChip,
+    _ => Mode::XoChip,
+  };
+  let quirks = match config / 3 % 4 {
+    0 => Quirks::COSMAC_VIP,
+    1 => Quirks::CHIP_48,
+    2 => Quirks::SUPER_CHIP_1_1,
+    _ => Quirks::XO_CHIP,
+  };
+
+  let platform = BasePlatform::new(SeededRandom::new(config as u64));
+  let executable = BaseExecutable::new(image, Address::new::<0x200>());
+  let Ok(interpreter) = Interpreter::new(platform, executable, mode, Font::default(), quirks)
+  else {
+    return;
+  };
+  let mut interpreter = match config / 12 % 3 {
+    0 => ControlledInterpreter::new(
+      interpreter,
+      DEFAULT_INSTRUCTION_DURATION,
+      DEFAULT_DELAY_TIMER_DURATION,
+      DEFAULT_SOUND_TIMER_DURATION,
+    ),
+    1 => ControlledInterpreter::new_cosmac_vip(interpreter),
+    _ => ControlledInterpreter::new_frame_based(interpreter, 15),
+  };
+
+  // Keys follow the image bytes, so programs waiting for input get to run too.
+  for (step, &byte) in (0..INSTRUCTIONS_COUNT).zip(image.iter().cycle()) {
+    if step % 64 == 0 {
+      let key = Nibble::try_from(byte & 0x0F).unwrap();
+      interpreter
+        .get_platform_mut()
+        .change_keyboard_state(key, byte & 0x10 != 0);
+    }
+    if interpreter.simulate_one_instruction().is_err() {
+      break;
+    }
+  }
+  let _ = interpreter.simulate_duration(Duration::from_millis(100));
+});
@@ -0,0 +1,21 @@
+#![no_main]
+
+use chip8_interpreter::{
+  Address, BaseExecutable, BasePlatform, Font, FontSet, Interpreter, Mode, Quirks, ScriptedRandom,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+  let Some((header, image)) = data.split_first_chunk::<4>() else {
+    return;
+  };
+  let load_point = Address::from(u16::from_le_bytes([header[0], header[1]]));
+  let font_load_point = Address::from(u16::from_le_bytes([header[2], header[3]]));
+
+  for mode in [Mode::Chip8, Mode::SuperChip, Mode::XoChip] {
+    let platform = BasePlatform::new(ScriptedRandom::default());
+    let executable = BaseExecutable::new(image, load_point);
+    let font = Font::from_set(FontSet::default(), font_load_point);
+    let _ = Interpreter::new(platform, executable, mode, font, Quirks::default());
+  }
+});
@@ -0,0 +1,759 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use crate::{
+  address::Address,
+  errors::{AssemblerError, AssemblerErrorKind, InterpreterError},
+  interpreter::Instruction,
+  nibble::Nibble,
+};
+
+pub const DEFAULT_LOAD_POINT: Address = Address::new::<0x200>();
+
+const SHORT_ADDRESS_MAX: i32 = 0xFFF;
+const MAX_MACRO_DEPTH: usize = 64;
+const ADDRESS_BYTE_STEP: usize = 2;
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum Symbol {
+  Label(Address),
+  Const(i32),
+  Alias(Nibble),
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Program {
+  image: Vec<u8>,
+  load_point: Address,
+  symbols: BTreeMap<String, Symbol>,
+  source_lines: BTreeMap<Address, usize>,
+}
+
+impl Program {
+  pub fn get_image(&self) -> &[u8] {
+    &self.image
+  }
+
+  pub fn get_load_point(&self) -> Address {
+    self.load_point
+  }
+
+  pub fn get_symbols(&self) -> &BTreeMap<String, Symbol> {
+    &self.symbols
+  }
+
+  /// Source line of every instruction by its address.
+  pub fn get_source_lines(&self) -> &BTreeMap<Address, usize> {
+    &self.source_lines
+  }
+
+  pub fn get_label(&self, name: &str) -> Option<Address> {
+    match self.symbols.get(name) {
+      Some(&Symbol::Label(address)) => Some(address),
+      _ => None,
+    }
+  }
+}
+
+pub fn assemble(source: &str) -> Result<Program, AssemblerError> {
+  let mut assembler = Assembler::new(source);
+  while let Some(token) = assembler.next_token() {
+    assembler.process_statement(token)?;
+  }
+  assembler.finish()
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+struct Token {
+  text: String,
+  line: usize,
+  depth: usize,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+struct Macro {
+  args: Vec<String>,
+  body: Vec<Token>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum FixupKind {
+  Short,
+  Long,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+struct Fixup {
+  offset: usize,
+  name: String,
+  kind: FixupKind,
+  line: usize,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+enum Block {
+  If {
+    jump_offset: usize,
+    line: usize,
+  },
+  Else {
+    jump_offset: usize,
+    line: usize,
+  },
+  Loop {
+    start: Address,
+    breaks: Vec<usize>,
+    line: usize,
+  },
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Condition {
+  Equal(Nibble, u8),
+  NotEqual(Nibble, u8),
+  RegistersEqual(Nibble, Nibble),
+  RegistersNotEqual(Nibble, Nibble),
+  KeyDown(Nibble),
+  KeyUp(Nibble),
+}
+
+impl Condition {
+  fn negate(self) -> Self {
+    match self {
+      Condition::Equal(x, value) => Condition::NotEqual(x, value),
+      Condition::NotEqual(x, value) => Condition::Equal(x, value),
+      Condition::RegistersEqual(x, y) => Condition::RegistersNotEqual(x, y),
+      Condition::RegistersNotEqual(x, y) => Condition::RegistersEqual(x, y),
+      Condition::KeyDown(x) => Condition::KeyUp(x),
+      Condition::KeyUp(x) => Condition::KeyDown(x),
+    }
+  }
+
+  fn get_skip_instruction(self) -> Instruction {
+    match self {
+      Condition::Equal(x, value) => Instruction::SkipIfEqual(x, value),
+      Condition::NotEqual(x, value) => Instruction::SkipIfNotEqual(x, value),
+      Condition::RegistersEqual(x, y) => Instruction::SkipIfRegistersEqual(x, y),
+      Condition::RegistersNotEqual(x, y) => Instruction::SkipIfRegistersNotEqual(x, y),
+      Condition::KeyDown(x) => Instruction::SkipIfKeyDown(x),
+      Condition::KeyUp(x) => Instruction::SkipIfKeyUp(x),
+    }
+  }
+}
+
+fn parse_number(text: &str) -> Option<i32> {
+  let (is_negative, text) = match text.strip_prefix('-') {
+    Some(rest) => (true, rest),
+    None => (false, text),
+  };
+  let value = if let Some(hex) = text.strip_prefix("0x") {
+    i32::from_str_radix(hex, 16).ok()?
+  } else if let Some(binary) = text.strip_prefix("0b") {
+    i32::from_str_radix(binary, 2).ok()?
+  } else {
+    text.parse::<i32>().ok()?
+  };
+  Some(match is_negative {
+    true => -value,
+    false => value,
+  })
+}
+
+fn parse_register(text: &str) -> Option<Nibble> {
+  let digit = text.strip_prefix('v').or(text.strip_prefix('V'))?;
+  if digit.len() != 1 {
+    return None;
+  }
+  Nibble::try_from(u8::from_str_radix(digit, 16).ok()?).ok()
+}
+
+struct Assembler {
+  tokens: VecDeque<Token>,
+  image: Vec<u8>,
+  position: usize,
+  symbols: BTreeMap<String, Symbol>,
+  macros: HashMap<String, Macro>,
+  fixups: Vec<Fixup>,
+  blocks: Vec<Block>,
+  source_lines: BTreeMap<Address, usize>,
+  line: usize,
+}
+
+impl Assembler {
+  fn new(source: &str) -> Self {
+    let tokens = source
+      .lines()
+      .enumerate()
+      .flat_map(|(index, line)| {
+        let code = line.split('#').next().unwrap_or_default();
+        code.split_whitespace().map(move |text| Token {
+          text: String::from(text),
+          line: index + 1,
+          depth: 0,
+        })
+      })
+      .collect();
+
+    Self {
+      tokens,
+      image: Vec::new(),
+      position: 0,
+      symbols: BTreeMap::new(),
+      macros: HashMap::new(),
+      fixups: Vec::new(),
+      blocks: Vec::new(),
+      source_lines: BTreeMap::new(),
+      line: 1,
+    }
+  }
+
+  fn error(&self, kind: AssemblerErrorKind) -> AssemblerError {
+    AssemblerError::new(self.line, kind)
+  }
+
+  fn get_address(&self) -> Address {
+    DEFAULT_LOAD_POINT + self.position as i16
+  }
+
+  fn next_token(&mut self) -> Option<Token> {
+    let token = self.tokens.pop_front()?;
+    self.line = token.line;
+    Some(token)
+  }
+
+  fn expect_token(&mut self) -> Result<Token, AssemblerError> {
+    self
+      .next_token()
+      .ok_or_else(|| self.error(AssemblerErrorKind::UnexpectedEnd))
+  }
+
+  fn expect_keyword(&mut self, keyword: &str) -> Result<(), AssemblerError> {
+    let token = self.expect_token()?;
+    match token.text == keyword {
+      true => Ok(()),
+      false => Err(self.error(AssemblerErrorKind::UnexpectedToken(token.text))),
+    }
+  }
+
+  fn expect_name(&mut self) -> Result<String, AssemblerError> {
+    let token = self.expect_token()?;
+    let is_valid = token
+      .text
+      .chars()
+      .all(|char| char.is_ascii_alphanumeric() || char == '_' || char == '-')
+      && parse_number(&token.text).is_none();
+    match is_valid {
+      true => Ok(token.text),
+      false => Err(self.error(AssemblerErrorKind::UnexpectedToken(token.text))),
+    }
+  }
+
+  fn define_symbol(&mut self, name: String, symbol: Symbol) -> Result<(), AssemblerError> {
+    if self.symbols.contains_key(&name) || self.macros.contains_key(&name) {
+      return Err(self.error(AssemblerErrorKind::DuplicateSymbol(name)));
+    }
+    self.symbols.insert(name, symbol);
+    Ok(())
+  }
+
+  fn resolve_register(&self, text: &str) -> Option<Nibble> {
+    match self.symbols.get(text) {
+      Some(&Symbol::Alias(register)) => Some(register),
+      _ => parse_register(text),
+    }
+  }
+
+  fn expect_register(&mut self) -> Result<Nibble, AssemblerError> {
+    let token = self.expect_token()?;
+    self
+      .resolve_register(&token.text)
+      .ok_or_else(|| self.error(AssemblerErrorKind::InvalidRegister(token.text)))
+  }
+
+  fn resolve_value(&self, text: &str) -> Option<i32> {
+    match self.symbols.get(text) {
+      Some(&Symbol::Const(value)) => Some(value),
+      Some(&Symbol::Label(address)) => Some(address.as_u16() as i32),
+      _ => parse_number(text),
+    }
+  }
+
+  fn expect_value(&mut self, min: i32, max: i32) -> Result<i32, AssemblerError> {
+    let token = self.expect_token()?;
+    let value = self
+      .resolve_value(&token.text)
+      .ok_or_else(|| self.error(AssemblerErrorKind::UnknownIdentifier(token.text.clone())))?;
+    match (min..=max).contains(&value) {
+      true => Ok(value),
+      false => Err(self.error(AssemblerErrorKind::ValueOutOfRange(token.text))),
+    }
+  }
+
+  fn expect_byte(&mut self) -> Result<u8, AssemblerError> {
+    Ok(self.expect_value(i8::MIN as i32, u8::MAX as i32)? as u8)
+  }
+
+  fn expect_nibble(&mut self) -> Result<Nibble, AssemblerError> {
+    let value = self.expect_value(0, Nibble::SIZE as i32 - 1)?;
+    Ok(Nibble::try_from(value as u8).unwrap())
+  }
+
+  fn emit_byte(&mut self, value: u8) {
+    if self.position >= self.image.len() {
+      self.image.resize(self.position + 1, 0);
+    }
+    self.image[self.position] = value;
+    self.position += 1;
+  }
+
+  fn emit_instruction(&mut self, instruction: Instruction) -> Result<(), AssemblerError> {
+    let bytes = instruction.encode().map_err(|err| match err {
+      InterpreterError::AddressOverflow(address) => self.error(
+        AssemblerErrorKind::ValueOutOfRange(format!("0x{:X}", address.as_u16())),
+      ),
+      _ => self.error(AssemblerErrorKind::UnexpectedToken(format!(
+        "{instruction:?}"
+      ))),
+    })?;
+    self.source_lines.insert(self.get_address(), self.line);
+    bytes.iter().for_each(|&byte| self.emit_byte(byte));
+    Ok(())
+  }
+
+  fn emit_address_instruction(
+    &mut self,
+    instruction: fn(Address) -> Instruction,
+  ) -> Result<(), AssemblerError> {
+    let token = self.expect_token()?;
+    let offset = self.position;
+    self.emit_instruction(instruction(Address::default()))?;
+    self.patch_or_defer(offset, token, FixupKind::Short)
+  }
+
+  fn emit_long_address(&mut self) -> Result<(), AssemblerError> {
+    let token = self.expect_token()?;
+    let offset = self.position + ADDRESS_BYTE_STEP;
+    self.emit_instruction(Instruction::SetLongAddressRegister(Address::default()))?;
+    self.patch_or_defer(offset, token, FixupKind::Long)
+  }
+
+  fn patch_or_defer(
+    &mut self,
+    offset: usize,
+    token: Token,
+    kind: FixupKind,
+  ) -> Result<(), AssemblerError> {
+    match self.resolve_value(&token.text) {
+      Some(value) => self.patch_address(offset, value, kind, &token.text),
+      None if parse_register(&token.text).is_none() => {
+        self.fixups.push(Fixup {
+          offset,
+          name: token.text,
+          kind,
+          line: token.line,
+        });
+        Ok(())
+      }
+      None => Err(self.error(AssemblerErrorKind::UnexpectedToken(token.text))),
+    }
+  }
+
+  fn patch_address(
+    &mut self,
+    offset: usize,
+    value: i32,
+    kind: FixupKind,
+    text: &str,
+  ) -> Result<(), AssemblerError> {
+    let max = match kind {
+      FixupKind::Short => SHORT_ADDRESS_MAX,
+      FixupKind::Long => u16::MAX as i32,
+    };
+    if !(0..=max).contains(&value) {
+      return Err(self.error(AssemblerErrorKind::ValueOutOfRange(String::from(text))));
+    }
+    let current = u16::from_be_bytes([self.image[offset], self.image[offset + 1]]);
+    let patched = (current | value as u16).to_be_bytes();
+    self.image[offset..offset + 2].copy_from_slice(&patched);
+    Ok(())
+  }
+
+  fn expect_condition(&mut self) -> Result<Condition, AssemblerError> {
+    let x = self.expect_register()?;
+    let operator = self.expect_token()?;
+    match operator.text.as_str() {
+      "key" => return Ok(Condition::KeyDown(x)),
+      "-key" => return Ok(Condition::KeyUp(x)),
+      "==" | "!=" => {}
+      _ => return Err(self.error(AssemblerErrorKind::UnexpectedToken(operator.text))),
+    }
+
+    let is_equal = operator.text == "==";
+    let operand = self.expect_token()?;
+    let condition = match self.resolve_register(&operand.text) {
+      Some(y) => Condition::RegistersEqual(x, y),
+      None => {
+        self.tokens.push_front(operand);
+        Condition::Equal(x, self.expect_byte()?)
+      }
+    };
+    Ok(match is_equal {
+      true => condition,
+      false => condition.negate(),
+    })
+  }
+
+  fn process_statement(&mut self, token: Token) -> Result<(), AssemblerError> {
+    let text = token.text.clone();
+    match text.as_str() {
+      /* Директивы */
+      ":" => {
+        let name = self.expect_name()?;
+        self.define_symbol(name, Symbol::Label(self.get_address()))?;
+      }
+      ":alias" => {
+        let name = self.expect_name()?;
+        let register = self.expect_register()?;
+        self.define_symbol(name, Symbol::Alias(register))?;
+      }
+      ":const" => {
+        let name = self.expect_name()?;
+        let value = self.expect_value(i32::MIN, i32::MAX)?;
+        self.define_symbol(name, Symbol::Const(value))?;
+      }
+      ":macro" => self.process_macro_definition()?,
+      ":org" => {
+        let value = self.expect_value(DEFAULT_LOAD_POINT.as_u16() as i32, u16::MAX as i32)?;
+        self.position = (value - DEFAULT_LOAD_POINT.as_u16() as i32) as usize;
+      }
+      ":call" => self.emit_address_instruction(Instruction::Call)?,
+      ":byte" => {
+        let value = self.expect_byte()?;
+        self.emit_byte(value);
+      }
+      /* Управляющие конструкции */
+      "if" => self.process_if()?,
+      "else" => match self.blocks.pop() {
+        Some(Block::If { jump_offset, line }) => {
+          let else_jump_offset = self.position;
+          self.emit_instruction(Instruction::Jump(Address::default()))?;
+          let address = self.get_address().as_u16() as i32;
+          self.patch_address(jump_offset, address, FixupKind::Short, "else")?;
+          self.blocks.push(Block::Else {
+            jump_offset: else_jump_offset,
+            line,
+          });
+        }
+        _ => return Err(self.error(AssemblerErrorKind::UnbalancedBlock(String::from("else")))),
+      },
+      "end" => match self.blocks.pop() {
+        Some(Block::If { jump_offset, .. }) | Some(Block::Else { jump_offset, .. }) => {
+          let address = self.get_address().as_u16() as i32;
+          self.patch_address(jump_offset, address, FixupKind::Short, "end")?;
+        }
+        _ => return Err(self.error(AssemblerErrorKind::UnbalancedBlock(String::from("end")))),
+      },
+      "loop" => self.blocks.push(Block::Loop {
+        start: self.get_address(),
+        breaks: Vec::new(),
+        line: token.line,
+      }),
+      "while" => {
+        let condition = self.expect_condition()?;
+        self.emit_instruction(condition.get_skip_instruction())?;
+        let break_offset = self.position;
+        self.emit_instruction(Instruction::Jump(Address::default()))?;
+        match self
+          .blocks
+          .iter_mut()
+          .rev()
+          .find(|block| matches!(block, Block::Loop { .. }))
+        {
+          Some(Block::Loop { breaks, .. }) => breaks.push(break_offset),
+          _ => return Err(self.error(AssemblerErrorKind::UnbalancedBlock(String::from("while")))),
+        }
+      }
+      "again" => match self.blocks.pop() {
+        Some(Block::Loop { start, breaks, .. }) => {
+          self.emit_instruction(Instruction::Jump(start))?;
+          let end = self.get_address().as_u16() as i32;
+          for offset in breaks {
+            self.patch_address(offset, end, FixupKind::Short, "again")?;
+          }
+        }
+        _ => return Err(self.error(AssemblerErrorKind::UnbalancedBlock(String::from("again")))),
+      },
+      /* Инструкции без аргументов */
+      "clear" => self.emit_instruction(Instruction::ClearScreen)?,
+      "return" | ";" => self.emit_instruction(Instruction::Return)?,
+      "scroll-right" => self.emit_instruction(Instruction::ScrollRight)?,
+      "scroll-left" => self.emit_instruction(Instruction::ScrollLeft)?,
+      "exit" => self.emit_instruction(Instruction::Exit)?,
+      "lores" => self.emit_instruction(Instruction::LowResolution)?,
+      "hires" => self.emit_instruction(Instruction::HighResolution)?,
+      "audio" => self.emit_instruction(Instruction::LoadAudioPattern)?,
+      /* Инструкции с аргументами */
+      "scroll-down" => {
+        let n = self.expect_nibble()?;
+        self.emit_instruction(Instruction::ScrollDown(n))?;
+      }
+      "scroll-up" => {
+        let n = self.expect_nibble()?;
+        self.emit_instruction(Instruction::ScrollUp(n))?;
+      }
+      "plane" => {
+        let n = self.expect_nibble()?;
+        self.emit_instruction(Instruction::SelectPlanes(n))?;
+      }
+      "bcd" => {
+        let x = self.expect_register()?;
+        self.emit_instruction(Instruction::RegisterToBCD(x))?;
+      }
+      "save" | "load" => self.process_save_load(token.text == "save")?,
+      "saveflags" => {
+        let x = self.expect_register()?;
+        self.emit_instruction(Instruction::SaveFlags(x))?;
+      }
+      "loadflags" => {
+        let x = self.expect_register()?;
+        self.emit_instruction(Instruction::LoadFlags(x))?;
+      }
+      "sprite" => {
+        let x = self.expect_register()?;
+        let y = self.expect_register()?;
+        let n = self.expect_nibble()?;
+        self.emit_instruction(Instruction::DrawSprite(x, y, n))?;
+      }
+      "jump" => self.emit_address_instruction(Instruction::Jump)?,
+      "jump0" => self.emit_address_instruction(Instruction::JumpV0)?,
+      "delay" | "buzzer" | "pitch" => {
+        self.expect_keyword(":=")?;
+        let x = self.expect_register()?;
+        let instruction = match token.text.as_str() {
+          "delay" => Instruction::SetDelayTimer(x),
+          "buzzer" => Instruction::SetSoundTimer(x),
+          _ => Instruction::SetPitch(x),
+        };
+        self.emit_instruction(instruction)?;
+      }
+      "i" => self.process_index_register()?,
+      text => {
+        if let Some(x) = self.resolve_register(text) {
+          return self.process_register_operation(x);
+        }
+        if let Some(definition) = self.macros.get(text).cloned() {
+          return self.expand_macro(&token, definition);
+        }
+        if text.starts_with(':') {
+          return Err(self.error(AssemblerErrorKind::UnsupportedDirective(token.text)));
+        }
+        self.tokens.push_front(token);
+        match self.resolve_value(text).is_some() && self.get_label(text).is_none() {
+          true => {
+            let value = self.expect_byte()?;
+            self.emit_byte(value);
+          }
+          false => self.emit_address_instruction(Instruction::Call)?,
+        }
+      }
+    }
+    Ok(())
+  }
+
+  fn get_label(&self, name: &str) -> Option<Address> {
+    match self.symbols.get(name) {
+      Some(&Symbol::Label(address)) => Some(address),
+      _ => None,
+    }
+  }
+
+  fn process_macro_definition(&mut self) -> Result<(), AssemblerError> {
+    let name = self.expect_name()?;
+    if self.symbols.contains_key(&name) || self.macros.contains_key(&name) {
+      return Err(self.error(AssemblerErrorKind::DuplicateSymbol(name)));
+    }
+
+    let mut args = Vec::new();
+    loop {
+      let token = self.expect_token()?;
+      if token.text == "{" {
+        break;
+      }
+      self.tokens.push_front(token);
+      args.push(self.expect_name()?);
+    }
+
+    let mut body = Vec::new();
+    let mut depth = 1;
+    loop {
+      let token = self.expect_token()?;
+      match token.text.as_str() {
+        "{" => depth += 1,
+        "}" => depth -= 1,
+        _ => {}
+      }
+      if depth == 0 {
+        break;
+      }
+      body.push(token);
+    }
+
+    self.macros.insert(name, Macro { args, body });
+    Ok(())
+  }
+
+  fn expand_macro(&mut self, token: &Token, definition: Macro) -> Result<(), AssemblerError> {
+    if token.depth >= MAX_MACRO_DEPTH {
+      return Err(self.error(AssemblerErrorKind::MacroTooDeep(token.text.clone())));
+    }
+
+    let mut args = HashMap::new();
+    for arg in &definition.args {
+      args.insert(arg, self.expect_token()?.text);
+    }
+
+    definition.body.iter().rev().for_each(|body_token| {
+      let text = args.get(&body_token.text).unwrap_or(&body_token.text);
+      self.tokens.push_front(Token {
+        text: text.clone(),
+        line: token.line,
+        depth: token.depth + 1,
+      });
+    });
+    Ok(())
+  }
+
+  fn process_if(&mut self) -> Result<(), AssemblerError> {
+    let line = self.line;
+    let condition = self.expect_condition()?;
+    let keyword = self.expect_token()?;
+    match keyword.text.as_str() {
+      "then" => self.emit_instruction(condition.negate().get_skip_instruction())?,
+      "begin" => {
+        self.emit_instruction(condition.get_skip_instruction())?;
+        self.blocks.push(Block::If {
+          jump_offset: self.position,
+          line,
+        });
+        self.emit_instruction(Instruction::Jump(Address::default()))?;
+      }
+      _ => return Err(self.error(AssemblerErrorKind::UnexpectedToken(keyword.text))),
+    }
+    Ok(())
+  }
+
+  fn process_save_load(&mut self, is_save: bool) -> Result<(), AssemblerError> {
+    let x = self.expect_register()?;
+    let Some(separator) = self.tokens.front().filter(|token| token.text == "-") else {
+      let instruction = match is_save {
+        true => Instruction::WriteRegistersToMem(x),
+        false => Instruction::LoadRegistersFromMem(x),
+      };
+      return self.emit_instruction(instruction);
+    };
+
+    self.line = separator.line;
+    self.tokens.pop_front();
+    let y = self.expect_register()?;
+    let instruction = match is_save {
+      true => Instruction::WriteRegisterRangeToMem(x, y),
+      false => Instruction::LoadRegisterRangeFromMem(x, y),
+    };
+    self.emit_instruction(instruction)
+  }
+
+  fn process_index_register(&mut self) -> Result<(), AssemblerError> {
+    let operator = self.expect_token()?;
+    match operator.text.as_str() {
+      "+=" => {
+        let x = self.expect_register()?;
+        self.emit_instruction(Instruction::AddAddressRegister(x))?;
+      }
+      ":=" => {
+        let operand = self.expect_token()?;
+        match operand.text.as_str() {
+          "hex" => {
+            let x = self.expect_register()?;
+            self.emit_instruction(Instruction::SetAddressRegisterToDigit(x))?;
+          }
+          "bighex" => {
+            let x = self.expect_register()?;
+            self.emit_instruction(Instruction::SetAddressRegisterToBigDigit(x))?;
+          }
+          "long" => self.emit_long_address()?,
+          _ => {
+            self.tokens.push_front(operand);
+            self.emit_address_instruction(Instruction::SetAddressRegister)?;
+          }
+        }
+      }
+      _ => return Err(self.error(AssemblerErrorKind::UnexpectedToken(operator.text))),
+    }
+    Ok(())
+  }
+
+  fn process_register_operation(&mut self, x: Nibble) -> Result<(), AssemblerError> {
+    let operator = self.expect_token()?;
+    let operand = self.expect_token()?;
+    let y = self.resolve_register(&operand.text);
+
+    let instruction = match (operator.text.as_str(), y) {
+      (":=", Some(y)) => Instruction::CopyRegister(x, y),
+      ("|=", Some(y)) => Instruction::OrRegisters(x, y),
+      ("&=", Some(y)) => Instruction::AndRegisters(x, y),
+      ("^=", Some(y)) => Instruction::XorRegisters(x, y),
+      ("+=", Some(y)) => Instruction::AddRegisters(x, y),
+      ("-=", Some(y)) => Instruction::SubRegisters(x, y),
+      (">>=", Some(y)) => Instruction::RShiftRegisters(x, y),
+      ("=-", Some(y)) => Instruction::SubRegisterReversed(x, y),
+      ("<<=", Some(y)) => Instruction::LShiftRegisters(x, y),
+      (":=", None) => match operand.text.as_str() {
+        "random" => Instruction::SetRegisterRandom(x, self.expect_byte()?),
+        "delay" => Instruction::GetDelayTimer(x),
+        "key" => Instruction::WaitForKeyDown(x),
+        _ => {
+          self.tokens.push_front(operand);
+          Instruction::SetRegister(x, self.expect_byte()?)
+        }
+      },
+      ("+=", None) => {
+        self.tokens.push_front(operand);
+        Instruction::AddRegister(x, self.expect_byte()?)
+      }
+      _ => return Err(self.error(AssemblerErrorKind::UnexpectedToken(operator.text))),
+    };
+    self.emit_instruction(instruction)
+  }
+
+  fn finish(mut self) -> Result<Program, AssemblerError> {
+    if let Some(block) = self.blocks.last() {
+      let (line, name) = match block {
+        Block::If { line, .. } | Block::Else { line, .. } => (*line, "if"),
+        Block::Loop { line, .. } => (*line, "loop"),
+      };
+      return Err(AssemblerError::new(
+        line,
+        AssemblerErrorKind::UnbalancedBlock(String::from(name)),
+      ));
+    }
+
+    for fixup in std::mem::take(&mut self.fixups) {
+      self.line = fixup.line;
+      let address = self
+        .get_label(&fixup.name)
+        .ok_or_else(|| self.error(AssemblerErrorKind::UnknownIdentifier(fixup.name.clone())))?;
+      self.patch_address(
+        fixup.offset,
+        address.as_u16() as i32,
+        fixup.kind,
+        &fixup.name,
+      )?;
+    }
+
+    Ok(Program {
+      image: self.image,
+      load_point: DEFAULT_LOAD_POINT,
+      symbols: self.symbols,
+      source_lines: self.source_lines,
+    })
+  }
+}
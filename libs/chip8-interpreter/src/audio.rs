@@ -0,0 +1,201 @@
+use std::{
+  collections::VecDeque,
+  io::{self, Write},
+  time::Duration,
+};
+
+pub const DEFAULT_SAMPLE_RATE: u32 = 44100;
+pub const DEFAULT_TONE_FREQUENCY: f32 = 440.0;
+pub const DEFAULT_VOLUME: f32 = 0.25;
+
+const NANOS_PER_SECOND: u128 = 1_000_000_000;
+
+/// Parameters of the generated sound.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct AudioConfig {
+  /// Samples per second.
+  pub sample_rate: u32,
+  /// Frequency of the square wave in Hz.
+  pub frequency: f32,
+  /// Amplitude from 0.0 to 1.0.
+  pub volume: f32,
+  /// Time the tone takes to fade in and out, which avoids clicks on its edges.
+  pub envelope: Duration,
+  /// How much audio is kept until it is pulled. Older samples are dropped, `Duration::MAX`
+  /// keeps everything.
+  pub buffer_duration: Duration,
+}
+
+impl Default for AudioConfig {
+  fn default() -> Self {
+    Self {
+      sample_rate: DEFAULT_SAMPLE_RATE,
+      frequency: DEFAULT_TONE_FREQUENCY,
+      volume: DEFAULT_VOLUME,
+      envelope: Duration::from_millis(5),
+      buffer_duration: Duration::from_millis(500),
+    }
+  }
+}
+
+/// Bounded queue of mono 16-bit samples consumed by the host audio backend.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct AudioBuffer {
+  samples: VecDeque<i16>,
+  capacity: usize,
+}
+
+impl AudioBuffer {
+  pub fn new(capacity: usize) -> Self {
+    Self {
+      samples: VecDeque::new(),
+      capacity: capacity.max(1),
+    }
+  }
+
+  pub fn get_capacity(&self) -> usize {
+    self.capacity
+  }
+
+  pub fn len(&self) -> usize {
+    self.samples.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.samples.is_empty()
+  }
+
+  pub fn push(&mut self, sample: i16) {
+    if self.samples.len() == self.capacity {
+      self.samples.pop_front();
+    }
+    self.samples.push_back(sample);
+  }
+
+  /// Fills `output` with the oldest samples and silence after them. Returns the number of
+  /// samples taken from the buffer.
+  pub fn pull(&mut self, output: &mut [i16]) -> usize {
+    let count = output.len().min(self.samples.len());
+    output
+      .iter_mut()
+      .zip(self.samples.drain(..count).chain(std::iter::repeat(0)))
+      .for_each(|(output, sample)| *output = sample);
+    count
+  }
+
+  /// Takes all buffered samples.
+  pub fn drain(&mut self) -> Vec<i16> {
+    self.samples.drain(..).collect()
+  }
+
+  pub fn clear(&mut self) {
+    self.samples.clear();
+  }
+}
+
+/// Square wave generator driven by the state of the sound timer.
+#[derive(Clone, PartialEq, Debug)]
+pub struct AudioGenerator {
+  config: AudioConfig,
+  buffer: AudioBuffer,
+  phase: f32,
+  gain: f32,
+  elapsed_nanos: u128,
+  generated_count: u128,
+}
+
+impl AudioGenerator {
+  pub fn new(config: AudioConfig) -> Self {
+    let capacity =
+      config.buffer_duration.as_nanos() * config.sample_rate as u128 / NANOS_PER_SECOND;
+    Self {
+      config,
+      buffer: AudioBuffer::new(usize::try_from(capacity).unwrap_or(usize::MAX)),
+      phase: 0.0,
+      gain: 0.0,
+      elapsed_nanos: 0,
+      generated_count: 0,
+    }
+  }
+
+  pub fn get_config(&self) -> &AudioConfig {
+    &self.config
+  }
+
+  pub fn get_buffer(&self) -> &AudioBuffer {
+    &self.buffer
+  }
+
+  pub fn get_buffer_mut(&mut self) -> &mut AudioBuffer {
+    &mut self.buffer
+  }
+
+  /// Number of samples generated since the start.
+  pub fn get_generated_count(&self) -> u128 {
+    self.generated_count
+  }
+
+  /// Generates the samples for `duration` during which the tone is on or off.
+  pub fn advance(&mut self, duration: Duration, is_sounding: bool) {
+    self.elapsed_nanos += duration.as_nanos();
+    let target_count = self.elapsed_nanos * self.config.sample_rate as u128 / NANOS_PER_SECOND;
+
+    let sample_rate = self.config.sample_rate.max(1) as f32;
+    let phase_step = self.config.frequency / sample_rate;
+    let gain_step = match self.config.envelope.is_zero() {
+      true => 1.0,
+      false => 1.0 / (self.config.envelope.as_secs_f32() * sample_rate).max(1.0),
+    };
+    let target_gain = match is_sounding {
+      true => 1.0,
+      false => 0.0,
+    };
+
+    while self.generated_count < target_count {
+      self.gain = match self.gain < target_gain {
+        true => (self.gain + gain_step).min(target_gain),
+        false => (self.gain - gain_step).max(target_gain),
+      };
+      let level = match self.phase < 0.5 {
+        true => 1.0,
+        false => -1.0,
+      };
+      let amplitude = self.config.volume.clamp(0.0, 1.0) * self.gain * i16::MAX as f32;
+      self.buffer.push((level * amplitude) as i16);
+
+      self.phase = (self.phase + phase_step).fract();
+      self.generated_count += 1;
+    }
+  }
+}
+
+/// Writes mono 16-bit samples as a WAV file.
+pub fn write_wav<W: Write>(mut writer: W, sample_rate: u32, samples: &[i16]) -> io::Result<()> {
+  const CHANNELS: u16 = 1;
+  const BITS_PER_SAMPLE: u16 = 16;
+  let block_align = CHANNELS * BITS_PER_SAMPLE / 8;
+  let data_size = u32::try_from(samples.len() * block_align as usize)
+    .ok()
+    .filter(|size| *size <= u32::MAX - 36)
+    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Too many samples for WAV"))?;
+
+  writer.write_all(b"RIFF")?;
+  writer.write_all(&(36 + data_size).to_le_bytes())?;
+  writer.write_all(b"WAVEfmt ")?;
+  writer.write_all(&16u32.to_le_bytes())?;
+  writer.write_all(&1u16.to_le_bytes())?;
+  writer.write_all(&CHANNELS.to_le_bytes())?;
+  writer.write_all(&sample_rate.to_le_bytes())?;
+  writer.write_all(&(sample_rate * block_align as u32).to_le_bytes())?;
+  writer.write_all(&block_align.to_le_bytes())?;
+  writer.write_all(&BITS_PER_SAMPLE.to_le_bytes())?;
+  writer.write_all(b"data")?;
+  writer.write_all(&data_size.to_le_bytes())?;
+
+  let data = samples
+    .iter()
+    .flat_map(|sample| sample.to_le_bytes())
+    .collect::<Vec<_>>();
+  writer.write_all(&data)?;
+  writer.flush()
+}
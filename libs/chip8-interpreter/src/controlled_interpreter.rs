@@ -1,12 +1,47 @@
 use std::time::Duration;
 
-use crate::{errors::InterpreterError, Executable, Interpreter, Platform};
+use crate::{
+  audio::{AudioConfig, AudioGenerator},
+  debugger::{Breakpoint, Debugger, StopReason, Watchpoint},
+  errors::{InterpreterError, RewindError, SaveStateError},
+  rewind::RewindBuffer,
+  save_state::{SaveState, StateKind, StateReader, StateWriter, SAVE_STATE_VERSION},
+  timing::{
+    get_exact_frame_duration, get_frame_instruction_interval, get_vip_frame_duration,
+    get_vip_instruction_cycles, get_vip_instruction_duration, Timing,
+  },
+  Interpreter, InterpreterState, Platform,
+};
 
 pub struct ControlledInterpreter<P: Platform> {
   interpreter: Interpreter<P>,
   delay_timer: Timer,
   sound_timer: Timer,
   instruction_timer: Timer,
+  debugger: Debugger,
+  elapsed: Duration,
+  rewind: Option<Rewind<P>>,
+  audio: Option<AudioGenerator>,
+  timing: Timing,
+  frame_count: u64,
+  tick_count: u64,
+  frame_handler: Option<FrameHandler>,
+}
+
+type FrameHandler = Box<dyn FnMut(&FrameEvent) + Send>;
+
+/// End of a frame, reported right after the timers were decremented.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct FrameEvent {
+  /// Index of the frame that ended, starting from zero.
+  pub index: u64,
+  pub elapsed: Duration,
+  pub executed_count: u64,
+}
+
+struct Rewind<P: Platform> {
+  buffer: RewindBuffer,
+  take_snapshot: fn(&ControlledInterpreter<P>) -> Vec<u8>,
 }
 
 struct Timer {
@@ -26,6 +61,15 @@ impl Timer {
     self.interval.saturating_sub(self.cur_time)
   }
 
+  pub fn write_state(&self, writer: &mut StateWriter) {
+    writer.write_u64(self.cur_time.as_nanos() as u64);
+  }
+
+  pub fn read_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+    self.cur_time = Duration::from_nanos(reader.read_u64()?);
+    Ok(())
+  }
+
   pub fn add_time(&mut self, value: Duration) -> bool {
     self.cur_time = self.cur_time.saturating_add(value);
     if self.cur_time >= self.interval {
@@ -41,46 +85,219 @@ pub const DEFAULT_DELAY_TIMER_DURATION: Duration = Duration::from_millis(16);
 pub const DEFAULT_SOUND_TIMER_DURATION: Duration = Duration::from_millis(16);
 
 impl<P: Platform> ControlledInterpreter<P> {
-  pub fn new<E: Executable>(
-    platform: P,
-    executable: E,
+  pub fn new(
+    interpreter: Interpreter<P>,
     instruction_duration: Duration,
     delay_timer_duration: Duration,
     sound_timer_duration: Duration,
   ) -> Self {
     Self {
-      interpreter: Interpreter::new::<E>(platform, executable),
+      interpreter,
       delay_timer: Timer::new(delay_timer_duration),
       sound_timer: Timer::new(sound_timer_duration),
       instruction_timer: Timer::new(instruction_duration),
+      debugger: Debugger::default(),
+      elapsed: Duration::ZERO,
+      rewind: None,
+      audio: None,
+      timing: Timing::Fixed,
+      frame_count: 0,
+      tick_count: 0,
+      frame_handler: None,
     }
   }
 
+  /// Creates an interpreter that runs at the speed of the COSMAC VIP: instructions take as
+  /// many machine cycles as on the VIP and both timers tick at its 60 Hz frame rate.
+  pub fn new_cosmac_vip(interpreter: Interpreter<P>) -> Self {
+    let frame_duration = get_vip_frame_duration();
+    let mut res = Self::new(interpreter, frame_duration, frame_duration, frame_duration);
+    res.timing = Timing::CosmacVip;
+    res
+  }
+
+  /// Creates an interpreter that runs `instructions_per_frame` instructions in every frame of
+  /// exactly 1/60 s and decrements both timers once at the end of each frame.
+  pub fn new_frame_based(interpreter: Interpreter<P>, instructions_per_frame: u32) -> Self {
+    let instructions_per_frame = instructions_per_frame.max(1);
+    let frame_duration = get_exact_frame_duration(0);
+    let mut res = Self::new(
+      interpreter,
+      get_frame_instruction_interval(0, instructions_per_frame),
+      frame_duration,
+      frame_duration,
+    );
+    res.timing = Timing::FrameBased {
+      instructions_per_frame,
+    };
+    res
+  }
+
+  pub fn get_timing(&self) -> Timing {
+    self.timing
+  }
+
+  pub fn get_interpreter(&self) -> &Interpreter<P> {
+    &self.interpreter
+  }
+
+  pub fn get_interpreter_mut(&mut self) -> &mut Interpreter<P> {
+    &mut self.interpreter
+  }
+
+  pub fn get_state(&self) -> InterpreterState<'_> {
+    self.interpreter.get_state()
+  }
+
+  pub fn get_platform(&self) -> &P {
+    self.interpreter.get_platform()
+  }
+
   pub fn get_platform_mut(&mut self) -> &mut P {
     self.interpreter.get_platform_mut()
   }
 
-  pub fn simulate_one_instruction(&mut self) -> Result<(), InterpreterError> {
-    self.simulate_duration(self.instruction_timer.time_until_tick())
+  pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+    self.debugger.add_breakpoint(breakpoint);
   }
 
-  pub fn simulate_duration(&mut self, duration: Duration) -> Result<(), InterpreterError> {
+  pub fn remove_breakpoint(&mut self, breakpoint: Breakpoint) -> bool {
+    self.debugger.remove_breakpoint(breakpoint)
+  }
+
+  pub fn get_breakpoints(&self) -> &[Breakpoint] {
+    self.debugger.get_breakpoints()
+  }
+
+  pub fn clear_breakpoints(&mut self) {
+    self.debugger.clear_breakpoints();
+  }
+
+  pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+    self.debugger.add_watchpoint(watchpoint);
+  }
+
+  pub fn remove_watchpoint(&mut self, watchpoint: Watchpoint) -> bool {
+    self.debugger.remove_watchpoint(watchpoint)
+  }
+
+  pub fn get_watchpoints(&self) -> &[Watchpoint] {
+    self.debugger.get_watchpoints()
+  }
+
+  pub fn clear_watchpoints(&mut self) {
+    self.debugger.clear_watchpoints();
+  }
+
+  /// Total simulated time.
+  pub fn get_elapsed(&self) -> Duration {
+    self.elapsed
+  }
+
+  pub fn get_frame_duration(&self) -> Duration {
+    self.delay_timer.interval
+  }
+
+  /// Number of frames that ended since the start.
+  pub fn get_frame_count(&self) -> u64 {
+    self.frame_count
+  }
+
+  /// Calls `handler` at the end of every frame.
+  pub fn set_frame_handler<F: FnMut(&FrameEvent) + Send + 'static>(&mut self, handler: F) {
+    self.frame_handler = Some(Box::new(handler));
+  }
+
+  pub fn remove_frame_handler(&mut self) {
+    self.frame_handler = None;
+  }
+
+  /// Starts generating PCM audio from the sound timer.
+  pub fn enable_audio(&mut self, config: AudioConfig) {
+    self.audio = Some(AudioGenerator::new(config));
+  }
+
+  pub fn disable_audio(&mut self) {
+    self.audio = None;
+  }
+
+  pub fn get_audio(&self) -> Option<&AudioGenerator> {
+    self.audio.as_ref()
+  }
+
+  pub fn get_audio_mut(&mut self) -> Option<&mut AudioGenerator> {
+    self.audio.as_mut()
+  }
+
+  /// Runs the next instruction. After a sprite draw that waits for the display, the call
+  /// simulates the rest of the frame and returns once the following instruction has run.
+  pub fn simulate_one_instruction(&mut self) -> Result<StopReason, InterpreterError> {
+    let executed_count = self.get_state().get_executed_count();
+    loop {
+      let is_waiting_vblank = self.get_state().is_waiting_vblank();
+      self.update_timer_intervals();
+      let reason = self.simulate_duration(self.instruction_timer.time_until_tick())?;
+      let is_idle = is_waiting_vblank && self.get_state().get_executed_count() == executed_count;
+      if reason != StopReason::TimeElapsed || !is_idle {
+        return Ok(reason);
+      }
+    }
+  }
+
+  /// Runs until the end of the current frame. Instructions scheduled at the same time as the
+  /// end of the frame run too.
+  pub fn run_frame(&mut self) -> Result<StopReason, InterpreterError> {
+    self.update_timer_intervals();
+    self.simulate_duration(self.delay_timer.time_until_tick())
+  }
+
+  /// Simulates `duration` of time. Instruction ticks while a sprite draw waits for the display
+  /// run nothing. The COSMAC VIP timing stops the instruction timer instead and starts the next
+  /// instruction at the end of the frame, like the VIP interpreter that resumes after the
+  /// display interrupt.
+  pub fn simulate_duration(&mut self, duration: Duration) -> Result<StopReason, InterpreterError> {
     let mut remaining = duration;
 
     while remaining > Duration::ZERO {
+      self.take_rewind_snapshot();
+      self.update_timer_intervals();
+
+      let is_waiting_vblank = self.interpreter.get_state().is_waiting_vblank();
+      let is_instruction_timer_stopped = is_waiting_vblank && self.timing == Timing::CosmacVip;
+      let instruction_time = match is_instruction_timer_stopped {
+        true => Duration::MAX,
+        false => self.instruction_timer.time_until_tick(),
+      };
       let next_tick_duration = *[
         remaining,
         self.delay_timer.time_until_tick(),
         self.sound_timer.time_until_tick(),
-        self.instruction_timer.time_until_tick(),
+        instruction_time,
       ]
       .iter()
       .min()
       .unwrap();
 
-      if self.delay_timer.add_time(next_tick_duration) {
+      // The wait ends before the instruction tick when both fall on the end of the frame.
+      let is_idle_tick =
+        is_waiting_vblank && next_tick_duration < self.delay_timer.time_until_tick();
+      if next_tick_duration == instruction_time && !is_idle_tick {
+        let state = self.interpreter.get_state();
+        if let Some(breakpoint) = self.debugger.check_breakpoints(&state) {
+          return Ok(StopReason::BreakpointHit(breakpoint));
+        }
+      }
+
+      if let Some(audio) = &mut self.audio {
+        let is_sounding = self.interpreter.get_platform().get_sound_timer() > 0;
+        audio.advance(next_tick_duration, is_sounding);
+      }
+
+      let is_frame_end = self.delay_timer.add_time(next_tick_duration);
+      if is_frame_end {
         let platform = self.get_platform_mut();
         platform.set_delay_timer(platform.get_delay_timer().saturating_sub(1));
+        self.interpreter.notify_vblank();
       }
 
       if self.sound_timer.add_time(next_tick_duration) {
@@ -88,12 +305,236 @@ impl<P: Platform> ControlledInterpreter<P> {
         platform.set_sound_timer(platform.get_sound_timer().saturating_sub(1));
       }
 
-      if self.instruction_timer.add_time(next_tick_duration) {
+      self.elapsed += next_tick_duration;
+
+      if is_frame_end {
+        self.end_frame();
+        if is_instruction_timer_stopped {
+          self.instruction_timer.cur_time = Duration::ZERO;
+        }
+      }
+
+      if !is_instruction_timer_stopped && self.instruction_timer.add_time(next_tick_duration) {
+        self.tick_count = self.tick_count.wrapping_add(1);
+        let watched_values = self
+          .debugger
+          .read_watched_values(&self.interpreter.get_state());
         self.interpreter.run_next()?;
+
+        let state = self.interpreter.get_state();
+        if let Some(watchpoint) = self.debugger.check_watchpoints(&watched_values, &state) {
+          return Ok(StopReason::WatchpointHit(watchpoint));
+        }
       }
 
       remaining = remaining.saturating_sub(next_tick_duration);
     }
+    Ok(StopReason::TimeElapsed)
+  }
+
+  /// Sets the durations of the running instruction and frame when the timing changes them.
+  fn update_timer_intervals(&mut self) {
+    match self.timing {
+      Timing::Fixed => {}
+      Timing::CosmacVip => {
+        if self.instruction_timer.cur_time.is_zero() {
+          let cycles = get_vip_instruction_cycles(&self.interpreter.get_state());
+          self.instruction_timer.interval =
+            get_vip_instruction_duration(self.delay_timer.cur_time, cycles);
+        }
+      }
+      Timing::FrameBased {
+        instructions_per_frame,
+      } => {
+        self.instruction_timer.interval =
+          get_frame_instruction_interval(self.tick_count, instructions_per_frame);
+        let frame_duration = get_exact_frame_duration(self.frame_count);
+        self.delay_timer.interval = frame_duration;
+        self.sound_timer.interval = frame_duration;
+      }
+    }
+  }
+
+  fn end_frame(&mut self) {
+    let event = FrameEvent {
+      index: self.frame_count,
+      elapsed: self.elapsed,
+      executed_count: self.get_state().get_executed_count(),
+    };
+    self.frame_count = self.frame_count.wrapping_add(1);
+    if let Some(handler) = &mut self.frame_handler {
+      handler(&event);
+    }
+  }
+
+  fn take_rewind_snapshot(&mut self) {
+    let Some(rewind) = &self.rewind else {
+      return;
+    };
+    if !rewind.buffer.is_due(self.elapsed) {
+      return;
+    }
+
+    let state = (rewind.take_snapshot)(self);
+    let executed_count = self.get_state().get_executed_count();
+    if let Some(rewind) = &mut self.rewind {
+      rewind.buffer.push(self.elapsed, executed_count, state);
+    }
+  }
+}
+
+impl<P: Platform + SaveState> ControlledInterpreter<P> {
+  /// Starts keeping up to `capacity` snapshots taken every `interval` of simulated time.
+  pub fn enable_rewind(&mut self, interval: Duration, capacity: usize) {
+    self.rewind = Some(Rewind {
+      buffer: RewindBuffer::new(interval, capacity),
+      take_snapshot: |interpreter| {
+        let mut writer = StateWriter::new();
+        interpreter.write_state(&mut writer);
+        writer.into_bytes()
+      },
+    });
+  }
+
+  pub fn disable_rewind(&mut self) {
+    self.rewind = None;
+  }
+
+  pub fn is_rewind_enabled(&self) -> bool {
+    self.rewind.is_some()
+  }
+
+  /// How far back the machine can be rewound.
+  pub fn get_rewind_span(&self) -> Duration {
+    self
+      .rewind
+      .as_ref()
+      .and_then(|rewind| rewind.buffer.get_oldest())
+      .map_or(Duration::ZERO, |oldest| self.elapsed - oldest.elapsed)
+  }
+
+  /// Returns the machine to the state it had `duration` of simulated time ago.
+  ///
+  /// The part after the closest snapshot is simulated again without breakpoints, watchpoints,
+  /// tracing, audio and frame events, so it matches the original run only for deterministic platforms.
+  pub fn rewind(&mut self, duration: Duration) -> Result<(), RewindError> {
+    if !self.is_rewind_enabled() {
+      return Err(RewindError::Disabled);
+    }
+    let target = self
+      .elapsed
+      .checked_sub(duration)
+      .ok_or(RewindError::OutOfRange)?;
+    self.restore_snapshot(|snapshot| snapshot.elapsed <= target)?;
+    self.replay(|interpreter| interpreter.elapsed >= target, target)
+  }
+
+  /// Rewinds by the duration of one frame.
+  pub fn step_back_frame(&mut self) -> Result<(), RewindError> {
+    self.rewind(self.get_frame_duration())
+  }
+
+  /// Returns the machine to the state right before the last executed instruction.
+  pub fn step_back_instruction(&mut self) -> Result<(), RewindError> {
+    if !self.is_rewind_enabled() {
+      return Err(RewindError::Disabled);
+    }
+    let target = self
+      .get_state()
+      .get_executed_count()
+      .checked_sub(1)
+      .ok_or(RewindError::OutOfRange)?;
+    let end = self.elapsed;
+    self.restore_snapshot(|snapshot| snapshot.executed_count <= target)?;
+    self.replay(
+      |interpreter| interpreter.get_state().get_executed_count() >= target,
+      end,
+    )
+  }
+
+  fn restore_snapshot(
+    &mut self,
+    predicate: impl Fn(&crate::rewind::Snapshot) -> bool,
+  ) -> Result<(), RewindError> {
+    let rewind = self.rewind.as_mut().ok_or(RewindError::Disabled)?;
+    let index = rewind
+      .buffer
+      .find_latest(predicate)
+      .ok_or(RewindError::OutOfRange)?;
+    let (elapsed, state) = rewind.buffer.restore(index);
+
+    self.read_state(&mut StateReader::new(&state, SAVE_STATE_VERSION))?;
+    self.elapsed = elapsed;
+    Ok(())
+  }
+
+  fn replay(&mut self, is_done: impl Fn(&Self) -> bool, end: Duration) -> Result<(), RewindError> {
+    let debugger = std::mem::take(&mut self.debugger);
+    let trace_sink = self.interpreter.remove_trace_sink();
+    let audio = self.audio.take();
+    let frame_handler = self.frame_handler.take();
+
+    let mut res = Ok(());
+    while res.is_ok() && !is_done(self) && self.elapsed < end {
+      let step = self
+        .instruction_timer
+        .time_until_tick()
+        .min(end - self.elapsed);
+      res = self.simulate_duration(step).map(|_| ());
+    }
+
+    self.debugger = debugger;
+    self.interpreter.restore_trace_sink(trace_sink);
+    self.audio = audio;
+    self.frame_handler = frame_handler;
+    res.map_err(RewindError::from)
+  }
+}
+
+impl<P: Platform + SaveState> SaveState for ControlledInterpreter<P> {
+  const KIND: StateKind = StateKind::ControlledInterpreter;
+
+  fn write_state(&self, writer: &mut StateWriter) {
+    self.timing.write_state(writer);
+    self.interpreter.write_state(writer);
+    self.interpreter.get_platform().write_state(writer);
+    self.delay_timer.write_state(writer);
+    self.sound_timer.write_state(writer);
+    self.instruction_timer.write_state(writer);
+    writer.write_u64(self.instruction_timer.interval.as_nanos() as u64);
+    writer.write_u64(self.frame_count);
+    writer.write_u64(self.tick_count);
+  }
+
+  fn read_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+    if reader.get_version() >= 6 && Timing::read_state(reader)? != self.timing {
+      return Err(SaveStateError::TimingMismatch);
+    }
+    self.interpreter.read_state(reader)?;
+    self.interpreter.get_platform_mut().read_state(reader)?;
+    self.delay_timer.read_state(reader)?;
+    self.sound_timer.read_state(reader)?;
+    self.instruction_timer.read_state(reader)?;
+    if reader.get_version() >= 3 {
+      let interval = Duration::from_nanos(reader.read_u64()?);
+      // The fixed timing keeps the configured duration, the VIP one restores the duration of
+      // the instruction that was running.
+      if self.timing == Timing::CosmacVip {
+        self.instruction_timer.interval = interval;
+      }
+    }
+    if reader.get_version() >= 4 {
+      self.frame_count = reader.read_u64()?;
+      self.tick_count = reader.read_u64()?;
+    }
+    // Keeps the next tick within one interval, so damaged states can't stall the simulation.
+    for timer in [
+      &mut self.delay_timer,
+      &mut self.sound_timer,
+      &mut self.instruction_timer,
+    ] {
+      timer.cur_time = timer.cur_time.min(timer.interval);
+    }
     Ok(())
   }
 }
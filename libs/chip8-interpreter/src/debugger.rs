@@ -0,0 +1,150 @@
+use crate::{address::Address, nibble::Nibble, state::InterpreterState};
+
+/// Condition checked before an instruction runs.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum Breakpoint {
+  /// Stops when `PC` reaches the address.
+  Address(Address),
+  /// Stops before an instruction with exactly this opcode.
+  OpCode(u16),
+  /// Stops before every instruction whose opcode matches `value` in the bits set in `mask`,
+  /// e.g. `value: 0xD000, mask: 0xF000` stops on every `DXYN`.
+  OpCodePattern { value: u16, mask: u16 },
+}
+
+/// Condition checked after an instruction ran.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum Watchpoint {
+  /// Stops when any byte in `start..start + len` changes.
+  Memory { start: Address, len: usize },
+  /// Stops when the value of `vX` changes.
+  Register(Nibble),
+  /// Stops when the value of `I` changes.
+  IndexRegister,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum StopReason {
+  /// The whole requested duration was simulated.
+  TimeElapsed,
+  /// The instruction at `PC` has not run yet.
+  BreakpointHit(Breakpoint),
+  /// The instruction that triggered the watchpoint has already run.
+  WatchpointHit(Watchpoint),
+}
+
+impl Breakpoint {
+  fn is_hit(&self, state: &InterpreterState) -> bool {
+    let address = state.get_instruction_address();
+    let memory = state.get_memory();
+    let op_code = u16::from_be_bytes([memory[address], memory[address + 1]]);
+
+    match *self {
+      Breakpoint::Address(value) => address == value,
+      Breakpoint::OpCode(value) => op_code == value,
+      Breakpoint::OpCodePattern { value, mask } => op_code & mask == value & mask,
+    }
+  }
+}
+
+impl Watchpoint {
+  fn read_value(&self, state: &InterpreterState) -> Vec<u8> {
+    match *self {
+      Watchpoint::Memory { start, len } => {
+        let memory = state.get_memory();
+        (0..len)
+          .map(|offset| memory[start + offset as i16])
+          .collect()
+      }
+      Watchpoint::Register(index) => vec![state.get_register(index)],
+      Watchpoint::IndexRegister => state.get_index_register().as_u16().to_le_bytes().to_vec(),
+    }
+  }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub(crate) struct Debugger {
+  breakpoints: Vec<Breakpoint>,
+  watchpoints: Vec<Watchpoint>,
+  resumed_at: Option<u64>,
+}
+
+impl Debugger {
+  pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+    if !self.breakpoints.contains(&breakpoint) {
+      self.breakpoints.push(breakpoint);
+    }
+  }
+
+  pub fn remove_breakpoint(&mut self, breakpoint: Breakpoint) -> bool {
+    let len = self.breakpoints.len();
+    self.breakpoints.retain(|&item| item != breakpoint);
+    self.breakpoints.len() != len
+  }
+
+  pub fn get_breakpoints(&self) -> &[Breakpoint] {
+    &self.breakpoints
+  }
+
+  pub fn clear_breakpoints(&mut self) {
+    self.breakpoints.clear();
+  }
+
+  pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+    if !self.watchpoints.contains(&watchpoint) {
+      self.watchpoints.push(watchpoint);
+    }
+  }
+
+  pub fn remove_watchpoint(&mut self, watchpoint: Watchpoint) -> bool {
+    let len = self.watchpoints.len();
+    self.watchpoints.retain(|&item| item != watchpoint);
+    self.watchpoints.len() != len
+  }
+
+  pub fn get_watchpoints(&self) -> &[Watchpoint] {
+    &self.watchpoints
+  }
+
+  pub fn clear_watchpoints(&mut self) {
+    self.watchpoints.clear();
+  }
+
+  pub fn check_breakpoints(&mut self, state: &InterpreterState) -> Option<Breakpoint> {
+    // Resuming from a breakpoint must run the instruction it stopped on.
+    if self.resumed_at == Some(state.get_executed_count()) {
+      return None;
+    }
+
+    let hit = self
+      .breakpoints
+      .iter()
+      .find(|item| item.is_hit(state))
+      .copied();
+    if hit.is_some() {
+      self.resumed_at = Some(state.get_executed_count());
+    }
+    hit
+  }
+
+  pub fn read_watched_values(&self, state: &InterpreterState) -> Vec<Vec<u8>> {
+    self
+      .watchpoints
+      .iter()
+      .map(|item| item.read_value(state))
+      .collect()
+  }
+
+  pub fn check_watchpoints(
+    &self,
+    previous_values: &[Vec<u8>],
+    state: &InterpreterState,
+  ) -> Option<Watchpoint> {
+    self
+      .watchpoints
+      .iter()
+      .zip(previous_values)
+      .find(|(item, previous_value)| item.read_value(state) != **previous_value)
+      .map(|(&item, _)| item)
+  }
+}
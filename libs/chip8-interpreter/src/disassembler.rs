@@ -0,0 +1,440 @@
+use std::collections::BTreeMap;
+
+use crate::{
+  address::Address,
+  interpreter::{Instruction, OpCode},
+  memory::Memory,
+  mode::Mode,
+  nibble::Nibble,
+};
+
+const DATA_LINE_SIZE: usize = 4;
+const RAW_COLUMN_WIDTH: usize = 10;
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Default)]
+pub enum Syntax {
+  #[default]
+  Cowgod,
+  Octo,
+}
+
+/// Mnemonic of a single instruction with plain addresses instead of labels.
+pub fn format_instruction(instruction: Instruction, syntax: Syntax) -> String {
+  let disassembly = Disassembly {
+    lines: Vec::new(),
+    labels: BTreeMap::new(),
+  };
+  match syntax {
+    Syntax::Cowgod => disassembly.format_cowgod(instruction),
+    Syntax::Octo => disassembly.format_octo(instruction),
+  }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+enum LabelKind {
+  Data,
+  Jump,
+  Subroutine,
+  Entry,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ListingLine {
+  address: Address,
+  bytes: Vec<u8>,
+  instruction: Option<Instruction>,
+}
+
+impl ListingLine {
+  pub fn get_address(&self) -> Address {
+    self.address
+  }
+
+  pub fn get_bytes(&self) -> &[u8] {
+    &self.bytes
+  }
+
+  pub fn is_code(&self) -> bool {
+    self.instruction.is_some()
+  }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Disassembly {
+  lines: Vec<ListingLine>,
+  labels: BTreeMap<Address, String>,
+}
+
+impl Disassembly {
+  pub fn new(image: &[u8], load_point: Address, entry_point: Address, mode: Mode) -> Self {
+    let image = &image[..image.len().min(Address::SIZE)];
+    let analysis = Analysis::new(image, load_point, mode);
+    let (instructions, label_kinds) = analysis.follow_flow(entry_point);
+
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    while offset < image.len() {
+      let address = analysis.get_address(offset);
+      if let Some(&instruction) = instructions.get(&offset) {
+        let size = instruction.get_size() as usize;
+        lines.push(ListingLine {
+          address,
+          bytes: image[offset..offset + size].to_vec(),
+          instruction: Some(instruction),
+        });
+        offset += size;
+        continue;
+      }
+
+      let data_len = (offset..image.len())
+        .take(DATA_LINE_SIZE)
+        .enumerate()
+        .take_while(|&(index, data_offset)| {
+          index == 0
+            || !instructions.contains_key(&data_offset)
+              && !label_kinds.contains_key(&analysis.get_address(data_offset))
+        })
+        .count();
+      lines.push(ListingLine {
+        address,
+        bytes: image[offset..offset + data_len].to_vec(),
+        instruction: None,
+      });
+      offset += data_len;
+    }
+
+    let labels = lines
+      .iter()
+      .filter_map(|line| {
+        let kind = *label_kinds.get(&line.address)?;
+        let prefix = match (kind, line.is_code()) {
+          (LabelKind::Entry, _) => return Some((line.address, String::from("main"))),
+          (LabelKind::Subroutine, _) => "sub",
+          (LabelKind::Jump, _) | (LabelKind::Data, true) => "label",
+          (LabelKind::Data, false) => "data",
+        };
+        Some((
+          line.address,
+          format!("{prefix}_{:04X}", line.address.as_u16()),
+        ))
+      })
+      .collect();
+
+    Self { lines, labels }
+  }
+
+  pub fn from_memory(
+    memory: &Memory,
+    start: Address,
+    len: usize,
+    entry_point: Address,
+    mode: Mode,
+  ) -> Self {
+    let start_index = start.as_usize().min(memory.len());
+    let end_index = start_index.saturating_add(len).min(memory.len());
+    Self::new(
+      &memory.as_slice()[start_index..end_index],
+      start,
+      entry_point,
+      mode,
+    )
+  }
+
+  pub fn get_lines(&self) -> &[ListingLine] {
+    &self.lines
+  }
+
+  pub fn get_labels(&self) -> &BTreeMap<Address, String> {
+    &self.labels
+  }
+
+  pub fn get_label(&self, address: Address) -> Option<&str> {
+    self.labels.get(&address).map(String::as_str)
+  }
+
+  pub fn format_line(&self, line: &ListingLine, syntax: Syntax) -> String {
+    let Some(instruction) = line.instruction else {
+      let bytes = line.bytes.iter().map(|byte| format!("0x{byte:02X}"));
+      return match syntax {
+        Syntax::Cowgod => format!("DB {}", bytes.collect::<Vec<_>>().join(", ")),
+        Syntax::Octo => bytes.collect::<Vec<_>>().join(" "),
+      };
+    };
+
+    match syntax {
+      Syntax::Cowgod => self.format_cowgod(instruction),
+      Syntax::Octo => self.format_octo(instruction),
+    }
+  }
+
+  pub fn to_listing(&self, syntax: Syntax) -> String {
+    let mut res = String::new();
+    self.lines.iter().for_each(|line| {
+      if let Some(label) = self.get_label(line.address) {
+        match syntax {
+          Syntax::Cowgod => res.push_str(&format!("{label}:\n")),
+          Syntax::Octo => res.push_str(&format!(": {label}\n")),
+        }
+      }
+
+      let raw = line
+        .bytes
+        .chunks(2)
+        .map(|chunk| chunk.iter().map(|byte| format!("{byte:02X}")).collect())
+        .collect::<Vec<String>>()
+        .join(" ");
+      res.push_str(&format!(
+        "{:04X}  {raw:<RAW_COLUMN_WIDTH$}  {}\n",
+        line.address.as_u16(),
+        self.format_line(line, syntax),
+      ));
+    });
+    res
+  }
+
+  fn format_address(&self, address: Address) -> String {
+    match self.get_label(address) {
+      Some(label) => String::from(label),
+      None => format!("0x{:03X}", address.as_u16()),
+    }
+  }
+
+  fn format_cowgod(&self, instruction: Instruction) -> String {
+    let reg = |index: Nibble| format!("V{:X}", index.as_u8());
+    match instruction {
+      /* Условия */
+      Instruction::SkipIfEqual(x, value) => format!("SE {}, 0x{value:02X}", reg(x)),
+      Instruction::SkipIfNotEqual(x, value) => format!("SNE {}, 0x{value:02X}", reg(x)),
+      Instruction::SkipIfRegistersEqual(x, y) => format!("SE {}, {}", reg(x), reg(y)),
+      Instruction::SkipIfRegistersNotEqual(x, y) => format!("SNE {}, {}", reg(x), reg(y)),
+      Instruction::SkipIfKeyDown(x) => format!("SKP {}", reg(x)),
+      Instruction::SkipIfKeyUp(x) => format!("SKNP {}", reg(x)),
+      /* Запись регистров */
+      Instruction::SetRegister(x, value) => format!("LD {}, 0x{value:02X}", reg(x)),
+      Instruction::CopyRegister(x, y) => format!("LD {}, {}", reg(x), reg(y)),
+      Instruction::SetAddressRegister(address) => {
+        format!("LD I, {}", self.format_address(address))
+      }
+      Instruction::SetLongAddressRegister(address) => {
+        format!("LD I, LONG {}", self.format_address(address))
+      }
+      Instruction::SetRegisterRandom(x, value) => format!("RND {}, 0x{value:02X}", reg(x)),
+      Instruction::GetDelayTimer(x) => format!("LD {}, DT", reg(x)),
+      Instruction::WriteRegistersToMem(x) => format!("LD [I], {}", reg(x)),
+      Instruction::LoadRegistersFromMem(x) => format!("LD {}, [I]", reg(x)),
+      Instruction::WriteRegisterRangeToMem(x, y) => format!("LD [I], {}-{}", reg(x), reg(y)),
+      Instruction::LoadRegisterRangeFromMem(x, y) => format!("LD {}-{}, [I]", reg(x), reg(y)),
+      Instruction::RegisterToBCD(x) => format!("LD B, {}", reg(x)),
+      Instruction::SetAddressRegisterToDigit(x) => format!("LD F, {}", reg(x)),
+      Instruction::SetAddressRegisterToBigDigit(x) => format!("LD HF, {}", reg(x)),
+      Instruction::SaveFlags(x) => format!("LD R, {}", reg(x)),
+      Instruction::LoadFlags(x) => format!("LD {}, R", reg(x)),
+      /* Математические операции */
+      Instruction::AddRegister(x, value) => format!("ADD {}, 0x{value:02X}", reg(x)),
+      Instruction::OrRegisters(x, y) => format!("OR {}, {}", reg(x), reg(y)),
+      Instruction::AndRegisters(x, y) => format!("AND {}, {}", reg(x), reg(y)),
+      Instruction::XorRegisters(x, y) => format!("XOR {}, {}", reg(x), reg(y)),
+      Instruction::AddAddressRegister(x) => format!("ADD I, {}", reg(x)),
+      Instruction::AddRegisters(x, y) => format!("ADD {}, {}", reg(x), reg(y)),
+      Instruction::SubRegisters(x, y) => format!("SUB {}, {}", reg(x), reg(y)),
+      Instruction::RShiftRegisters(x, y) => format!("SHR {}, {}", reg(x), reg(y)),
+      Instruction::SubRegisterReversed(x, y) => format!("SUBN {}, {}", reg(x), reg(y)),
+      Instruction::LShiftRegisters(x, y) => format!("SHL {}, {}", reg(x), reg(y)),
+      /* Экран */
+      Instruction::ClearScreen => String::from("CLS"),
+      Instruction::DrawSprite(x, y, n) => format!("DRW {}, {}, {}", reg(x), reg(y), n.as_u8()),
+      Instruction::ScrollDown(n) => format!("SCD {}", n.as_u8()),
+      Instruction::ScrollUp(n) => format!("SCU {}", n.as_u8()),
+      Instruction::ScrollRight => String::from("SCR"),
+      Instruction::ScrollLeft => String::from("SCL"),
+      Instruction::LowResolution => String::from("LOW"),
+      Instruction::HighResolution => String::from("HIGH"),
+      Instruction::SelectPlanes(n) => format!("PLANE {}", n.as_u8()),
+      /* Таймеры */
+      Instruction::SetDelayTimer(x) => format!("LD DT, {}", reg(x)),
+      Instruction::SetSoundTimer(x) => format!("LD ST, {}", reg(x)),
+      /* Звук */
+      Instruction::LoadAudioPattern => String::from("AUDIO"),
+      Instruction::SetPitch(x) => format!("PITCH {}", reg(x)),
+      /* Клавиатура */
+      Instruction::WaitForKeyDown(x) => format!("LD {}, K", reg(x)),
+      /* Работа с адресами */
+      Instruction::Jump(address) => format!("JP {}", self.format_address(address)),
+      Instruction::JumpV0(address) => format!("JP V0, {}", self.format_address(address)),
+      Instruction::Call(address) => format!("CALL {}", self.format_address(address)),
+      Instruction::Return => String::from("RET"),
+      Instruction::Exit => String::from("EXIT"),
+    }
+  }
+
+  fn format_octo(&self, instruction: Instruction) -> String {
+    let reg = |index: Nibble| format!("v{:x}", index.as_u8());
+    match instruction {
+      /* Условия */
+      Instruction::SkipIfEqual(x, value) => format!("if {} != 0x{value:02X} then", reg(x)),
+      Instruction::SkipIfNotEqual(x, value) => format!("if {} == 0x{value:02X} then", reg(x)),
+      Instruction::SkipIfRegistersEqual(x, y) => format!("if {} != {} then", reg(x), reg(y)),
+      Instruction::SkipIfRegistersNotEqual(x, y) => format!("if {} == {} then", reg(x), reg(y)),
+      Instruction::SkipIfKeyDown(x) => format!("if {} -key then", reg(x)),
+      Instruction::SkipIfKeyUp(x) => format!("if {} key then", reg(x)),
+      /* Запись регистров */
+      Instruction::SetRegister(x, value) => format!("{} := 0x{value:02X}", reg(x)),
+      Instruction::CopyRegister(x, y) => format!("{} := {}", reg(x), reg(y)),
+      Instruction::SetAddressRegister(address) => format!("i := {}", self.format_address(address)),
+      Instruction::SetLongAddressRegister(address) => {
+        format!("i := long {}", self.format_address(address))
+      }
+      Instruction::SetRegisterRandom(x, value) => format!("{} := random 0x{value:02X}", reg(x)),
+      Instruction::GetDelayTimer(x) => format!("{} := delay", reg(x)),
+      Instruction::WriteRegistersToMem(x) => format!("save {}", reg(x)),
+      Instruction::LoadRegistersFromMem(x) => format!("load {}", reg(x)),
+      Instruction::WriteRegisterRangeToMem(x, y) => format!("save {} - {}", reg(x), reg(y)),
+      Instruction::LoadRegisterRangeFromMem(x, y) => format!("load {} - {}", reg(x), reg(y)),
+      Instruction::RegisterToBCD(x) => format!("bcd {}", reg(x)),
+      Instruction::SetAddressRegisterToDigit(x) => format!("i := hex {}", reg(x)),
+      Instruction::SetAddressRegisterToBigDigit(x) => format!("i := bighex {}", reg(x)),
+      Instruction::SaveFlags(x) => format!("saveflags {}", reg(x)),
+      Instruction::LoadFlags(x) => format!("loadflags {}", reg(x)),
+      /* Математические операции */
+      Instruction::AddRegister(x, value) => format!("{} += 0x{value:02X}", reg(x)),
+      Instruction::OrRegisters(x, y) => format!("{} |= {}", reg(x), reg(y)),
+      Instruction::AndRegisters(x, y) => format!("{} &= {}", reg(x), reg(y)),
+      Instruction::XorRegisters(x, y) => format!("{} ^= {}", reg(x), reg(y)),
+      Instruction::AddAddressRegister(x) => format!("i += {}", reg(x)),
+      Instruction::AddRegisters(x, y) => format!("{} += {}", reg(x), reg(y)),
+      Instruction::SubRegisters(x, y) => format!("{} -= {}", reg(x), reg(y)),
+      Instruction::RShiftRegisters(x, y) => format!("{} >>= {}", reg(x), reg(y)),
+      Instruction::SubRegisterReversed(x, y) => format!("{} =- {}", reg(x), reg(y)),
+      Instruction::LShiftRegisters(x, y) => format!("{} <<= {}", reg(x), reg(y)),
+      /* Экран */
+      Instruction::ClearScreen => String::from("clear"),
+      Instruction::DrawSprite(x, y, n) => format!("sprite {} {} {}", reg(x), reg(y), n.as_u8()),
+      Instruction::ScrollDown(n) => format!("scroll-down {}", n.as_u8()),
+      Instruction::ScrollUp(n) => format!("scroll-up {}", n.as_u8()),
+      Instruction::ScrollRight => String::from("scroll-right"),
+      Instruction::ScrollLeft => String::from("scroll-left"),
+      Instruction::LowResolution => String::from("lores"),
+      Instruction::HighResolution => String::from("hires"),
+      Instruction::SelectPlanes(n) => format!("plane {}", n.as_u8()),
+      /* Таймеры */
+      Instruction::SetDelayTimer(x) => format!("delay := {}", reg(x)),
+      Instruction::SetSoundTimer(x) => format!("buzzer := {}", reg(x)),
+      /* Звук */
+      Instruction::LoadAudioPattern => String::from("audio"),
+      Instruction::SetPitch(x) => format!("pitch := {}", reg(x)),
+      /* Клавиатура */
+      Instruction::WaitForKeyDown(x) => format!("{} := key", reg(x)),
+      /* Работа с адресами */
+      Instruction::Jump(address) => format!("jump {}", self.format_address(address)),
+      Instruction::JumpV0(address) => format!("jump0 {}", self.format_address(address)),
+      Instruction::Call(address) => match self.get_label(address) {
+        Some(label) => String::from(label),
+        None => format!(":call 0x{:03X}", address.as_u16()),
+      },
+      Instruction::Return => String::from("return"),
+      Instruction::Exit => String::from("exit"),
+    }
+  }
+}
+
+struct Analysis<'a> {
+  image: &'a [u8],
+  load_point: Address,
+  mode: Mode,
+}
+
+impl<'a> Analysis<'a> {
+  fn new(image: &'a [u8], load_point: Address, mode: Mode) -> Self {
+    Self {
+      image,
+      load_point,
+      mode,
+    }
+  }
+
+  fn get_address(&self, offset: usize) -> Address {
+    Address::from(self.load_point.as_u16().wrapping_add(offset as u16))
+  }
+
+  fn get_offset(&self, address: Address) -> Option<usize> {
+    let offset = address.as_u16().wrapping_sub(self.load_point.as_u16()) as usize;
+    Some(offset).filter(|&offset| offset < self.image.len())
+  }
+
+  fn read_op_code(&self, offset: usize) -> Option<OpCode> {
+    let bytes = self.image.get(offset..offset.checked_add(2)?)?;
+    Some(OpCode::from_bytes(bytes[0], bytes[1]))
+  }
+
+  fn decode(&self, address: Address) -> Option<Instruction> {
+    let offset = self.get_offset(address)?;
+    let code = self.read_op_code(offset)?;
+    let next_code = self.read_op_code(offset + 2).unwrap_or_default();
+    let instruction = Instruction::decode(code, next_code).ok()?;
+    let size = instruction.get_size() as usize;
+    Some(instruction)
+      .filter(|&instruction| self.mode.supports(instruction) && offset + size <= self.image.len())
+  }
+
+  fn follow_flow(
+    &self,
+    entry_point: Address,
+  ) -> (BTreeMap<usize, Instruction>, BTreeMap<Address, LabelKind>) {
+    let mut instructions = BTreeMap::new();
+    let mut labels = BTreeMap::new();
+    let mut add_label = |address: Address, kind: LabelKind| {
+      if self.get_offset(address).is_some() {
+        let label = labels.entry(address).or_insert(kind);
+        *label = kind.max(*label);
+      }
+    };
+    add_label(entry_point, LabelKind::Entry);
+
+    let mut pending = vec![entry_point];
+    while let Some(address) = pending.pop() {
+      let Some(offset) = self.get_offset(address) else {
+        continue;
+      };
+      if instructions.contains_key(&offset) {
+        continue;
+      }
+      let Some(instruction) = self.decode(address) else {
+        continue;
+      };
+      instructions.insert(offset, instruction);
+
+      let next_address = address + instruction.get_size();
+      match instruction {
+        Instruction::Jump(target) | Instruction::JumpV0(target) => {
+          add_label(target, LabelKind::Jump);
+          pending.push(target);
+        }
+        Instruction::Call(target) => {
+          add_label(target, LabelKind::Subroutine);
+          pending.push(target);
+          pending.push(next_address);
+        }
+        Instruction::Return | Instruction::Exit => {}
+        Instruction::SkipIfEqual(..)
+        | Instruction::SkipIfNotEqual(..)
+        | Instruction::SkipIfRegistersEqual(..)
+        | Instruction::SkipIfRegistersNotEqual(..)
+        | Instruction::SkipIfKeyDown(_)
+        | Instruction::SkipIfKeyUp(_) => {
+          let skipped_size = self
+            .decode(next_address)
+            .map_or(2, |skipped| skipped.get_size());
+          pending.push(next_address);
+          pending.push(next_address + skipped_size);
+        }
+        Instruction::SetAddressRegister(target) | Instruction::SetLongAddressRegister(target) => {
+          add_label(target, LabelKind::Data);
+          pending.push(next_address);
+        }
+        _ => pending.push(next_address),
+      }
+    }
+
+    (instructions, labels)
+  }
+}
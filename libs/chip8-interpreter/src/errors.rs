@@ -1,19 +1,189 @@
-use super::{address::Address, nibble::Nibble};
-use crate::interpreter::OpCode;
+use std::fmt;
+
+use super::nibble::Nibble;
+use crate::{
+  address::Address,
+  disassembler::{format_instruction, Syntax},
+  interpreter::{Instruction, OpCode},
+};
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+#[derive(Error, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
 pub enum InterpreterError {
-  #[error("Interpreter crashed")]
-  Crashed,
+  #[error("Interpreter crashed earlier: {0}")]
+  Crashed(#[source] Box<InterpreterError>),
+  #[error("{cause}\n{report}")]
+  Fault {
+    #[source]
+    cause: Box<InterpreterError>,
+    report: Box<CrashReport>,
+  },
   #[error("Unknown opcode: {0}")]
   UnknownOpCode(OpCode),
   #[error("Max nibble size exceeded. Should be less or equal {}", Nibble::MAX)]
   NibbleOverflow,
-  #[error("Max address size exceeded. Should be less or equal {}", Address::MAX)]
-  AddressOverflow,
   #[error("Stack overflow")]
   StackOverflow,
   #[error("Stack underflow")]
   StackUnderflow,
+  #[error("Address does not fit into the instruction: {0}")]
+  AddressOverflow(Address),
+  /// Crash restored from a save state that predates stored crash causes.
+  #[error("Crash cause is unknown")]
+  UnknownCause,
+}
+
+/// Machine state at the instruction that failed. Failed instructions change nothing, so the
+/// registers and the stack are the ones the instruction saw.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct CrashReport {
+  pub address: Address,
+  pub op_code: OpCode,
+  /// `None` when the opcode can't be decoded.
+  pub instruction: Option<Instruction>,
+  pub registers: [u8; Nibble::SIZE],
+  pub index_register: Address,
+  /// Return addresses on the stack, the innermost call first.
+  pub backtrace: Vec<Address>,
+}
+
+impl fmt::Display for CrashReport {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "PC:{} OP:{}", self.address, self.op_code)?;
+    if let Some(instruction) = self.instruction {
+      write!(f, " ({})", format_instruction(instruction, Syntax::Cowgod))?;
+    }
+    write!(f, "\nI:{}", self.index_register)?;
+    for (index, value) in self.registers.iter().enumerate() {
+      write!(f, " V{index:X}:{value:02X}")?;
+    }
+    write!(f, "\nBacktrace:")?;
+    match self.backtrace.is_empty() {
+      true => write!(f, " empty"),
+      false => self
+        .backtrace
+        .iter()
+        .try_for_each(|address| write!(f, " {address}")),
+    }
+  }
+}
+
+#[derive(Error, Debug)]
+pub enum LoaderError {
+  #[error("Image is empty")]
+  EmptyImage,
+  #[error("Image of {size} bytes does not fit into memory at {load_point}")]
+  ImageTooLarge { size: usize, load_point: Address },
+  #[error("Font does not fit into memory at {0}")]
+  FontOutOfMemory(Address),
+}
+
+#[cfg(feature = "export")]
+#[derive(Error, Debug)]
+pub enum ExportError {
+  #[error(transparent)]
+  Io(#[from] std::io::Error),
+  #[error("PNG encoding failed: {0}")]
+  Png(#[from] png::EncodingError),
+  #[error("GIF encoding failed: {0}")]
+  Gif(#[from] gif::EncodingError),
+  #[error(transparent)]
+  Interpreter(#[from] InterpreterError),
+}
+
+#[derive(Error, Debug)]
+pub enum MovieError {
+  #[error("Movie was recorded with another image")]
+  ImageMismatch,
+  #[error(
+    "Replay desynced at frame {frame}: expected screen hash {expected:016X}, got {actual:016X}"
+  )]
+  Desync {
+    frame: usize,
+    expected: u64,
+    actual: u64,
+  },
+  #[error(transparent)]
+  Interpreter(#[from] InterpreterError),
+  #[error(transparent)]
+  Loader(#[from] LoaderError),
+  #[error(transparent)]
+  SaveState(#[from] SaveStateError),
+}
+
+#[derive(Error, Debug)]
+pub enum RewindError {
+  #[error("Rewind is disabled")]
+  Disabled,
+  #[error("Requested point is out of the rewind buffer")]
+  OutOfRange,
+  #[error(transparent)]
+  Interpreter(#[from] InterpreterError),
+  #[error(transparent)]
+  SaveState(#[from] SaveStateError),
+}
+
+#[derive(Error, Debug)]
+pub enum SaveStateError {
+  #[error("Not a save state")]
+  InvalidMagic,
+  #[error("Unsupported save state version: {0}")]
+  UnsupportedVersion(u16),
+  #[error("Save state checksum mismatch")]
+  ChecksumMismatch,
+  #[error("Save state holds another kind of object: {0}")]
+  WrongKind(u8),
+  #[error("Unexpected end of save state")]
+  UnexpectedEnd,
+  #[error("Unexpected data after the end of save state")]
+  TrailingData,
+  #[error("Invalid {0} in save state")]
+  InvalidValue(&'static str),
+  #[error("Save state was made in another interpreter mode")]
+  ModeMismatch,
+  #[error("Save state was made with another timing model")]
+  TimingMismatch,
+}
+
+#[derive(Error, Debug)]
+#[error("Line {line}: {kind}")]
+pub struct AssemblerError {
+  line: usize,
+  kind: AssemblerErrorKind,
+}
+
+impl AssemblerError {
+  pub fn new(line: usize, kind: AssemblerErrorKind) -> Self {
+    Self { line, kind }
+  }
+
+  pub fn get_line(&self) -> usize {
+    self.line
+  }
+
+  pub fn get_kind(&self) -> &AssemblerErrorKind {
+    &self.kind
+  }
+}
+
+#[derive(Error, Clone, Eq, PartialEq, Debug)]
+pub enum AssemblerErrorKind {
+  #[error("Unexpected end of source")]
+  UnexpectedEnd,
+  #[error("Unexpected token: {0}")]
+  UnexpectedToken(String),
+  #[error("Unknown identifier: {0}")]
+  UnknownIdentifier(String),
+  #[error("Symbol is already defined: {0}")]
+  DuplicateSymbol(String),
+  #[error("Not a register: {0}")]
+  InvalidRegister(String),
+  #[error("Value out of range: {0}")]
+  ValueOutOfRange(String),
+  #[error("Unsupported directive: {0}")]
+  UnsupportedDirective(String),
+  #[error("Unbalanced block: {0}")]
+  UnbalancedBlock(String),
+  #[error("Macro expansion is too deep: {0}")]
+  MacroTooDeep(String),
 }
@@ -1,7 +1,7 @@
-use crate::{address::Address, memory::Memory};
+use crate::{address::Address, errors::LoaderError, memory::Memory};
 
 pub trait Executable {
-  fn load_into_memory(&self, memory: &mut Memory);
+  fn load_into_memory(&self, memory: &mut Memory) -> Result<(), LoaderError>;
   fn get_entry_point(&self) -> Address;
 }
 
@@ -18,9 +18,21 @@ impl<'a> BaseExecutable<'a> {
 }
 
 impl<'a> Executable for BaseExecutable<'a> {
-  fn load_into_memory(&self, memory: &mut Memory) {
-    let mem_end = self.load_point + (self.image.len() - 1) as i16;
-    memory[self.load_point..=mem_end].copy_from_slice(self.image);
+  fn load_into_memory(&self, memory: &mut Memory) -> Result<(), LoaderError> {
+    if self.image.is_empty() {
+      return Err(LoaderError::EmptyImage);
+    }
+    let mem_start = self.load_point.as_usize();
+    let target = memory
+      .as_mut_slice()
+      .get_mut(mem_start..)
+      .and_then(|free| free.get_mut(..self.image.len()))
+      .ok_or(LoaderError::ImageTooLarge {
+        size: self.image.len(),
+        load_point: self.load_point,
+      })?;
+    target.copy_from_slice(self.image);
+    Ok(())
   }
 
   fn get_entry_point(&self) -> Address {
@@ -0,0 +1,214 @@
+use std::{
+  io::{self, Write},
+  time::Duration,
+};
+
+use crate::{
+  errors::ExportError,
+  platform::{BasePlatform, RandomGenerator},
+  screen::{ScreenFrame, ALL_PLANES, HIGH_RES_SCREEN_HEIGHT, HIGH_RES_SCREEN_WIDTH},
+  ControlledInterpreter,
+};
+
+pub type Color = [u8; 3];
+
+/// Colours of the pixel values: background, first plane, second plane and both planes.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Palette([Color; 4]);
+
+impl Palette {
+  pub const fn new(colors: [Color; 4]) -> Self {
+    Self(colors)
+  }
+
+  /// Palette that draws every plane with `foreground`.
+  pub const fn monochrome(background: Color, foreground: Color) -> Self {
+    Self([background, foreground, foreground, foreground])
+  }
+
+  pub fn get_color(&self, pixel: u8) -> Color {
+    self.0[(pixel & ALL_PLANES) as usize]
+  }
+
+  fn is_dark(&self, pixel: u8) -> bool {
+    let [r, g, b] = self.get_color(pixel);
+    (r as u32 * 299 + g as u32 * 587 + b as u32 * 114) < 128 * 1000
+  }
+
+  fn as_bytes(&self) -> Vec<u8> {
+    self.0.concat()
+  }
+}
+
+pub const DEFAULT_PALETTE: Palette = Palette::new([
+  [0x00, 0x00, 0x00],
+  [0xFF, 0xFF, 0xFF],
+  [0xAA, 0xAA, 0xAA],
+  [0x55, 0x55, 0x55],
+]);
+
+impl Default for Palette {
+  fn default() -> Self {
+    DEFAULT_PALETTE
+  }
+}
+
+/// Pixel values of the frame with every pixel repeated `scale` times in both directions.
+fn scale_pixels(screen_frame: &ScreenFrame, scale: usize) -> Vec<u8> {
+  screen_frame
+    .iter_rows()
+    .flat_map(|row| {
+      let row = row
+        .iter()
+        .flat_map(|&pixel| std::iter::repeat_n(pixel & ALL_PLANES, scale))
+        .collect::<Vec<_>>();
+      std::iter::repeat_n(row, scale).flatten()
+    })
+    .collect()
+}
+
+/// Writes the frame as an indexed colour PNG.
+pub fn write_png<W: Write>(
+  writer: W,
+  screen_frame: &ScreenFrame,
+  scale: usize,
+  palette: &Palette,
+) -> Result<(), ExportError> {
+  let scale = scale.max(1);
+  let width = screen_frame.get_width() * scale;
+  let height = screen_frame.get_height() * scale;
+
+  let mut encoder = png::Encoder::new(writer, width as u32, height as u32);
+  encoder.set_color(png::ColorType::Indexed);
+  encoder.set_depth(png::BitDepth::Eight);
+  encoder.set_palette(palette.as_bytes());
+  let mut writer = encoder.write_header()?;
+  writer.write_image_data(&scale_pixels(screen_frame, scale))?;
+  writer.finish()?;
+  Ok(())
+}
+
+/// Writes the frame as a binary PBM. Pixels with dark palette colours become black.
+pub fn write_pbm<W: Write>(
+  mut writer: W,
+  screen_frame: &ScreenFrame,
+  scale: usize,
+  palette: &Palette,
+) -> io::Result<()> {
+  let scale = scale.max(1);
+  let width = screen_frame.get_width() * scale;
+  let height = screen_frame.get_height() * scale;
+  write!(writer, "P4\n{width} {height}\n")?;
+
+  let pixels = scale_pixels(screen_frame, scale);
+  let data = pixels
+    .chunks(width)
+    .flat_map(|row| {
+      row.chunks(8).map(|chunk| {
+        chunk
+          .iter()
+          .enumerate()
+          .filter(|(_, &pixel)| palette.is_dark(pixel))
+          .fold(0u8, |byte, (index, _)| byte | 0x80 >> index)
+      })
+    })
+    .collect::<Vec<_>>();
+  writer.write_all(&data)?;
+  writer.flush()
+}
+
+pub const GIF_FRAME_RATE: u32 = 60;
+
+const CENTISECONDS_PER_SECOND: u64 = 100;
+
+/// Records frames at 60 Hz into an animated GIF. Runs of equal frames become one GIF frame.
+///
+/// Low resolution frames are drawn with a doubled scale, so all frames have the size of a high
+/// resolution frame.
+pub struct GifRecorder<W: Write> {
+  encoder: gif::Encoder<W>,
+  scale: usize,
+  pending_frame: Option<ScreenFrame>,
+  captured_count: u64,
+  written_centiseconds: u64,
+}
+
+impl<W: Write> GifRecorder<W> {
+  pub fn new(writer: W, scale: usize, palette: &Palette) -> Result<Self, ExportError> {
+    let scale = scale.max(1);
+    let mut encoder = gif::Encoder::new(
+      writer,
+      (HIGH_RES_SCREEN_WIDTH * scale) as u16,
+      (HIGH_RES_SCREEN_HEIGHT * scale) as u16,
+      &palette.as_bytes(),
+    )?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+    Ok(Self {
+      encoder,
+      scale,
+      pending_frame: None,
+      captured_count: 0,
+      written_centiseconds: 0,
+    })
+  }
+
+  /// Number of captured 60 Hz frames.
+  pub fn get_captured_count(&self) -> u64 {
+    self.captured_count
+  }
+
+  /// Adds the frame shown for one 60 Hz tick.
+  pub fn capture(&mut self, screen_frame: &ScreenFrame) -> Result<(), ExportError> {
+    if self.pending_frame.as_ref() != Some(screen_frame) {
+      self.write_pending()?;
+      self.pending_frame = Some(*screen_frame);
+    }
+    self.captured_count += 1;
+    Ok(())
+  }
+
+  /// Runs the interpreter until the next 60 Hz tick and captures its screen.
+  pub fn record_frame<R: RandomGenerator>(
+    &mut self,
+    interpreter: &mut ControlledInterpreter<BasePlatform<R>>,
+  ) -> Result<(), ExportError> {
+    let tick_end = Duration::from_secs(self.captured_count + 1) / GIF_FRAME_RATE;
+    let tick_start = Duration::from_secs(self.captured_count) / GIF_FRAME_RATE;
+    interpreter.simulate_duration(tick_end - tick_start)?;
+    self.capture(interpreter.get_platform().get_screen_frame())
+  }
+
+  /// Writes the last frame and the GIF trailer.
+  pub fn finish(mut self) -> Result<W, ExportError> {
+    self.write_pending()?;
+    Ok(self.encoder.into_inner()?)
+  }
+
+  fn write_pending(&mut self) -> Result<(), ExportError> {
+    let Some(screen_frame) = self.pending_frame.take() else {
+      return Ok(());
+    };
+
+    let frame_rate = GIF_FRAME_RATE as u64;
+    let end_centiseconds =
+      (self.captured_count * CENTISECONDS_PER_SECOND * 2 + frame_rate) / (frame_rate * 2);
+    let delay = end_centiseconds
+      .saturating_sub(self.written_centiseconds)
+      .max(1);
+    self.written_centiseconds += delay;
+
+    let scale = match screen_frame.is_high_resolution() {
+      true => self.scale,
+      false => self.scale * 2,
+    };
+    let mut frame = gif::Frame::from_indexed_pixels(
+      (screen_frame.get_width() * scale) as u16,
+      (screen_frame.get_height() * scale) as u16,
+      scale_pixels(&screen_frame, scale),
+      None,
+    );
+    frame.delay = delay.min(u16::MAX as u64) as u16;
+    self.encoder.write_frame(&frame)?;
+    Ok(())
+  }
+}
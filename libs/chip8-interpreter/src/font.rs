@@ -0,0 +1,242 @@
+use crate::{address::Address, errors::LoaderError, memory::Memory, nibble::Nibble};
+
+pub const FONT_GLYPH_HEIGHT: usize = 5;
+pub const FONT_SIZE: usize = FONT_GLYPH_HEIGHT * Nibble::SIZE;
+pub const BIG_FONT_GLYPH_HEIGHT: usize = 10;
+pub const BIG_FONT_SIZE: usize = BIG_FONT_GLYPH_HEIGHT * Nibble::SIZE;
+pub const DEFAULT_FONT_LOAD_POINT: Address = Address::new::<0x050>();
+
+#[rustfmt::skip]
+const COSMAC_VIP_GLYPHS: [u8; FONT_SIZE] = [
+  0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+  0x60, 0x20, 0x20, 0x20, 0x70, // 1
+  0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+  0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+  0xA0, 0xA0, 0xF0, 0x20, 0x20, // 4
+  0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+  0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+  0xF0, 0x10, 0x10, 0x10, 0x10, // 7
+  0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+  0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+  0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+  0xF0, 0x50, 0x70, 0x50, 0xF0, // B
+  0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+  0xF0, 0x50, 0x50, 0x50, 0xF0, // D
+  0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+  0xF0, 0x80, 0xF0, 0x80, 0x80, // F
+];
+
+#[rustfmt::skip]
+const DREAM_6800_GLYPHS: [u8; FONT_SIZE] = [
+  0xE0, 0xA0, 0xA0, 0xA0, 0xE0, // 0
+  0x40, 0x40, 0x40, 0x40, 0x40, // 1
+  0xE0, 0x20, 0xE0, 0x80, 0xE0, // 2
+  0xE0, 0x20, 0xE0, 0x20, 0xE0, // 3
+  0x80, 0xA0, 0xA0, 0xE0, 0x20, // 4
+  0xE0, 0x80, 0xE0, 0x20, 0xE0, // 5
+  0xE0, 0x80, 0xE0, 0xA0, 0xE0, // 6
+  0xE0, 0x20, 0x20, 0x20, 0x20, // 7
+  0xE0, 0xA0, 0xE0, 0xA0, 0xE0, // 8
+  0xE0, 0xA0, 0xE0, 0x20, 0xE0, // 9
+  0xE0, 0xA0, 0xE0, 0xA0, 0xA0, // A
+  0xC0, 0xA0, 0xE0, 0xA0, 0xC0, // B
+  0xE0, 0x80, 0x80, 0x80, 0xE0, // C
+  0xC0, 0xA0, 0xA0, 0xA0, 0xC0, // D
+  0xE0, 0x80, 0xE0, 0x80, 0xE0, // E
+  0xE0, 0x80, 0xC0, 0x80, 0x80, // F
+];
+
+#[rustfmt::skip]
+const ETI_660_GLYPHS: [u8; FONT_SIZE] = [
+  0xE0, 0xA0, 0xA0, 0xA0, 0xE0, // 0
+  0x20, 0x20, 0x20, 0x20, 0x20, // 1
+  0xE0, 0x20, 0xE0, 0x80, 0xE0, // 2
+  0xE0, 0x20, 0xE0, 0x20, 0xE0, // 3
+  0xA0, 0xA0, 0xE0, 0x20, 0x20, // 4
+  0xE0, 0x80, 0xE0, 0x20, 0xE0, // 5
+  0xE0, 0x80, 0xE0, 0xA0, 0xE0, // 6
+  0xE0, 0x20, 0x20, 0x20, 0x20, // 7
+  0xE0, 0xA0, 0xE0, 0xA0, 0xE0, // 8
+  0xE0, 0xA0, 0xE0, 0x20, 0xE0, // 9
+  0xE0, 0xA0, 0xE0, 0xA0, 0xA0, // A
+  0x80, 0x80, 0xE0, 0xA0, 0xE0, // B
+  0xE0, 0x80, 0x80, 0x80, 0xE0, // C
+  0x20, 0x20, 0xE0, 0xA0, 0xE0, // D
+  0xE0, 0x80, 0xE0, 0x80, 0xE0, // E
+  0xE0, 0x80, 0xC0, 0x80, 0x80, // F
+];
+
+#[rustfmt::skip]
+const OCTO_GLYPHS: [u8; FONT_SIZE] = [
+  0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+  0x20, 0x60, 0x20, 0x20, 0x70, // 1
+  0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+  0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+  0x90, 0x90, 0xF0, 0x10, 0x10, // 4
+  0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+  0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+  0xF0, 0x10, 0x20, 0x40, 0x40, // 7
+  0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+  0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+  0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+  0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
+  0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+  0xE0, 0x90, 0x90, 0x90, 0xE0, // D
+  0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+  0xF0, 0x80, 0xF0, 0x80, 0x80, // F
+];
+
+/// Small font of the SUPER-CHIP 1.1 ROM, taken over from CHIP-48.
+#[rustfmt::skip]
+const SUPER_CHIP_GLYPHS: [u8; FONT_SIZE] = [
+  0x60, 0xA0, 0xA0, 0xA0, 0xC0, // 0
+  0x40, 0xC0, 0x40, 0x40, 0xE0, // 1
+  0xC0, 0x20, 0x40, 0x80, 0xE0, // 2
+  0xC0, 0x20, 0x40, 0x20, 0xC0, // 3
+  0x20, 0xA0, 0xE0, 0x20, 0x20, // 4
+  0xE0, 0x80, 0xC0, 0x20, 0xC0, // 5
+  0x40, 0x80, 0xC0, 0xA0, 0x40, // 6
+  0xE0, 0x20, 0x60, 0x40, 0x40, // 7
+  0x40, 0xA0, 0x40, 0xA0, 0x40, // 8
+  0x40, 0xA0, 0x60, 0x20, 0x40, // 9
+  0x40, 0xA0, 0xE0, 0xA0, 0xA0, // A
+  0xC0, 0xA0, 0xC0, 0xA0, 0xC0, // B
+  0x60, 0x80, 0x80, 0x80, 0x60, // C
+  0xC0, 0xA0, 0xA0, 0xA0, 0xC0, // D
+  0xE0, 0x80, 0xC0, 0x80, 0xE0, // E
+  0xE0, 0x80, 0xC0, 0x80, 0x80, // F
+];
+
+#[rustfmt::skip]
+const SUPER_CHIP_BIG_GLYPHS: [u8; BIG_FONT_SIZE] = [
+  0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, // 0
+  0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, // 1
+  0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, // 2
+  0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C, // 3
+  0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06, // 4
+  0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C, // 5
+  0x3E, 0x7C, 0xC0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C, // 6
+  0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, // 7
+  0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, // 8
+  0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C, // 9
+  0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+  0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+  0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+  0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+  0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+  0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+];
+
+#[rustfmt::skip]
+const OCTO_BIG_GLYPHS: [u8; BIG_FONT_SIZE] = [
+  0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
+  0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
+  0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
+  0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
+  0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
+  0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
+  0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
+  0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
+  0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
+  0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
+  0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+  0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+  0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+  0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+  0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+  0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+];
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Default)]
+pub enum FontSet {
+  CosmacVip,
+  Dream6800,
+  Eti660,
+  #[default]
+  Octo,
+  SuperChip,
+}
+
+impl FontSet {
+  pub fn get_glyphs(self) -> [u8; FONT_SIZE] {
+    match self {
+      FontSet::CosmacVip => COSMAC_VIP_GLYPHS,
+      FontSet::Dream6800 => DREAM_6800_GLYPHS,
+      FontSet::Eti660 => ETI_660_GLYPHS,
+      FontSet::Octo => OCTO_GLYPHS,
+      FontSet::SuperChip => SUPER_CHIP_GLYPHS,
+    }
+  }
+
+  pub fn get_big_glyphs(self) -> [u8; BIG_FONT_SIZE] {
+    match self {
+      FontSet::SuperChip => SUPER_CHIP_BIG_GLYPHS,
+      _ => OCTO_BIG_GLYPHS,
+    }
+  }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct Font {
+  glyphs: [u8; FONT_SIZE],
+  big_glyphs: [u8; BIG_FONT_SIZE],
+  load_point: Address,
+}
+
+impl Font {
+  /// Font with the given small glyphs and the big glyphs of the default set.
+  pub fn new(glyphs: [u8; FONT_SIZE], load_point: Address) -> Self {
+    Self {
+      glyphs,
+      big_glyphs: FontSet::default().get_big_glyphs(),
+      load_point,
+    }
+  }
+
+  pub fn with_big_glyphs(self, big_glyphs: [u8; BIG_FONT_SIZE]) -> Self {
+    Self { big_glyphs, ..self }
+  }
+
+  pub fn from_set(set: FontSet, load_point: Address) -> Self {
+    Self::new(set.get_glyphs(), load_point).with_big_glyphs(set.get_big_glyphs())
+  }
+
+  pub fn get_glyphs(&self) -> &[u8; FONT_SIZE] {
+    &self.glyphs
+  }
+
+  pub fn get_big_glyphs(&self) -> &[u8; BIG_FONT_SIZE] {
+    &self.big_glyphs
+  }
+
+  pub fn get_load_point(&self) -> Address {
+    self.load_point
+  }
+
+  pub fn load_into_memory(&self, memory: &mut Memory) -> Result<(), LoaderError> {
+    let mem_start = self.load_point.as_usize();
+    let target = memory
+      .as_mut_slice()
+      .get_mut(mem_start..)
+      .and_then(|free| free.get_mut(..FONT_SIZE + BIG_FONT_SIZE))
+      .ok_or(LoaderError::FontOutOfMemory(self.load_point))?;
+    let (glyphs, big_glyphs) = target.split_at_mut(FONT_SIZE);
+    glyphs.copy_from_slice(&self.glyphs);
+    big_glyphs.copy_from_slice(&self.big_glyphs);
+    Ok(())
+  }
+
+  pub fn get_glyph_address(&self, digit: Nibble) -> Address {
+    self.load_point + (digit.as_usize() * FONT_GLYPH_HEIGHT) as i16
+  }
+
+  pub fn get_big_glyph_address(&self, digit: Nibble) -> Address {
+    self.load_point + (FONT_SIZE + digit.as_usize() * BIG_FONT_GLYPH_HEIGHT) as i16
+  }
+}
+
+impl Default for Font {
+  fn default() -> Self {
+    Self::from_set(FontSet::default(), DEFAULT_FONT_LOAD_POINT)
+  }
+}
@@ -2,15 +2,21 @@ use std::fmt;
 
 use crate::{
   address::Address,
-  errors::InterpreterError,
+  errors::{CrashReport, InterpreterError, LoaderError, SaveStateError},
   executable::Executable,
+  font::Font,
   memory::Memory,
+  mode::Mode,
   nibble::Nibble,
-  platform::Platform,
+  platform::{Platform, AUDIO_PATTERN_SIZE},
+  quirks::{MemoryIncrement, Quirks},
   registers::Registers,
+  save_state::{SaveState, StateKind, StateReader, StateWriter},
   sprite::{Point, Sprite},
   stack::Stack,
-  SCREEN_HEIGHT, SCREEN_WIDTH,
+  state::InterpreterState,
+  trace::{BoxedTraceSink, TraceRecord, TraceSink, Tracer},
+  HIGH_RES_SCREEN_HEIGHT, HIGH_RES_SCREEN_WIDTH, SCREEN_HEIGHT, SCREEN_WIDTH,
 };
 
 #[derive(Eq, PartialEq, Ord, PartialOrd, Debug)]
@@ -22,35 +28,138 @@ pub struct Interpreter<P: Platform> {
   instruction_address: Address,
   stack: Stack<Address, 16>,
   expecting_key: Option<Nibble>,
-  is_crashed: bool,
+  crash: Option<InterpreterError>,
+  is_exited: bool,
+  is_waiting_vblank: bool,
+  executed_count: u64,
+  tracer: Tracer,
+  mode: Mode,
+  font: Font,
+  quirks: Quirks,
 }
 
 const ADDRESS_BYTE_STEP: i16 = 2;
+const LONG_ADDRESS_OP_CODE: u16 = 0xF000;
+const SHORT_ADDRESS_MAX: u16 = 0xFFF;
+
+fn iter_register_range(x_reg_index: Nibble, y_reg_index: Nibble) -> impl Iterator<Item = Nibble> {
+  let (start, end) = (x_reg_index.as_u8(), y_reg_index.as_u8());
+  (0..=start.abs_diff(end)).map(move |offset| {
+    let index = match start <= end {
+      true => start + offset,
+      false => start - offset,
+    };
+    Nibble::try_from(index).unwrap()
+  })
+}
 
 impl<P: Platform> Interpreter<P> {
-  pub fn new<E: Executable>(platform: P, executable: E) -> Self {
+  pub fn new<E: Executable>(
+    platform: P,
+    executable: E,
+    mode: Mode,
+    font: Font,
+    quirks: Quirks,
+  ) -> Result<Self, LoaderError> {
     let mut res = Self {
       platform,
       registers: Registers::new(),
       index_register: Address::default(),
-      memory: Memory::new(),
+      memory: Memory::new(mode.get_memory_size()),
       stack: Stack::new(),
       instruction_address: executable.get_entry_point(),
       expecting_key: None,
-      is_crashed: false,
+      crash: None,
+      is_exited: false,
+      is_waiting_vblank: false,
+      executed_count: 0,
+      tracer: Tracer::default(),
+      mode,
+      font,
+      quirks,
     };
 
-    executable.load_into_memory(&mut res.memory);
-    res
+    font.load_into_memory(&mut res.memory)?;
+    executable.load_into_memory(&mut res.memory)?;
+    Ok(res)
+  }
+
+  pub fn get_platform(&self) -> &P {
+    &self.platform
   }
 
   pub fn get_platform_mut(&mut self) -> &mut P {
     &mut self.platform
   }
 
+  pub fn get_state(&self) -> InterpreterState<'_> {
+    InterpreterState {
+      registers: &self.registers,
+      index_register: self.index_register,
+      instruction_address: self.instruction_address,
+      stack: self.stack.as_slice(),
+      memory: &self.memory,
+      expecting_key: self.expecting_key,
+      delay_timer: self.platform.get_delay_timer(),
+      sound_timer: self.platform.get_sound_timer(),
+      is_crashed: self.crash.is_some(),
+      is_exited: self.is_exited,
+      is_waiting_vblank: self.is_waiting_vblank,
+      executed_count: self.executed_count,
+    }
+  }
+
+  pub fn set_register(&mut self, index: Nibble, value: u8) {
+    self.registers[index] = value;
+  }
+
+  pub fn set_index_register(&mut self, value: Address) {
+    self.index_register = value;
+  }
+
+  pub fn set_instruction_address(&mut self, value: Address) {
+    self.instruction_address = value;
+  }
+
+  pub fn write_memory(&mut self, address: Address, bytes: &[u8]) {
+    bytes.iter().enumerate().for_each(|(offset, &byte)| {
+      self.memory[address + offset as i16] = byte;
+    });
+  }
+
+  pub fn is_exited(&self) -> bool {
+    self.is_exited
+  }
+
+  /// Passes a [`TraceRecord`] of every executed instruction to `sink`.
+  /// Returns the previous sink.
+  pub fn set_trace_sink<S: TraceSink + Send + 'static>(
+    &mut self,
+    sink: S,
+  ) -> Option<BoxedTraceSink> {
+    self.tracer.set_sink(Some(Box::new(sink)))
+  }
+
+  pub fn remove_trace_sink(&mut self) -> Option<BoxedTraceSink> {
+    self.tracer.set_sink(None)
+  }
+
+  pub(crate) fn restore_trace_sink(&mut self, sink: Option<BoxedTraceSink>) {
+    self.tracer.set_sink(sink);
+  }
+
+  pub fn notify_vblank(&mut self) {
+    self.is_waiting_vblank = false;
+    self.platform.notify_vblank();
+  }
+
   pub fn run_next(&mut self) -> Result<(), InterpreterError> {
-    if self.is_crashed {
-      return Err(InterpreterError::Crashed);
+    if let Some(crash) = &self.crash {
+      return Err(InterpreterError::Crashed(Box::new(crash.clone())));
+    }
+
+    if self.is_exited || self.is_waiting_vblank {
+      return Ok(());
     }
 
     if let Some(expecting_key_reg_index) = self.expecting_key {
@@ -62,33 +171,91 @@ impl<P: Platform> Interpreter<P> {
       self.expecting_key = None;
     }
 
-    let instruction_res = self.process_next_instruction();
-    if instruction_res.is_err() {
-      self.is_crashed = true;
-      return instruction_res;
+    let trace_record = self.tracer.is_enabled().then(|| self.begin_trace_record());
+    if let Err(cause) = self.process_next_instruction() {
+      self.tracer.take_memory_writes();
+      let fault = self.create_fault(cause);
+      self.crash = Some(fault.clone());
+      return Err(fault);
     }
+    self.index_register = self.mode.wrap_address(self.index_register);
+    self.instruction_address = self.mode.wrap_address(self.instruction_address);
 
+    if let Some(mut record) = trace_record.flatten() {
+      record.registers_after = self.registers.as_array();
+      record.index_register_after = self.index_register;
+      record.memory_writes = self.tracer.take_memory_writes();
+      self.tracer.record(&record);
+    }
+    self.executed_count = self.executed_count.wrapping_add(1);
     Ok(())
   }
 
+  /// Error of the failed instruction, `None` while the interpreter runs.
+  pub fn get_crash(&self) -> Option<&InterpreterError> {
+    self.crash.as_ref()
+  }
+
+  fn create_fault(&self, cause: InterpreterError) -> InterpreterError {
+    let op_code = self.read_op_code(self.instruction_address);
+    let following_op_code = self.read_op_code(self.instruction_address + ADDRESS_BYTE_STEP);
+    let report = CrashReport {
+      address: self.instruction_address,
+      op_code,
+      instruction: Instruction::decode(op_code, following_op_code).ok(),
+      registers: self.registers.as_array(),
+      index_register: self.index_register,
+      backtrace: self.stack.as_slice().iter().rev().copied().collect(),
+    };
+    InterpreterError::Fault {
+      cause: Box::new(cause),
+      report: Box::new(report),
+    }
+  }
+
+  fn begin_trace_record(&self) -> Option<TraceRecord> {
+    let op_code = self.read_op_code(self.instruction_address);
+    let following_op_code = self.read_op_code(self.instruction_address + ADDRESS_BYTE_STEP);
+    let instruction = Instruction::decode(op_code, following_op_code).ok()?;
+    let registers = self.registers.as_array();
+    Some(TraceRecord {
+      cycle: self.executed_count,
+      address: self.instruction_address,
+      op_code,
+      instruction,
+      registers,
+      registers_after: registers,
+      index_register: self.index_register,
+      index_register_after: self.index_register,
+      memory_writes: Vec::new(),
+    })
+  }
+
+  fn store_memory(&mut self, address: Address, value: u8) {
+    let address = self.mode.wrap_address(address);
+    self.memory[address] = value;
+    self.tracer.add_memory_write(address, value);
+  }
+
   fn process_next_instruction(&mut self) -> Result<(), InterpreterError> {
-    let next_op_code = OpCode::from_bytes(
-      self.memory[self.instruction_address],
-      self.memory[self.instruction_address + 1],
-    );
-    let instruction = Instruction::try_from(next_op_code)?;
+    let next_op_code = self.read_op_code(self.instruction_address);
+    let following_op_code = self.read_op_code(self.instruction_address + ADDRESS_BYTE_STEP);
+    let instruction = Instruction::decode(next_op_code, following_op_code)?;
+    if !self.mode.supports(instruction) {
+      return Err(InterpreterError::UnknownOpCode(next_op_code));
+    }
 
-    let mut instruction_step: i16 = 1;
+    let mut is_skipping = false;
     match instruction {
       Instruction::SkipIfEqual(reg_index, value) => {
         if self.registers[reg_index] == value {
-          instruction_step = 2;
+          is_skipping = true;
         }
       }
 
       Instruction::SkipIfNotEqual(reg_index, value) => {
         if self.registers[reg_index] != value {
-          instruction_step = 2;
+          is_skipping = true;
         }
       }
 
@@ -96,7 +263,7 @@ impl<P: Platform> Interpreter<P> {
         let reg_x = self.registers[x_reg_index];
         let reg_y = self.registers[y_reg_index];
         if reg_x == reg_y {
-          instruction_step = 2;
+          is_skipping = true;
         }
       }
 
@@ -104,21 +271,21 @@ impl<P: Platform> Interpreter<P> {
         let reg_x = self.registers[x_reg_index];
         let reg_y = self.registers[y_reg_index];
         if reg_x != reg_y {
-          instruction_step = 2;
+          is_skipping = true;
         }
       }
 
       Instruction::SkipIfKeyDown(reg_index) => {
         let key = Nibble::try_from(self.registers[reg_index])?;
         if self.platform.is_key_down(key) {
-          instruction_step = 2;
+          is_skipping = true;
         }
       }
 
       Instruction::SkipIfKeyUp(reg_index) => {
         let key = Nibble::try_from(self.registers[reg_index])?;
         if !self.platform.is_key_down(key) {
-          instruction_step = 2;
+          is_skipping = true;
         }
       }
 
@@ -134,6 +301,10 @@ impl<P: Platform> Interpreter<P> {
         self.index_register = address;
       }
 
+      Instruction::SetLongAddressRegister(address) => {
+        self.index_register = address;
+      }
+
       Instruction::SetRegisterRandom(reg_index, mask) => {
         self.registers[reg_index] = self.platform.get_random_byte() & mask;
       }
@@ -145,9 +316,12 @@ impl<P: Platform> Interpreter<P> {
       Instruction::WriteRegistersToMem(end_index) => {
         (0..=end_index.as_usize()).for_each(|reg_index| {
           let address = self.index_register + reg_index as i16;
-          self.memory[address] = self.registers[Nibble::try_from(reg_index as u8).unwrap()];
+          self.store_memory(
+            address,
+            self.registers[Nibble::try_from(reg_index as u8).unwrap()],
+          );
         });
-        self.index_register += (end_index.as_usize() + 1) as i16;
+        self.increment_index_register(end_index);
       }
 
       Instruction::LoadRegistersFromMem(end_index) => {
@@ -155,7 +329,54 @@ impl<P: Platform> Interpreter<P> {
           let address = self.index_register + reg_index as i16;
           self.registers[Nibble::try_from(reg_index as u8).unwrap()] = self.memory[address]
         });
-        self.index_register += (end_index.as_usize() + 1) as i16;
+        self.increment_index_register(end_index);
+      }
+
+      Instruction::WriteRegisterRangeToMem(x_reg_index, y_reg_index) => {
+        iter_register_range(x_reg_index, y_reg_index)
+          .enumerate()
+          .for_each(|(offset, reg_index)| {
+            self.store_memory(
+              self.index_register + offset as i16,
+              self.registers[reg_index],
+            );
+          });
+      }
+
+      Instruction::LoadRegisterRangeFromMem(x_reg_index, y_reg_index) => {
+        iter_register_range(x_reg_index, y_reg_index)
+          .enumerate()
+          .for_each(|(offset, reg_index)| {
+            self.registers[reg_index] = self.memory[self.index_register + offset as i16];
+          });
+      }
+
+      Instruction::SetAddressRegisterToDigit(reg_index) => {
+        let digit = Nibble::try_from(self.registers[reg_index] & Nibble::MAX.as_u8())?;
+        self.index_register = self.font.get_glyph_address(digit);
+      }
+
+      Instruction::SetAddressRegisterToBigDigit(reg_index) => {
+        let digit = Nibble::try_from(self.registers[reg_index] & Nibble::MAX.as_u8())?;
+        self.index_register = self.font.get_big_glyph_address(digit);
+      }
+
+      Instruction::SaveFlags(end_index) => {
+        let mut flags = [0; Nibble::SIZE];
+        (0..=end_index.as_usize()).for_each(|reg_index| {
+          flags[reg_index] = self.registers[Nibble::try_from(reg_index as u8).unwrap()];
+        });
+        self.platform.save_flags(&flags[..=end_index.as_usize()]);
+      }
+
+      Instruction::LoadFlags(end_index) => {
+        let mut flags = [0; Nibble::SIZE];
+        self
+          .platform
+          .load_flags(&mut flags[..=end_index.as_usize()]);
+        (0..=end_index.as_usize()).for_each(|reg_index| {
+          self.registers[Nibble::try_from(reg_index as u8).unwrap()] = flags[reg_index];
+        });
       }
 
       Instruction::RegisterToBCD(reg_index) => {
@@ -163,7 +384,7 @@ impl<P: Platform> Interpreter<P> {
 
         (0..=2).for_each(|offset| {
           let dev = 10_u8.pow(2 - offset);
-          self.memory[self.index_register + offset as i16] = reg / dev % 10;
+          self.store_memory(self.index_register + offset as i16, reg / dev % 10);
         });
       }
 
@@ -176,21 +397,27 @@ impl<P: Platform> Interpreter<P> {
         let reg_x = self.registers[x_reg_index];
         let reg_y = self.registers[y_reg_index];
         self.registers[x_reg_index] = reg_x | reg_y;
-        self.registers[Nibble::new::<15>()] = 0;
+        if self.quirks.vf_reset {
+          self.registers[Nibble::new::<15>()] = 0;
+        }
       }
 
       Instruction::AndRegisters(x_reg_index, y_reg_index) => {
         let reg_x = self.registers[x_reg_index];
         let reg_y = self.registers[y_reg_index];
         self.registers[x_reg_index] = reg_x & reg_y;
-        self.registers[Nibble::new::<15>()] = 0;
+        if self.quirks.vf_reset {
+          self.registers[Nibble::new::<15>()] = 0;
+        }
       }
 
       Instruction::XorRegisters(x_reg_index, y_reg_index) => {
         let reg_x = self.registers[x_reg_index];
         let reg_y = self.registers[y_reg_index];
         self.registers[x_reg_index] = reg_x ^ reg_y;
-        self.registers[Nibble::new::<15>()] = 0;
+        if self.quirks.vf_reset {
+          self.registers[Nibble::new::<15>()] = 0;
+        }
       }
 
       Instruction::AddAddressRegister(reg_index) => {
@@ -214,9 +441,9 @@ impl<P: Platform> Interpreter<P> {
       }
 
       Instruction::RShiftRegisters(x_reg_index, y_reg_index) => {
-        let reg_y = self.registers[y_reg_index];
-        self.registers[x_reg_index] = reg_y >> 1;
-        self.registers[Nibble::new::<15>()] = reg_y & 0x1;
+        let reg = self.registers[self.get_shift_source(x_reg_index, y_reg_index)];
+        self.registers[x_reg_index] = reg >> 1;
+        self.registers[Nibble::new::<15>()] = reg & 0x1;
       }
 
       Instruction::SubRegisterReversed(x_reg_index, y_reg_index) => {
@@ -228,25 +455,57 @@ impl<P: Platform> Interpreter<P> {
       }
 
       Instruction::LShiftRegisters(x_reg_index, y_reg_index) => {
-        let reg_y = self.registers[y_reg_index];
-        self.registers[x_reg_index] = reg_y << 1;
-        self.registers[Nibble::new::<15>()] = (reg_y & 0x80) >> 7;
+        let reg = self.registers[self.get_shift_source(x_reg_index, y_reg_index)];
+        self.registers[x_reg_index] = reg << 1;
+        self.registers[Nibble::new::<15>()] = (reg & 0x80) >> 7;
       }
 
       Instruction::ClearScreen => self.platform.clear_screen(),
 
+      Instruction::ScrollDown(rows_count) => self.platform.scroll_down(rows_count.as_u8()),
+
+      Instruction::ScrollUp(rows_count) => self.platform.scroll_up(rows_count.as_u8()),
+
+      Instruction::ScrollRight => self.platform.scroll_right(),
+
+      Instruction::ScrollLeft => self.platform.scroll_left(),
+
+      Instruction::LowResolution => self.platform.set_high_resolution(false),
+
+      Instruction::HighResolution => self.platform.set_high_resolution(true),
+
+      Instruction::SelectPlanes(planes) => self.platform.select_planes(planes.as_u8()),
+
       Instruction::DrawSprite(x_reg_index, y_reg_index, rows_count) => {
         let reg_x = self.registers[x_reg_index];
         let reg_y = self.registers[y_reg_index];
-        let mem_start = self.index_register;
-        let mem_end = self.index_register + rows_count.as_u8() as i16;
-        let sprite = Sprite::new(&self.memory[mem_start..mem_end]);
+        let is_wide = self.mode >= Mode::SuperChip && rows_count.as_u8() == 0;
+        let plane_size = match is_wide {
+          true => Sprite::WIDE_SIZE,
+          false => rows_count.as_usize(),
+        };
+        let planes_count = self.platform.get_selected_planes().count_ones() as usize;
+        let sprite_size = plane_size * planes_count;
+        // Sprite data past the end of memory wraps around to its start.
+        let sprite_data = (0..sprite_size)
+          .map(|offset| self.memory[self.index_register + offset as i16])
+          .collect::<Vec<_>>();
+        let sprite = match is_wide {
+          true => Sprite::new_wide(&sprite_data),
+          false => Sprite::new(&sprite_data),
+        };
 
+        let (width, height) = match self.platform.is_high_resolution() {
+          true => (HIGH_RES_SCREEN_WIDTH, HIGH_RES_SCREEN_HEIGHT),
+          false => (SCREEN_WIDTH, SCREEN_HEIGHT),
+        };
         let pos = Point {
-          x: reg_x % SCREEN_WIDTH as u8,
-          y: reg_y % SCREEN_HEIGHT as u8,
+          x: reg_x % width as u8,
+          y: reg_y % height as u8,
         };
-        self.registers[Nibble::new::<15>()] = self.platform.draw_sprite(pos, sprite) as u8;
+        let was_collision = self.platform.draw_sprite(pos, sprite, self.quirks.clipping);
+        self.registers[Nibble::new::<15>()] = was_collision as u8;
+        self.is_waiting_vblank = self.quirks.display_wait;
       }
 
       Instruction::SetDelayTimer(reg_index) => {
@@ -257,6 +516,18 @@ impl<P: Platform> Interpreter<P> {
         self.platform.set_sound_timer(self.registers[reg_index]);
       }
 
+      Instruction::LoadAudioPattern => {
+        let mut pattern = [0; AUDIO_PATTERN_SIZE];
+        pattern.iter_mut().enumerate().for_each(|(offset, value)| {
+          *value = self.memory[self.index_register + offset as i16];
+        });
+        self.platform.set_audio_pattern(pattern);
+      }
+
+      Instruction::SetPitch(reg_index) => {
+        self.platform.set_audio_pitch(self.registers[reg_index]);
+      }
+
       Instruction::WaitForKeyDown(reg_index) => {
         self.expecting_key = Some(reg_index);
         self.platform.get_last_pressed_key();
@@ -268,9 +539,10 @@ impl<P: Platform> Interpreter<P> {
       }
 
       Instruction::Call(address) => {
-        self
-          .stack
-          .push(self.instruction_address + ADDRESS_BYTE_STEP)?;
+        let return_address = self
+          .mode
+          .wrap_address(self.instruction_address + ADDRESS_BYTE_STEP);
+        self.stack.push(return_address)?;
         self.instruction_address = address;
         return Ok(());
       }
@@ -280,13 +552,165 @@ impl<P: Platform> Interpreter<P> {
         return Ok(());
       }
 
+      Instruction::Exit => {
+        self.is_exited = true;
+        return Ok(());
+      }
+
       Instruction::JumpV0(address) => {
-        self.instruction_address = address + self.registers[Nibble::new::<0>()] as i16;
+        let reg_index = match self.quirks.jumping {
+          true => Nibble::try_from((address.as_u16() >> 8) as u8)?,
+          false => Nibble::new::<0>(),
+        };
+        self.instruction_address = address + self.registers[reg_index] as i16;
         return Ok(());
       }
     }
 
-    self.instruction_address += instruction_step * ADDRESS_BYTE_STEP;
+    self.instruction_address += instruction.get_size();
+    if is_skipping {
+      self.instruction_address += self.get_instruction_size_at(self.instruction_address);
+    }
+    Ok(())
+  }
+
+  fn read_op_code(&self, address: Address) -> OpCode {
+    OpCode::from_bytes(self.memory[address], self.memory[address + 1])
+  }
+
+  fn get_instruction_size_at(&self, address: Address) -> i16 {
+    let op_code = self.read_op_code(address);
+    match self.mode >= Mode::XoChip && u16::from(op_code) == LONG_ADDRESS_OP_CODE {
+      true => 2 * ADDRESS_BYTE_STEP,
+      false => ADDRESS_BYTE_STEP,
+    }
+  }
+
+  fn increment_index_register(&mut self, end_index: Nibble) {
+    match self.quirks.memory_increment {
+      MemoryIncrement::XPlusOne => self.index_register += (end_index.as_usize() + 1) as i16,
+      MemoryIncrement::X => self.index_register += end_index.as_usize() as i16,
+      MemoryIncrement::Unchanged => {}
+    }
+  }
+
+  fn get_shift_source(&self, x_reg_index: Nibble, y_reg_index: Nibble) -> Nibble {
+    match self.quirks.shifting {
+      true => x_reg_index,
+      false => y_reg_index,
+    }
+  }
+}
+
+const NO_KEY: u8 = 0xFF;
+
+/// Writes the cause of a crash. Wrapping errors are stored as the error they wrap, the crash
+/// report is built again from the loaded state.
+fn write_error(writer: &mut StateWriter, error: &InterpreterError) {
+  match error {
+    InterpreterError::Crashed(cause) | InterpreterError::Fault { cause, .. } => {
+      write_error(writer, cause)
+    }
+    InterpreterError::UnknownOpCode(op_code) => {
+      writer.write_u8(0);
+      writer.write_u16(u16::from(*op_code));
+    }
+    InterpreterError::NibbleOverflow => writer.write_u8(1),
+    InterpreterError::StackOverflow => writer.write_u8(2),
+    InterpreterError::StackUnderflow => writer.write_u8(3),
+    InterpreterError::AddressOverflow(address) => {
+      writer.write_u8(4);
+      writer.write_u16(address.as_u16());
+    }
+    InterpreterError::UnknownCause => writer.write_u8(5),
+  }
+}
+
+fn read_error(reader: &mut StateReader) -> Result<InterpreterError, SaveStateError> {
+  let error = match reader.read_u8()? {
+    0 => InterpreterError::UnknownOpCode(OpCode::from(reader.read_u16()?)),
+    1 => InterpreterError::NibbleOverflow,
+    2 => InterpreterError::StackOverflow,
+    3 => InterpreterError::StackUnderflow,
+    4 => InterpreterError::AddressOverflow(Address::from(reader.read_u16()?)),
+    5 => InterpreterError::UnknownCause,
+    _ => return Err(SaveStateError::InvalidValue("crash cause")),
+  };
+  Ok(error)
+}
+
+impl<P: Platform> SaveState for Interpreter<P> {
+  const KIND: StateKind = StateKind::Interpreter;
+
+  fn write_state(&self, writer: &mut StateWriter) {
+    writer.write_u8(self.mode as u8);
+    writer.write_bytes(&self.registers.as_array());
+    writer.write_u16(self.index_register.as_u16());
+    writer.write_u16(self.instruction_address.as_u16());
+    writer.write_u8(self.stack.as_slice().len() as u8);
+    self
+      .stack
+      .as_slice()
+      .iter()
+      .for_each(|address| writer.write_u16(address.as_u16()));
+    writer.write_u32(self.memory.len() as u32);
+    writer.write_bytes(self.memory.as_slice());
+    writer.write_u8(self.expecting_key.map_or(NO_KEY, Nibble::as_u8));
+    writer.write_bool(self.crash.is_some());
+    writer.write_bool(self.is_exited);
+    writer.write_bool(self.is_waiting_vblank);
+    writer.write_u64(self.executed_count);
+    if let Some(crash) = &self.crash {
+      write_error(writer, crash);
+    }
+  }
+
+  fn read_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+    if reader.read_u8()? != self.mode as u8 {
+      return Err(SaveStateError::ModeMismatch);
+    }
+
+    let registers: [u8; Nibble::SIZE] = reader.read_array()?;
+    registers.iter().enumerate().for_each(|(index, &value)| {
+      self.registers[Nibble::try_from(index as u8).unwrap()] = value;
+    });
+    self.index_register = Address::from(reader.read_u16()?);
+    self.instruction_address = Address::from(reader.read_u16()?);
+
+    let mut stack = Stack::new();
+    for _ in 0..reader.read_u8()? {
+      stack
+        .push(Address::from(reader.read_u16()?))
+        .map_err(|_| SaveStateError::InvalidValue("stack depth"))?;
+    }
+    self.stack = stack;
+
+    if reader.read_u32()? as usize != self.memory.len() {
+      return Err(SaveStateError::InvalidValue("memory size"));
+    }
+    let memory_len = self.memory.len();
+    self
+      .memory
+      .as_mut_slice()
+      .copy_from_slice(reader.read_bytes(memory_len)?);
+
+    self.expecting_key = match reader.read_u8()? {
+      NO_KEY => None,
+      key => Some(Nibble::try_from(key).map_err(|_| SaveStateError::InvalidValue("key"))?),
+    };
+    let is_crashed = reader.read_bool()?;
+    self.is_exited = reader.read_bool()?;
+    self.is_waiting_vblank = reader.read_bool()?;
+    self.executed_count = match reader.get_version() {
+      1 => 0,
+      _ => reader.read_u64()?,
+    };
+    self.crash = match (is_crashed, reader.get_version()) {
+      (false, _) => None,
+      // Older versions keep only the crashed flag, so the report is built for an unknown cause.
+      (true, ..=4) => Some(self.create_fault(InterpreterError::UnknownCause)),
+      (true, _) => Some(self.create_fault(read_error(reader)?)),
+    };
     Ok(())
   }
 }
@@ -308,7 +732,31 @@ impl OpCode {
   }
 
   pub fn get_address(self) -> Address {
-    Address::try_from(self.get_part(0, 12)).unwrap()
+    Address::from(self.get_part(0, 12))
+  }
+
+  pub fn to_bytes(self) -> [u8; 2] {
+    self.0.to_be_bytes()
+  }
+
+  fn from_nibbles(first: u8, x: Nibble, y: Nibble, n: Nibble) -> Self {
+    Self(
+      ((first as u16) << 12)
+        | ((x.as_u8() as u16) << 8)
+        | ((y.as_u8() as u16) << 4)
+        | n.as_u8() as u16,
+    )
+  }
+
+  fn from_word(first: u8, x: Nibble, word: u8) -> Self {
+    Self(((first as u16) << 12) | ((x.as_u8() as u16) << 8) | word as u16)
+  }
+
+  fn from_address(first: u8, address: Address) -> Result<Self, InterpreterError> {
+    if address.as_u16() > SHORT_ADDRESS_MAX {
+      return Err(InterpreterError::AddressOverflow(address));
+    }
+    Ok(Self(((first as u16) << 12) | address.as_u16()))
   }
 
   fn get_part(self, index: u32, width: u32) -> u16 {
@@ -320,7 +768,7 @@ impl OpCode {
 
 impl fmt::Display for OpCode {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-    write!(f, "{}", self.0)
+    write!(f, "{:04X}", self.0)
   }
 }
 
@@ -330,6 +778,12 @@ impl From<OpCode> for u16 {
   }
 }
 
+impl From<u16> for OpCode {
+  fn from(value: u16) -> Self {
+    Self(value)
+  }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
 pub enum Instruction {
   /* Условия */
@@ -343,11 +797,18 @@ pub enum Instruction {
   SetRegister(Nibble, u8),
   CopyRegister(Nibble, Nibble),
   SetAddressRegister(Address),
+  SetLongAddressRegister(Address),
   SetRegisterRandom(Nibble, u8),
   GetDelayTimer(Nibble),
   WriteRegistersToMem(Nibble),
   LoadRegistersFromMem(Nibble),
+  WriteRegisterRangeToMem(Nibble, Nibble),
+  LoadRegisterRangeFromMem(Nibble, Nibble),
   RegisterToBCD(Nibble),
+  SetAddressRegisterToDigit(Nibble),
+  SetAddressRegisterToBigDigit(Nibble),
+  SaveFlags(Nibble),
+  LoadFlags(Nibble),
   /* Математические операции */
   AddRegister(Nibble, u8),
   OrRegisters(Nibble, Nibble),
@@ -362,9 +823,19 @@ pub enum Instruction {
   /* Экран */
   ClearScreen,
   DrawSprite(Nibble, Nibble, Nibble),
+  ScrollDown(Nibble),
+  ScrollUp(Nibble),
+  ScrollRight,
+  ScrollLeft,
+  LowResolution,
+  HighResolution,
+  SelectPlanes(Nibble),
   /* Таймеры */
   SetDelayTimer(Nibble),
   SetSoundTimer(Nibble),
+  /* Звук */
+  LoadAudioPattern,
+  SetPitch(Nibble),
   /* Клавиатура */
   WaitForKeyDown(Nibble),
   /* Работа с адресами */
@@ -372,6 +843,112 @@ pub enum Instruction {
   JumpV0(Address),
   Call(Address),
   Return,
+  Exit,
+}
+
+impl Instruction {
+  pub fn decode(code: OpCode, next_code: OpCode) -> Result<Self, InterpreterError> {
+    match u16::from(code) {
+      LONG_ADDRESS_OP_CODE => Ok(Instruction::SetLongAddressRegister(Address::from(
+        u16::from(next_code),
+      ))),
+      _ => Instruction::try_from(code),
+    }
+  }
+
+  pub fn get_size(self) -> i16 {
+    match self {
+      Instruction::SetLongAddressRegister(_) => 2 * ADDRESS_BYTE_STEP,
+      _ => ADDRESS_BYTE_STEP,
+    }
+  }
+
+  /// Bytes of the instruction as stored in memory, `get_size()` bytes long.
+  /// Decoding them with [`Instruction::decode`] gives back the same instruction.
+  pub fn encode(self) -> Result<Vec<u8>, InterpreterError> {
+    let mut res = OpCode::try_from(self)?.to_bytes().to_vec();
+    if let Instruction::SetLongAddressRegister(address) = self {
+      res.extend_from_slice(&address.as_u16().to_be_bytes());
+    }
+    Ok(res)
+  }
+}
+
+impl TryFrom<Instruction> for OpCode {
+  type Error = InterpreterError;
+
+  /// First word of the instruction. Fails when the address does not fit into `NNN`.
+  /// The address of `F000 NNNN` is stored in the following word, see [`Instruction::encode`].
+  fn try_from(instruction: Instruction) -> Result<Self, Self::Error> {
+    let zero = Nibble::default();
+    let op_code = match instruction {
+      /* Условия */
+      Instruction::SkipIfEqual(x, value) => OpCode::from_word(0x3, x, value),
+      Instruction::SkipIfNotEqual(x, value) => OpCode::from_word(0x4, x, value),
+      Instruction::SkipIfRegistersEqual(x, y) => OpCode::from_nibbles(0x5, x, y, zero),
+      Instruction::SkipIfRegistersNotEqual(x, y) => OpCode::from_nibbles(0x9, x, y, zero),
+      Instruction::SkipIfKeyDown(x) => OpCode::from_word(0xE, x, 0x9E),
+      Instruction::SkipIfKeyUp(x) => OpCode::from_word(0xE, x, 0xA1),
+      /* Запись регистров */
+      Instruction::SetRegister(x, value) => OpCode::from_word(0x6, x, value),
+      Instruction::CopyRegister(x, y) => OpCode::from_nibbles(0x8, x, y, zero),
+      Instruction::SetAddressRegister(address) => OpCode::from_address(0xA, address)?,
+      Instruction::SetLongAddressRegister(_) => OpCode(LONG_ADDRESS_OP_CODE),
+      Instruction::SetRegisterRandom(x, value) => OpCode::from_word(0xC, x, value),
+      Instruction::GetDelayTimer(x) => OpCode::from_word(0xF, x, 0x07),
+      Instruction::WriteRegistersToMem(x) => OpCode::from_word(0xF, x, 0x55),
+      Instruction::LoadRegistersFromMem(x) => OpCode::from_word(0xF, x, 0x65),
+      Instruction::WriteRegisterRangeToMem(x, y) => {
+        OpCode::from_nibbles(0x5, x, y, Nibble::new::<0x2>())
+      }
+      Instruction::LoadRegisterRangeFromMem(x, y) => {
+        OpCode::from_nibbles(0x5, x, y, Nibble::new::<0x3>())
+      }
+      Instruction::RegisterToBCD(x) => OpCode::from_word(0xF, x, 0x33),
+      Instruction::SetAddressRegisterToDigit(x) => OpCode::from_word(0xF, x, 0x29),
+      Instruction::SetAddressRegisterToBigDigit(x) => OpCode::from_word(0xF, x, 0x30),
+      Instruction::SaveFlags(x) => OpCode::from_word(0xF, x, 0x75),
+      Instruction::LoadFlags(x) => OpCode::from_word(0xF, x, 0x85),
+      /* Математические операции */
+      Instruction::AddRegister(x, value) => OpCode::from_word(0x7, x, value),
+      Instruction::OrRegisters(x, y) => OpCode::from_nibbles(0x8, x, y, Nibble::new::<0x1>()),
+      Instruction::AndRegisters(x, y) => OpCode::from_nibbles(0x8, x, y, Nibble::new::<0x2>()),
+      Instruction::XorRegisters(x, y) => OpCode::from_nibbles(0x8, x, y, Nibble::new::<0x3>()),
+      Instruction::AddAddressRegister(x) => OpCode::from_word(0xF, x, 0x1E),
+      Instruction::AddRegisters(x, y) => OpCode::from_nibbles(0x8, x, y, Nibble::new::<0x4>()),
+      Instruction::SubRegisters(x, y) => OpCode::from_nibbles(0x8, x, y, Nibble::new::<0x5>()),
+      Instruction::RShiftRegisters(x, y) => OpCode::from_nibbles(0x8, x, y, Nibble::new::<0x6>()),
+      Instruction::SubRegisterReversed(x, y) => {
+        OpCode::from_nibbles(0x8, x, y, Nibble::new::<0x7>())
+      }
+      Instruction::LShiftRegisters(x, y) => OpCode::from_nibbles(0x8, x, y, Nibble::new::<0xE>()),
+      /* Экран */
+      Instruction::ClearScreen => OpCode(0x00E0),
+      Instruction::DrawSprite(x, y, n) => OpCode::from_nibbles(0xD, x, y, n),
+      Instruction::ScrollDown(n) => OpCode(0x00C0 | n.as_u8() as u16),
+      Instruction::ScrollUp(n) => OpCode(0x00D0 | n.as_u8() as u16),
+      Instruction::ScrollRight => OpCode(0x00FB),
+      Instruction::ScrollLeft => OpCode(0x00FC),
+      Instruction::LowResolution => OpCode(0x00FE),
+      Instruction::HighResolution => OpCode(0x00FF),
+      Instruction::SelectPlanes(n) => OpCode::from_word(0xF, n, 0x01),
+      /* Таймеры */
+      Instruction::SetDelayTimer(x) => OpCode::from_word(0xF, x, 0x15),
+      Instruction::SetSoundTimer(x) => OpCode::from_word(0xF, x, 0x18),
+      /* Звук */
+      Instruction::LoadAudioPattern => OpCode(0xF002),
+      Instruction::SetPitch(x) => OpCode::from_word(0xF, x, 0x3A),
+      /* Клавиатура */
+      Instruction::WaitForKeyDown(x) => OpCode::from_word(0xF, x, 0x0A),
+      /* Работа с адресами */
+      Instruction::Jump(address) => OpCode::from_address(0x1, address)?,
+      Instruction::JumpV0(address) => OpCode::from_address(0xB, address)?,
+      Instruction::Call(address) => OpCode::from_address(0x2, address)?,
+      Instruction::Return => OpCode(0x00EE),
+      Instruction::Exit => OpCode(0x00FD),
+    };
+    Ok(op_code)
+  }
 }
 
 impl TryFrom<OpCode> for Instruction {
@@ -403,7 +980,17 @@ impl TryFrom<OpCode> for Instruction {
       (0xF, _, 0x0, 0x7) => Instruction::GetDelayTimer(code.get_nibble(2)),
       (0xF, _, 0x5, 0x5) => Instruction::WriteRegistersToMem(code.get_nibble(2)),
       (0xF, _, 0x6, 0x5) => Instruction::LoadRegistersFromMem(code.get_nibble(2)),
+      (0x5, .., 0x2) => {
+        Instruction::WriteRegisterRangeToMem(code.get_nibble(2), code.get_nibble(1))
+      }
+      (0x5, .., 0x3) => {
+        Instruction::LoadRegisterRangeFromMem(code.get_nibble(2), code.get_nibble(1))
+      }
       (0xF, _, 0x3, 0x3) => Instruction::RegisterToBCD(code.get_nibble(2)),
+      (0xF, _, 0x2, 0x9) => Instruction::SetAddressRegisterToDigit(code.get_nibble(2)),
+      (0xF, _, 0x3, 0x0) => Instruction::SetAddressRegisterToBigDigit(code.get_nibble(2)),
+      (0xF, _, 0x7, 0x5) => Instruction::SaveFlags(code.get_nibble(2)),
+      (0xF, _, 0x8, 0x5) => Instruction::LoadFlags(code.get_nibble(2)),
       /* Математические операции */
       (0x7, ..) => Instruction::AddRegister(code.get_nibble(2), code.get_word(0)),
       (0x8, .., 0x1) => Instruction::OrRegisters(code.get_nibble(2), code.get_nibble(1)),
@@ -417,12 +1004,22 @@ impl TryFrom<OpCode> for Instruction {
       (0x8, .., 0xE) => Instruction::LShiftRegisters(code.get_nibble(2), code.get_nibble(1)),
       /* Экран */
       (0x0, 0x0, 0xE, 0x0) => Instruction::ClearScreen,
+      (0x0, 0x0, 0xC, _) => Instruction::ScrollDown(code.get_nibble(0)),
+      (0x0, 0x0, 0xD, _) => Instruction::ScrollUp(code.get_nibble(0)),
+      (0x0, 0x0, 0xF, 0xB) => Instruction::ScrollRight,
+      (0x0, 0x0, 0xF, 0xC) => Instruction::ScrollLeft,
+      (0x0, 0x0, 0xF, 0xE) => Instruction::LowResolution,
+      (0x0, 0x0, 0xF, 0xF) => Instruction::HighResolution,
+      (0xF, _, 0x0, 0x1) => Instruction::SelectPlanes(code.get_nibble(2)),
       (0xD, ..) => {
         Instruction::DrawSprite(code.get_nibble(2), code.get_nibble(1), code.get_nibble(0))
       }
       /* Таймеры */
       (0xF, _, 0x1, 0x5) => Instruction::SetDelayTimer(code.get_nibble(2)),
       (0xF, _, 0x1, 0x8) => Instruction::SetSoundTimer(code.get_nibble(2)),
+      /* Звук */
+      (0xF, 0x0, 0x0, 0x2) => Instruction::LoadAudioPattern,
+      (0xF, _, 0x3, 0xA) => Instruction::SetPitch(code.get_nibble(2)),
       /* Клавиатура */
       (0xF, _, 0x0, 0xA) => Instruction::WaitForKeyDown(code.get_nibble(2)),
       /* Работа с адресами */
@@ -430,6 +1027,7 @@ impl TryFrom<OpCode> for Instruction {
       (0xB, ..) => Instruction::JumpV0(code.get_address()),
       (0x2, ..) => Instruction::Call(code.get_address()),
       (0x0, 0x0, 0xE, 0xE) => Instruction::Return,
+      (0x0, 0x0, 0xF, 0xD) => Instruction::Exit,
       _ => return Err(InterpreterError::UnknownOpCode(code)),
     };
     Ok(instruction)
@@ -1,25 +1,85 @@
 mod address;
+mod assembler;
+mod audio;
 mod controlled_interpreter;
+mod debugger;
+mod disassembler;
 mod errors;
 mod executable;
+#[cfg(feature = "export")]
+mod export;
+mod font;
 mod interpreter;
 mod keyboard;
 mod memory;
+mod mode;
+mod movie;
 mod nibble;
 mod platform;
+mod quirks;
+mod random;
 mod registers;
+mod rewind;
+mod save_state;
 mod screen;
 mod sprite;
 mod stack;
+mod state;
+mod timing;
+mod trace;
 
 pub use address::Address;
+pub use assembler::{assemble, Program, Symbol, DEFAULT_LOAD_POINT};
+pub use audio::{
+  write_wav, AudioBuffer, AudioConfig, AudioGenerator, DEFAULT_SAMPLE_RATE, DEFAULT_TONE_FREQUENCY,
+  DEFAULT_VOLUME,
+};
 pub use controlled_interpreter::{
-  ControlledInterpreter, DEFAULT_DELAY_TIMER_DURATION, DEFAULT_INSTRUCTION_DURATION,
+  ControlledInterpreter, FrameEvent, DEFAULT_DELAY_TIMER_DURATION, DEFAULT_INSTRUCTION_DURATION,
   DEFAULT_SOUND_TIMER_DURATION,
 };
+pub use debugger::{Breakpoint, StopReason, Watchpoint};
+pub use disassembler::{format_instruction, Disassembly, ListingLine, Syntax};
+#[cfg(feature = "export")]
+pub use errors::ExportError;
+pub use errors::{
+  AssemblerError, AssemblerErrorKind, CrashReport, InterpreterError, LoaderError, MovieError,
+  RewindError, SaveStateError,
+};
 pub use executable::{BaseExecutable, Executable};
-pub use interpreter::Interpreter;
+#[cfg(feature = "export")]
+pub use export::{
+  write_pbm, write_png, Color, GifRecorder, Palette, DEFAULT_PALETTE, GIF_FRAME_RATE,
+};
+pub use font::{
+  Font, FontSet, BIG_FONT_GLYPH_HEIGHT, BIG_FONT_SIZE, DEFAULT_FONT_LOAD_POINT, FONT_GLYPH_HEIGHT,
+  FONT_SIZE,
+};
+pub use interpreter::{Instruction, Interpreter, OpCode};
 pub use keyboard::{Key, Keyboard};
+pub use memory::{Memory, EXTENDED_MEMORY_SIZE, MEMORY_SIZE};
+pub use mode::Mode;
+pub use movie::{Movie, MovieConfig, MovieEvent, MoviePlayer, MovieRecorder};
 pub use nibble::Nibble;
-pub use platform::{BasePlatform, Platform};
-pub use screen::{ScreenFrame, SCREEN_HEIGHT, SCREEN_WIDTH};
+pub use platform::{
+  BasePlatform, Platform, RandomGenerator, AUDIO_PATTERN_SIZE, DEFAULT_AUDIO_PITCH, FLAGS_COUNT,
+};
+pub use quirks::{MemoryIncrement, Quirks};
+pub use random::{ScriptedRandom, SeededRandom, VipRandom, VIP_INTERPRETER_PAGE_SIZE};
+pub use rewind::{DEFAULT_REWIND_CAPACITY, DEFAULT_REWIND_INTERVAL};
+pub use save_state::{
+  crc32, SaveState, StateKind, StateReader, StateWriter, SAVE_STATE_MAGIC, SAVE_STATE_VERSION,
+};
+pub use screen::{
+  ScreenFrame, ALL_PLANES, HIGH_RES_SCREEN_HEIGHT, HIGH_RES_SCREEN_WIDTH, PLANES_COUNT,
+  SCREEN_HEIGHT, SCREEN_WIDTH,
+};
+pub use state::InterpreterState;
+pub use timing::{
+  get_frame_start, get_vip_cycles_duration, get_vip_frame_duration, Timing, FRAME_RATE,
+  VIP_CLOCKS_PER_MACHINE_CYCLE, VIP_CLOCK_FREQUENCY, VIP_FRAME_CYCLES, VIP_INTERRUPT_CYCLES,
+};
+pub use trace::{
+  BinaryLogSink, RingBufferSink, TextLogSink, TraceRecord, TraceSink, BINARY_TRACE_MAGIC,
+  BINARY_TRACE_VERSION,
+};
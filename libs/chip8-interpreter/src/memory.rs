@@ -2,18 +2,37 @@ use std::ops::{Index, IndexMut, Range, RangeInclusive};
 
 use super::address::Address;
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
-pub struct Memory([u8; Address::SIZE]);
+pub const MEMORY_SIZE: usize = 1 << 12;
+pub const EXTENDED_MEMORY_SIZE: usize = Address::SIZE;
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct Memory(Vec<u8>);
 
 impl Memory {
-  pub fn new() -> Self {
-    Self([0; Address::SIZE])
+  pub fn new(size: usize) -> Self {
+    Self(vec![0; size])
+  }
+
+  pub fn len(&self) -> usize {
+    self.0.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.0.is_empty()
+  }
+
+  pub fn as_slice(&self) -> &[u8] {
+    &self.0
+  }
+
+  pub fn as_mut_slice(&mut self) -> &mut [u8] {
+    &mut self.0
   }
 }
 
 impl Default for Memory {
   fn default() -> Self {
-    Self::new()
+    Self::new(MEMORY_SIZE)
   }
 }
 
@@ -21,13 +40,14 @@ impl Index<Address> for Memory {
   type Output = u8;
 
   fn index(&self, index: Address) -> &Self::Output {
-    &self.0[index.as_usize()]
+    &self.0[index.as_usize() % self.0.len()]
   }
 }
 
 impl IndexMut<Address> for Memory {
   fn index_mut(&mut self, index: Address) -> &mut Self::Output {
-    &mut self.0[index.as_usize()]
+    let len = self.0.len();
+    &mut self.0[index.as_usize() % len]
   }
 }
 
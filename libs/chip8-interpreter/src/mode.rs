@@ -0,0 +1,50 @@
+use crate::{
+  address::Address,
+  interpreter::Instruction,
+  memory::{EXTENDED_MEMORY_SIZE, MEMORY_SIZE},
+};
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Default)]
+pub enum Mode {
+  #[default]
+  Chip8,
+  SuperChip,
+  XoChip,
+}
+
+impl Mode {
+  pub fn get_memory_size(self) -> usize {
+    match self {
+      Mode::Chip8 | Mode::SuperChip => MEMORY_SIZE,
+      Mode::XoChip => EXTENDED_MEMORY_SIZE,
+    }
+  }
+
+  /// `PC` and `I` wrap around at the end of the memory: past `0xFFF` in CHIP-8 and SUPER-CHIP,
+  /// past `0xFFFF` in XO-CHIP.
+  pub fn wrap_address(self, address: Address) -> Address {
+    Address::from((address.as_usize() % self.get_memory_size()) as u16)
+  }
+
+  pub(crate) fn supports(self, instruction: Instruction) -> bool {
+    match instruction {
+      Instruction::ScrollDown(_)
+      | Instruction::ScrollRight
+      | Instruction::ScrollLeft
+      | Instruction::Exit
+      | Instruction::LowResolution
+      | Instruction::HighResolution
+      | Instruction::SetAddressRegisterToBigDigit(_)
+      | Instruction::SaveFlags(_)
+      | Instruction::LoadFlags(_) => self >= Mode::SuperChip,
+      Instruction::ScrollUp(_)
+      | Instruction::SetLongAddressRegister(_)
+      | Instruction::WriteRegisterRangeToMem(..)
+      | Instruction::LoadRegisterRangeFromMem(..)
+      | Instruction::SelectPlanes(_)
+      | Instruction::LoadAudioPattern
+      | Instruction::SetPitch(_) => self >= Mode::XoChip,
+      _ => true,
+    }
+  }
+}
@@ -0,0 +1,416 @@
+use std::time::Duration;
+
+use crate::{
+  errors::{InterpreterError, LoaderError, MovieError, SaveStateError},
+  executable::BaseExecutable,
+  font::{Font, BIG_FONT_SIZE, FONT_SIZE},
+  platform::BasePlatform,
+  quirks::{MemoryIncrement, Quirks},
+  random::SeededRandom,
+  save_state::{crc32, SaveState, StateKind, StateReader, StateWriter},
+  timing::{get_frame_start, get_vip_frame_duration, Timing},
+  Address, ControlledInterpreter, Interpreter, Mode, Nibble, StopReason,
+  DEFAULT_DELAY_TIMER_DURATION, DEFAULT_INSTRUCTION_DURATION, DEFAULT_SOUND_TIMER_DURATION,
+};
+
+/// Everything besides the image and the input that affects a run.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct MovieConfig {
+  pub mode: Mode,
+  pub quirks: Quirks,
+  pub font: Font,
+  pub load_point: Address,
+  pub instruction_duration: Duration,
+  pub delay_timer_duration: Duration,
+  pub sound_timer_duration: Duration,
+  /// Seed of the [`SeededRandom`] of the platform.
+  pub seed: u64,
+  /// The instruction and timer durations above apply only to [`Timing::Fixed`].
+  pub timing: Timing,
+}
+
+impl MovieConfig {
+  pub fn create_interpreter(
+    &self,
+    image: &[u8],
+  ) -> Result<ControlledInterpreter<BasePlatform<SeededRandom>>, LoaderError> {
+    let platform = BasePlatform::new(SeededRandom::new(self.seed));
+    let executable = BaseExecutable::new(image, self.load_point);
+    let interpreter = Interpreter::new(platform, executable, self.mode, self.font, self.quirks)?;
+    Ok(match self.timing {
+      Timing::Fixed => ControlledInterpreter::new(
+        interpreter,
+        self.instruction_duration,
+        self.delay_timer_duration,
+        self.sound_timer_duration,
+      ),
+      Timing::CosmacVip => ControlledInterpreter::new_cosmac_vip(interpreter),
+      Timing::FrameBased {
+        instructions_per_frame,
+      } => ControlledInterpreter::new_frame_based(interpreter, instructions_per_frame),
+    })
+  }
+
+  /// Simulated time at which the frame with the given index ends.
+  fn get_frame_end(&self, frame: usize) -> Duration {
+    let frame_duration = match self.timing {
+      Timing::Fixed => self.delay_timer_duration.max(Duration::from_nanos(1)),
+      Timing::CosmacVip => get_vip_frame_duration(),
+      Timing::FrameBased { .. } => return get_frame_start(frame as u64 + 1),
+    };
+    Duration::from_nanos((frame_duration.as_nanos() * (frame as u128 + 1)) as u64)
+  }
+}
+
+impl Default for MovieConfig {
+  fn default() -> Self {
+    Self {
+      mode: Mode::default(),
+      quirks: Quirks::default(),
+      font: Font::default(),
+      load_point: Address::new::<0x200>(),
+      instruction_duration: DEFAULT_INSTRUCTION_DURATION,
+      delay_timer_duration: DEFAULT_DELAY_TIMER_DURATION,
+      sound_timer_duration: DEFAULT_SOUND_TIMER_DURATION,
+      seed: 0,
+      timing: Timing::default(),
+    }
+  }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct MovieEvent {
+  pub time: Duration,
+  pub key: Nibble,
+  pub is_down: bool,
+}
+
+/// Recorded run: configuration, checksum of the image, key events and the hash of the screen
+/// at the end of every frame. Frames last one tick of the delay timer of the timing model.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Movie {
+  config: MovieConfig,
+  image_checksum: u32,
+  duration: Duration,
+  events: Vec<MovieEvent>,
+  frame_hashes: Vec<u64>,
+}
+
+impl Movie {
+  /// Reads a movie written by [`SaveState::save_state`].
+  pub fn from_bytes(data: &[u8]) -> Result<Self, SaveStateError> {
+    let mut movie = Self::default();
+    movie.load_state(data)?;
+    Ok(movie)
+  }
+
+  pub fn get_config(&self) -> &MovieConfig {
+    &self.config
+  }
+
+  pub fn get_image_checksum(&self) -> u32 {
+    self.image_checksum
+  }
+
+  /// Simulated length of the run.
+  pub fn get_duration(&self) -> Duration {
+    self.duration
+  }
+
+  pub fn get_events(&self) -> &[MovieEvent] {
+    &self.events
+  }
+
+  pub fn get_frame_hashes(&self) -> &[u64] {
+    &self.frame_hashes
+  }
+}
+
+fn write_duration(writer: &mut StateWriter, duration: Duration) {
+  writer.write_u64(duration.as_nanos() as u64);
+}
+
+fn read_duration(reader: &mut StateReader) -> Result<Duration, SaveStateError> {
+  Ok(Duration::from_nanos(reader.read_u64()?))
+}
+
+fn read_count(reader: &mut StateReader, item_size: usize) -> Result<usize, SaveStateError> {
+  let count = reader.read_u32()? as usize;
+  // Rejects lengths that cannot fit into the rest of the data before allocating for them.
+  reader.clone().read_bytes(count * item_size)?;
+  Ok(count)
+}
+
+impl SaveState for Movie {
+  const KIND: StateKind = StateKind::Movie;
+
+  fn write_state(&self, writer: &mut StateWriter) {
+    let config = &self.config;
+    writer.write_u8(config.mode as u8);
+    writer.write_bool(config.quirks.vf_reset);
+    writer.write_u8(config.quirks.memory_increment as u8);
+    writer.write_bool(config.quirks.shifting);
+    writer.write_bool(config.quirks.jumping);
+    writer.write_bool(config.quirks.clipping);
+    writer.write_bool(config.quirks.display_wait);
+    writer.write_bytes(config.font.get_glyphs());
+    writer.write_bytes(config.font.get_big_glyphs());
+    writer.write_u16(config.font.get_load_point().as_u16());
+    writer.write_u16(config.load_point.as_u16());
+    write_duration(writer, config.instruction_duration);
+    write_duration(writer, config.delay_timer_duration);
+    write_duration(writer, config.sound_timer_duration);
+    writer.write_u64(config.seed);
+    config.timing.write_state(writer);
+
+    writer.write_u32(self.image_checksum);
+    write_duration(writer, self.duration);
+    writer.write_u32(self.events.len() as u32);
+    self.events.iter().for_each(|event| {
+      write_duration(writer, event.time);
+      writer.write_u8(event.key.as_u8());
+      writer.write_bool(event.is_down);
+    });
+    writer.write_u32(self.frame_hashes.len() as u32);
+    self
+      .frame_hashes
+      .iter()
+      .for_each(|&hash| writer.write_u64(hash));
+  }
+
+  fn read_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+    let mode = match reader.read_u8()? {
+      0 => Mode::Chip8,
+      1 => Mode::SuperChip,
+      2 => Mode::XoChip,
+      _ => return Err(SaveStateError::InvalidValue("mode")),
+    };
+    let vf_reset = reader.read_bool()?;
+    let memory_increment = match reader.read_u8()? {
+      0 => MemoryIncrement::XPlusOne,
+      1 => MemoryIncrement::X,
+      2 => MemoryIncrement::Unchanged,
+      _ => return Err(SaveStateError::InvalidValue("memory increment")),
+    };
+    let quirks = Quirks {
+      vf_reset,
+      memory_increment,
+      shifting: reader.read_bool()?,
+      jumping: reader.read_bool()?,
+      clipping: reader.read_bool()?,
+      display_wait: reader.read_bool()?,
+    };
+    let glyphs: [u8; FONT_SIZE] = reader.read_array()?;
+    let big_glyphs: [u8; BIG_FONT_SIZE] = reader.read_array()?;
+    let font = Font::new(glyphs, Address::from(reader.read_u16()?)).with_big_glyphs(big_glyphs);
+    self.config = MovieConfig {
+      mode,
+      quirks,
+      font,
+      load_point: Address::from(reader.read_u16()?),
+      instruction_duration: read_duration(reader)?,
+      delay_timer_duration: read_duration(reader)?,
+      sound_timer_duration: read_duration(reader)?,
+      seed: reader.read_u64()?,
+      timing: match reader.get_version() {
+        ..=5 => Timing::Fixed,
+        _ => Timing::read_state(reader)?,
+      },
+    };
+
+    self.image_checksum = reader.read_u32()?;
+    self.duration = read_duration(reader)?;
+    self.events = (0..read_count(reader, 10)?)
+      .map(|_| {
+        let time = read_duration(reader)?;
+        let key =
+          Nibble::try_from(reader.read_u8()?).map_err(|_| SaveStateError::InvalidValue("key"))?;
+        let is_down = reader.read_bool()?;
+        Ok(MovieEvent { time, key, is_down })
+      })
+      .collect::<Result<_, SaveStateError>>()?;
+    if !self.events.is_sorted_by_key(|event| event.time) {
+      return Err(SaveStateError::InvalidValue("event order"));
+    }
+    self.frame_hashes = (0..read_count(reader, 8)?)
+      .map(|_| reader.read_u64())
+      .collect::<Result<_, _>>()?;
+
+    // Frames of another timing model end at other times, so their count gives it away.
+    let frames = self.frame_hashes.len();
+    let is_last_frame_ended = frames == 0 || self.config.get_frame_end(frames - 1) <= self.duration;
+    if !is_last_frame_ended || self.config.get_frame_end(frames) <= self.duration {
+      return Err(SaveStateError::InvalidValue("frame count"));
+    }
+    Ok(())
+  }
+}
+
+/// Runs an interpreter and records its input into a [`Movie`].
+///
+/// Only key changes made through [`MovieRecorder::change_keyboard_state`] are recorded.
+pub struct MovieRecorder {
+  interpreter: ControlledInterpreter<BasePlatform<SeededRandom>>,
+  movie: Movie,
+}
+
+impl MovieRecorder {
+  pub fn new(image: &[u8], config: MovieConfig) -> Result<Self, LoaderError> {
+    Ok(Self {
+      interpreter: config.create_interpreter(image)?,
+      movie: Movie {
+        config,
+        image_checksum: crc32(image),
+        ..Movie::default()
+      },
+    })
+  }
+
+  pub fn get_interpreter(&self) -> &ControlledInterpreter<BasePlatform<SeededRandom>> {
+    &self.interpreter
+  }
+
+  pub fn get_interpreter_mut(&mut self) -> &mut ControlledInterpreter<BasePlatform<SeededRandom>> {
+    &mut self.interpreter
+  }
+
+  pub fn change_keyboard_state(&mut self, key: Nibble, is_down: bool) {
+    self.movie.events.push(MovieEvent {
+      time: self.interpreter.get_elapsed(),
+      key,
+      is_down,
+    });
+    self
+      .interpreter
+      .get_platform_mut()
+      .change_keyboard_state(key, is_down);
+  }
+
+  /// Same as [`ControlledInterpreter::simulate_duration`], also hashing the screen at the end of
+  /// every frame.
+  pub fn simulate_duration(&mut self, duration: Duration) -> Result<StopReason, InterpreterError> {
+    let end = self.interpreter.get_elapsed() + duration;
+    while self.interpreter.get_elapsed() < end {
+      let frame_end = self
+        .movie
+        .config
+        .get_frame_end(self.movie.frame_hashes.len());
+      let step = frame_end.min(end) - self.interpreter.get_elapsed();
+      let stop_reason = self.interpreter.simulate_duration(step)?;
+
+      if self.interpreter.get_elapsed() == frame_end {
+        let screen_frame = self.interpreter.get_platform().get_screen_frame();
+        self.movie.frame_hashes.push(screen_frame.get_hash());
+      }
+      if stop_reason != StopReason::TimeElapsed {
+        return Ok(stop_reason);
+      }
+    }
+    Ok(StopReason::TimeElapsed)
+  }
+
+  pub fn finish(mut self) -> Movie {
+    self.movie.duration = self.interpreter.get_elapsed();
+    self.movie
+  }
+}
+
+/// Replays a [`Movie`] and checks that every frame matches the recorded one.
+pub struct MoviePlayer {
+  interpreter: ControlledInterpreter<BasePlatform<SeededRandom>>,
+  movie: Movie,
+  next_event: usize,
+  frame: usize,
+}
+
+impl MoviePlayer {
+  pub fn new(movie: Movie, image: &[u8]) -> Result<Self, MovieError> {
+    if crc32(image) != movie.image_checksum {
+      return Err(MovieError::ImageMismatch);
+    }
+    Ok(Self {
+      interpreter: movie.config.create_interpreter(image)?,
+      movie,
+      next_event: 0,
+      frame: 0,
+    })
+  }
+
+  pub fn get_interpreter(&self) -> &ControlledInterpreter<BasePlatform<SeededRandom>> {
+    &self.interpreter
+  }
+
+  pub fn get_movie(&self) -> &Movie {
+    &self.movie
+  }
+
+  /// Number of played frames.
+  pub fn get_frame(&self) -> usize {
+    self.frame
+  }
+
+  pub fn is_finished(&self) -> bool {
+    self.interpreter.get_elapsed() >= self.movie.duration
+  }
+
+  /// Plays until the end of the next frame or the movie.
+  pub fn play_frame(&mut self) -> Result<(), MovieError> {
+    let frame_end = self.movie.config.get_frame_end(self.frame);
+    let end = frame_end.min(self.movie.duration);
+
+    loop {
+      self.apply_events();
+      let elapsed = self.interpreter.get_elapsed();
+      if elapsed >= end {
+        break;
+      }
+      let next_stop = self
+        .movie
+        .events
+        .get(self.next_event)
+        .map_or(end, |event| event.time.min(end));
+      self.interpreter.simulate_duration(next_stop - elapsed)?;
+    }
+
+    if self.interpreter.get_elapsed() == frame_end {
+      if let Some(&expected) = self.movie.frame_hashes.get(self.frame) {
+        let actual = self
+          .interpreter
+          .get_platform()
+          .get_screen_frame()
+          .get_hash();
+        if actual != expected {
+          return Err(MovieError::Desync {
+            frame: self.frame,
+            expected,
+            actual,
+          });
+        }
+      }
+      self.frame += 1;
+    }
+    Ok(())
+  }
+
+  /// Plays the rest of the movie, stopping at the first frame that differs from the recording.
+  pub fn play(&mut self) -> Result<(), MovieError> {
+    while !self.is_finished() {
+      self.play_frame()?;
+    }
+    Ok(())
+  }
+
+  fn apply_events(&mut self) {
+    let elapsed = self.interpreter.get_elapsed();
+    while let Some(event) = self.movie.events.get(self.next_event) {
+      if event.time > elapsed {
+        break;
+      }
+      self
+        .interpreter
+        .get_platform_mut()
+        .change_keyboard_state(event.key, event.is_down);
+      self.next_event += 1;
+    }
+  }
+}
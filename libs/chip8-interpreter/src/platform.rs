@@ -1,31 +1,76 @@
-use crate::{keyboard::Keyboard, screen::ScreenFrame, Nibble, SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::{
+  errors::SaveStateError,
+  keyboard::Keyboard,
+  save_state::{SaveState, StateKind, StateReader, StateWriter},
+  screen::{ScreenFrame, ALL_PLANES, HIGH_RES_SCREEN_HEIGHT, HIGH_RES_SCREEN_WIDTH, PLANES_COUNT},
+  Nibble,
+};
 
 use super::sprite::{Point, Sprite};
 
 pub trait Platform {
   fn get_random_byte(&mut self) -> u8;
-  fn draw_sprite(&mut self, pos: Point, sprite: Sprite) -> bool;
+  fn draw_sprite(&mut self, pos: Point, sprite: Sprite, is_clipping: bool) -> bool;
   fn clear_screen(&mut self);
+  fn is_high_resolution(&self) -> bool;
+  fn set_high_resolution(&mut self, is_high_resolution: bool);
+  fn get_selected_planes(&self) -> u8;
+  fn select_planes(&mut self, planes: u8);
+  fn scroll_down(&mut self, rows_count: u8);
+  fn scroll_up(&mut self, rows_count: u8);
+  fn scroll_right(&mut self);
+  fn scroll_left(&mut self);
   fn get_delay_timer(&self) -> u8;
   fn get_sound_timer(&self) -> u8;
   fn set_delay_timer(&mut self, value: u8);
   fn set_sound_timer(&mut self, value: u8);
+  fn set_audio_pattern(&mut self, pattern: [u8; AUDIO_PATTERN_SIZE]);
+  fn set_audio_pitch(&mut self, pitch: u8);
   fn is_key_down(&self, key: Nibble) -> bool;
   fn get_last_pressed_key(&mut self) -> Option<Nibble>;
+  fn save_flags(&mut self, flags: &[u8]);
+  fn load_flags(&mut self, flags: &mut [u8]);
+  fn notify_vblank(&mut self) {}
 }
 
-pub trait RandomGenerator: FnMut() -> u8 {}
+/// Source of CXNN values. Implemented for every `FnMut() -> u8`.
+pub trait RandomGenerator {
+  fn get_random_byte(&mut self) -> u8;
+
+  /// Called on every vertical blank, for generators that depend on the frame count.
+  fn notify_vblank(&mut self) {}
+
+  /// Writes the position in the sequence to platform save states, so rewinds and loaded states
+  /// draw the same values again. Generators without such state write nothing.
+  fn write_state(&self, _writer: &mut StateWriter) {}
+
+  fn read_state(&mut self, _reader: &mut StateReader) -> Result<(), SaveStateError> {
+    Ok(())
+  }
+}
+
+impl<R: FnMut() -> u8> RandomGenerator for R {
+  fn get_random_byte(&mut self) -> u8 {
+    self()
+  }
+}
 
-impl<R: FnMut() -> u8> RandomGenerator for R {}
+pub const FLAGS_COUNT: usize = Nibble::SIZE;
+pub const AUDIO_PATTERN_SIZE: usize = 16;
+pub const DEFAULT_AUDIO_PITCH: u8 = 64;
 
 #[derive(Eq, PartialEq, Ord, PartialOrd, Debug, Default)]
 pub struct BasePlatform<R: RandomGenerator> {
   rand: R,
   screen_frame: ScreenFrame,
+  selected_planes: u8,
   delay_timer: u8,
   sound_timer: u8,
+  audio_pattern: [u8; AUDIO_PATTERN_SIZE],
+  audio_pitch: u8,
   keyboard: Keyboard,
   last_pressed_key: Option<Nibble>,
+  flags: [u8; FLAGS_COUNT],
 }
 
 impl<R: RandomGenerator> BasePlatform<R> {
@@ -33,10 +78,14 @@ impl<R: RandomGenerator> BasePlatform<R> {
     Self {
       rand,
       screen_frame: ScreenFrame::new(),
+      selected_planes: 1,
       delay_timer: 0,
       sound_timer: 0,
+      audio_pattern: [0; AUDIO_PATTERN_SIZE],
+      audio_pitch: DEFAULT_AUDIO_PITCH,
       keyboard: Keyboard::new(),
       last_pressed_key: None,
+      flags: [0; FLAGS_COUNT],
     }
   }
 
@@ -52,38 +101,104 @@ impl<R: RandomGenerator> BasePlatform<R> {
     }
   }
 
+  pub fn get_random_generator(&self) -> &R {
+    &self.rand
+  }
+
   pub fn get_screen_frame(&self) -> &ScreenFrame {
     &self.screen_frame
   }
+
+  pub fn get_audio_pattern(&self) -> &[u8; AUDIO_PATTERN_SIZE] {
+    &self.audio_pattern
+  }
+
+  pub fn get_audio_pitch(&self) -> u8 {
+    self.audio_pitch
+  }
+
+  pub fn get_flags(&self) -> &[u8; FLAGS_COUNT] {
+    &self.flags
+  }
+
+  pub fn set_flags(&mut self, flags: [u8; FLAGS_COUNT]) {
+    self.flags = flags;
+  }
 }
 
 impl<R: RandomGenerator> Platform for BasePlatform<R> {
   fn get_random_byte(&mut self) -> u8 {
-    (self.rand)()
+    self.rand.get_random_byte()
   }
 
   fn clear_screen(&mut self) {
-    self.screen_frame.iter_rows_mut().for_each(|row| {
-      row.fill(false);
-    });
+    self.screen_frame.clear(self.selected_planes);
   }
 
-  fn draw_sprite(&mut self, pos: Point, sprite: Sprite) -> bool {
+  fn draw_sprite(&mut self, pos: Point, sprite: Sprite, is_clipping: bool) -> bool {
+    let width = self.screen_frame.get_width();
+    let height = self.screen_frame.get_height();
+    let planes = (0..PLANES_COUNT)
+      .map(|plane| 1 << plane)
+      .filter(|plane| self.selected_planes & plane != 0);
+    let planes_count = planes.clone().count();
     let mut was_collision = false;
-    sprite.iter_pixels().for_each(|point| {
-      let y_index = (point.y + pos.y) as usize;
-      let x_index = (point.x + pos.x) as usize;
-      if y_index >= SCREEN_HEIGHT || x_index >= SCREEN_WIDTH {
-        return;
-      }
-      let cur_state = &mut self.screen_frame.0[y_index][x_index];
-      *cur_state ^= true;
-      was_collision |= !*cur_state;
-    });
+    planes
+      .zip(sprite.split_planes(planes_count))
+      .for_each(|(plane, plane_sprite)| {
+        plane_sprite.iter_pixels().for_each(|point| {
+          let mut y_index = point.y as usize + pos.y as usize;
+          let mut x_index = point.x as usize + pos.x as usize;
+          if is_clipping && (y_index >= height || x_index >= width) {
+            return;
+          }
+          y_index %= height;
+          x_index %= width;
+          let cur_state = self.screen_frame.get_pixel_mut(x_index, y_index);
+          *cur_state ^= plane;
+          was_collision |= *cur_state & plane == 0;
+        });
+      });
 
     was_collision
   }
 
+  fn is_high_resolution(&self) -> bool {
+    self.screen_frame.is_high_resolution()
+  }
+
+  fn set_high_resolution(&mut self, is_high_resolution: bool) {
+    self.screen_frame.set_high_resolution(is_high_resolution);
+  }
+
+  fn get_selected_planes(&self) -> u8 {
+    self.selected_planes
+  }
+
+  fn select_planes(&mut self, planes: u8) {
+    self.selected_planes = planes & ALL_PLANES;
+  }
+
+  fn scroll_down(&mut self, rows_count: u8) {
+    self
+      .screen_frame
+      .scroll_down(rows_count as usize, self.selected_planes);
+  }
+
+  fn scroll_up(&mut self, rows_count: u8) {
+    self
+      .screen_frame
+      .scroll_up(rows_count as usize, self.selected_planes);
+  }
+
+  fn scroll_right(&mut self) {
+    self.screen_frame.scroll_right(self.selected_planes);
+  }
+
+  fn scroll_left(&mut self) {
+    self.screen_frame.scroll_left(self.selected_planes);
+  }
+
   fn get_delay_timer(&self) -> u8 {
     self.delay_timer
   }
@@ -100,6 +215,14 @@ impl<R: RandomGenerator> Platform for BasePlatform<R> {
     self.sound_timer = value;
   }
 
+  fn set_audio_pattern(&mut self, pattern: [u8; AUDIO_PATTERN_SIZE]) {
+    self.audio_pattern = pattern;
+  }
+
+  fn set_audio_pitch(&mut self, pitch: u8) {
+    self.audio_pitch = pitch;
+  }
+
   fn is_key_down(&self, key_index: Nibble) -> bool {
     self.keyboard[key_index].is_down()
   }
@@ -107,4 +230,74 @@ impl<R: RandomGenerator> Platform for BasePlatform<R> {
   fn get_last_pressed_key(&mut self) -> Option<Nibble> {
     self.last_pressed_key.take()
   }
+
+  fn save_flags(&mut self, flags: &[u8]) {
+    let count = flags.len().min(FLAGS_COUNT);
+    self.flags[..count].copy_from_slice(&flags[..count]);
+  }
+
+  fn load_flags(&mut self, flags: &mut [u8]) {
+    let count = flags.len().min(FLAGS_COUNT);
+    flags[..count].copy_from_slice(&self.flags[..count]);
+  }
+
+  fn notify_vblank(&mut self) {
+    self.rand.notify_vblank();
+  }
+}
+
+const NO_KEY: u8 = 0xFF;
+
+impl<R: RandomGenerator> SaveState for BasePlatform<R> {
+  const KIND: StateKind = StateKind::Platform;
+
+  fn write_state(&self, writer: &mut StateWriter) {
+    writer.write_bool(self.screen_frame.is_high_resolution());
+    (0..HIGH_RES_SCREEN_HEIGHT).for_each(|y| {
+      (0..HIGH_RES_SCREEN_WIDTH).for_each(|x| writer.write_u8(self.screen_frame.get_pixel(x, y)));
+    });
+    writer.write_u8(self.selected_planes);
+    writer.write_u8(self.delay_timer);
+    writer.write_u8(self.sound_timer);
+    writer.write_bytes(&self.audio_pattern);
+    writer.write_u8(self.audio_pitch);
+    self.keyboard.iter().for_each(|key| {
+      writer.write_u8(key.is_down() as u8 | (key.is_trackable() as u8) << 1);
+    });
+    writer.write_u8(self.last_pressed_key.map_or(NO_KEY, Nibble::as_u8));
+    writer.write_bytes(&self.flags);
+    self.rand.write_state(writer);
+  }
+
+  fn read_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+    self.screen_frame.set_high_resolution(reader.read_bool()?);
+    for y in 0..HIGH_RES_SCREEN_HEIGHT {
+      for x in 0..HIGH_RES_SCREEN_WIDTH {
+        let pixel = reader.read_u8()?;
+        if pixel & !ALL_PLANES != 0 {
+          return Err(SaveStateError::InvalidValue("pixel"));
+        }
+        *self.screen_frame.get_pixel_mut(x, y) = pixel;
+      }
+    }
+    self.selected_planes = reader.read_u8()? & ALL_PLANES;
+    self.delay_timer = reader.read_u8()?;
+    self.sound_timer = reader.read_u8()?;
+    self.audio_pattern = reader.read_array()?;
+    self.audio_pitch = reader.read_u8()?;
+    for key in self.keyboard.iter_mut() {
+      let value = reader.read_u8()?;
+      key.set_is_down(value & 0x1 != 0);
+      key.set_is_trackable(value & 0x2 != 0);
+    }
+    self.last_pressed_key = match reader.read_u8()? {
+      NO_KEY => None,
+      key => Some(Nibble::try_from(key).map_err(|_| SaveStateError::InvalidValue("key"))?),
+    };
+    self.flags = reader.read_array()?;
+    if reader.get_version() >= 7 {
+      self.rand.read_state(reader)?;
+    }
+    Ok(())
+  }
 }
@@ -0,0 +1,64 @@
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum MemoryIncrement {
+  XPlusOne,
+  X,
+  Unchanged,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct Quirks {
+  pub vf_reset: bool,
+  pub memory_increment: MemoryIncrement,
+  pub shifting: bool,
+  pub jumping: bool,
+  pub clipping: bool,
+  pub display_wait: bool,
+}
+
+impl Quirks {
+  pub const COSMAC_VIP: Self = Self {
+    vf_reset: true,
+    memory_increment: MemoryIncrement::XPlusOne,
+    shifting: false,
+    jumping: false,
+    clipping: true,
+    display_wait: true,
+  };
+
+  pub const CHIP_48: Self = Self {
+    vf_reset: false,
+    memory_increment: MemoryIncrement::X,
+    shifting: true,
+    jumping: true,
+    clipping: true,
+    display_wait: false,
+  };
+
+  pub const SUPER_CHIP_1_1: Self = Self {
+    vf_reset: false,
+    memory_increment: MemoryIncrement::Unchanged,
+    shifting: true,
+    jumping: true,
+    clipping: true,
+    display_wait: false,
+  };
+
+  pub const XO_CHIP: Self = Self {
+    vf_reset: false,
+    memory_increment: MemoryIncrement::XPlusOne,
+    shifting: false,
+    jumping: false,
+    clipping: false,
+    display_wait: false,
+  };
+}
+
+/// Quirks of the COSMAC VIP without the display wait, so `DXYN` never stalls the program.
+impl Default for Quirks {
+  fn default() -> Self {
+    Self {
+      display_wait: false,
+      ..Self::COSMAC_VIP
+    }
+  }
+}
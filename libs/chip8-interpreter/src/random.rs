@@ -0,0 +1,138 @@
+use crate::{
+  errors::SaveStateError,
+  platform::RandomGenerator,
+  save_state::{StateReader, StateWriter},
+};
+
+/// Fast generator with an explicit seed (SplitMix64). The same seed always gives the same
+/// sequence.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct SeededRandom {
+  state: u64,
+}
+
+impl SeededRandom {
+  pub fn new(seed: u64) -> Self {
+    Self { state: seed }
+  }
+
+  pub fn next_u64(&mut self) -> u64 {
+    self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut value = self.state;
+    value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    value ^ (value >> 31)
+  }
+}
+
+impl RandomGenerator for SeededRandom {
+  fn get_random_byte(&mut self) -> u8 {
+    (self.next_u64() >> 56) as u8
+  }
+
+  fn write_state(&self, writer: &mut StateWriter) {
+    writer.write_u64(self.state);
+  }
+
+  fn read_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+    self.state = reader.read_u64()?;
+    Ok(())
+  }
+}
+
+pub const VIP_INTERPRETER_PAGE_SIZE: usize = 0x100;
+
+/// Model of the random number routine of the COSMAC VIP interpreter.
+///
+/// The VIP keeps the random state in the 16-bit register `R9`, which its interrupt routine
+/// increments on every frame. CXNN increments `R9`, adds the byte of interpreter code at
+/// `0x0100 + R9.0` to `R9.1`, adds the sum shifted right through the carry to itself and stores
+/// the result back to `R9.1`. Only the algorithm is modelled, the interpreter ROM is not
+/// bundled: the sequence matches the hardware only when `interpreter_page` holds the original
+/// bytes `0x0100..0x0200` of the interpreter.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct VipRandom {
+  interpreter_page: [u8; VIP_INTERPRETER_PAGE_SIZE],
+  r9: u16,
+}
+
+impl VipRandom {
+  /// `r9` is the value of `R9` when the program starts.
+  pub fn new(interpreter_page: [u8; VIP_INTERPRETER_PAGE_SIZE], r9: u16) -> Self {
+    Self {
+      interpreter_page,
+      r9,
+    }
+  }
+
+  pub fn get_r9(&self) -> u16 {
+    self.r9
+  }
+}
+
+impl RandomGenerator for VipRandom {
+  fn get_random_byte(&mut self) -> u8 {
+    self.r9 = self.r9.wrapping_add(1);
+    let [high, low] = self.r9.to_be_bytes();
+    let (sum, carry) = self.interpreter_page[low as usize].overflowing_add(high);
+    let shifted = sum >> 1 | (carry as u8) << 7;
+    let value = sum.wrapping_add(shifted);
+    self.r9 = u16::from_be_bytes([value, low]);
+    value
+  }
+
+  fn notify_vblank(&mut self) {
+    self.r9 = self.r9.wrapping_add(1);
+  }
+
+  fn write_state(&self, writer: &mut StateWriter) {
+    writer.write_u16(self.r9);
+  }
+
+  fn read_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+    self.r9 = reader.read_u16()?;
+    Ok(())
+  }
+}
+
+/// Returns the given bytes in order and starts over after the last one. Gives zeros when
+/// created without bytes.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct ScriptedRandom {
+  values: Vec<u8>,
+  position: usize,
+}
+
+impl ScriptedRandom {
+  pub fn new(values: Vec<u8>) -> Self {
+    Self {
+      values,
+      position: 0,
+    }
+  }
+
+  /// Number of bytes returned so far.
+  pub fn get_position(&self) -> usize {
+    self.position
+  }
+}
+
+impl RandomGenerator for ScriptedRandom {
+  fn get_random_byte(&mut self) -> u8 {
+    let value = match self.values.is_empty() {
+      true => 0,
+      false => self.values[self.position % self.values.len()],
+    };
+    self.position += 1;
+    value
+  }
+
+  fn write_state(&self, writer: &mut StateWriter) {
+    writer.write_u64(self.position as u64);
+  }
+
+  fn read_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError> {
+    self.position = reader.read_u64()? as usize;
+    Ok(())
+  }
+}
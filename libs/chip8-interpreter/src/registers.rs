@@ -9,6 +9,10 @@ impl Registers {
   pub fn new() -> Self {
     Self([0; Nibble::SIZE])
   }
+
+  pub fn as_array(&self) -> [u8; Nibble::SIZE] {
+    self.0
+  }
 }
 
 impl Default for Registers {
@@ -0,0 +1,179 @@
+use std::{collections::VecDeque, time::Duration};
+
+pub const DEFAULT_REWIND_INTERVAL: Duration = Duration::from_millis(100);
+pub const DEFAULT_REWIND_CAPACITY: usize = 600;
+
+const KEYFRAME_INTERVAL: usize = 64;
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+enum SnapshotData {
+  Full(Vec<u8>),
+  Delta(Vec<u8>),
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) struct Snapshot {
+  pub elapsed: Duration,
+  pub executed_count: u64,
+  data: SnapshotData,
+}
+
+/// Bounded ring of machine snapshots. Every snapshot except periodic keyframes holds only
+/// the run-length encoded XOR difference with the previous one.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) struct RewindBuffer {
+  snapshots: VecDeque<Snapshot>,
+  interval: Duration,
+  capacity: usize,
+  last_state: Vec<u8>,
+  since_keyframe: usize,
+}
+
+impl RewindBuffer {
+  pub fn new(interval: Duration, capacity: usize) -> Self {
+    Self {
+      snapshots: VecDeque::new(),
+      interval,
+      capacity: capacity.max(1),
+      last_state: Vec::new(),
+      since_keyframe: 0,
+    }
+  }
+
+  pub fn is_due(&self, elapsed: Duration) -> bool {
+    self
+      .snapshots
+      .back()
+      .is_none_or(|last| elapsed >= last.elapsed + self.interval)
+  }
+
+  pub fn get_oldest(&self) -> Option<&Snapshot> {
+    self.snapshots.front()
+  }
+
+  pub fn push(&mut self, elapsed: Duration, executed_count: u64, state: Vec<u8>) {
+    let data = match self.snapshots.is_empty() || self.since_keyframe + 1 >= KEYFRAME_INTERVAL {
+      true => {
+        self.since_keyframe = 0;
+        SnapshotData::Full(state.clone())
+      }
+      false => {
+        self.since_keyframe += 1;
+        SnapshotData::Delta(encode_delta(&self.last_state, &state))
+      }
+    };
+    self.last_state = state;
+    self.snapshots.push_back(Snapshot {
+      elapsed,
+      executed_count,
+      data,
+    });
+
+    if self.snapshots.len() > self.capacity {
+      let oldest = self.snapshots.pop_front().unwrap();
+      if let (SnapshotData::Full(base), Some(next)) = (oldest.data, self.snapshots.front_mut()) {
+        if let SnapshotData::Delta(delta) = &next.data {
+          next.data = SnapshotData::Full(apply_delta(&base, delta));
+        }
+      }
+    }
+  }
+
+  /// Index of the latest snapshot satisfying `predicate`.
+  pub fn find_latest(&self, predicate: impl Fn(&Snapshot) -> bool) -> Option<usize> {
+    self.snapshots.iter().rposition(predicate)
+  }
+
+  /// Time and full state of the snapshot at `index`. Drops every later snapshot.
+  pub fn restore(&mut self, index: usize) -> (Duration, Vec<u8>) {
+    self.snapshots.truncate(index + 1);
+    let keyframe_index = self
+      .snapshots
+      .iter()
+      .rposition(|snapshot| matches!(snapshot.data, SnapshotData::Full(_)))
+      .unwrap();
+
+    let mut state = Vec::new();
+    self
+      .snapshots
+      .range(keyframe_index..)
+      .for_each(|snapshot| match &snapshot.data {
+        SnapshotData::Full(data) => state = data.clone(),
+        SnapshotData::Delta(delta) => state = apply_delta(&state, delta),
+      });
+    self.since_keyframe = index - keyframe_index;
+    self.last_state = state.clone();
+    (self.snapshots.back().unwrap().elapsed, state)
+  }
+}
+
+fn write_varint(data: &mut Vec<u8>, mut value: usize) {
+  while value >= 0x80 {
+    data.push((value as u8) | 0x80);
+    value >>= 7;
+  }
+  data.push(value as u8);
+}
+
+fn read_varint(data: &[u8], position: &mut usize) -> usize {
+  let mut value = 0;
+  let mut shift = 0;
+  while let Some(&byte) = data.get(*position) {
+    *position += 1;
+    value |= ((byte & 0x7F) as usize) << shift;
+    shift += 7;
+    if byte & 0x80 == 0 {
+      break;
+    }
+  }
+  value
+}
+
+/// Encodes `current` as the length of the state followed by pairs of a run of unchanged bytes
+/// and a run of XOR-ed changed bytes.
+fn encode_delta(previous: &[u8], current: &[u8]) -> Vec<u8> {
+  let xor = (0..current.len())
+    .map(|index| current[index] ^ previous.get(index).copied().unwrap_or(0))
+    .collect::<Vec<_>>();
+
+  let mut data = Vec::new();
+  write_varint(&mut data, current.len());
+  let mut position = 0;
+  while position < xor.len() {
+    let unchanged = xor[position..]
+      .iter()
+      .take_while(|&&byte| byte == 0)
+      .count();
+    let changed_start = position + unchanged;
+    let changed = xor[changed_start..]
+      .iter()
+      .take_while(|&&byte| byte != 0)
+      .count();
+    write_varint(&mut data, unchanged);
+    write_varint(&mut data, changed);
+    data.extend_from_slice(&xor[changed_start..changed_start + changed]);
+    position = changed_start + changed;
+  }
+  data
+}
+
+fn apply_delta(previous: &[u8], delta: &[u8]) -> Vec<u8> {
+  let mut position = 0;
+  let len = read_varint(delta, &mut position);
+  let mut state = (0..len)
+    .map(|index| previous.get(index).copied().unwrap_or(0))
+    .collect::<Vec<_>>();
+
+  let mut offset = 0;
+  while position < delta.len() {
+    offset += read_varint(delta, &mut position);
+    let changed = read_varint(delta, &mut position);
+    delta[position..position + changed]
+      .iter()
+      .enumerate()
+      .for_each(|(index, byte)| state[offset + index] ^= byte);
+    position += changed;
+    offset += changed;
+  }
+  state
+}
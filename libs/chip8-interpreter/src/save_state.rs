@@ -0,0 +1,220 @@
+//! Versioned binary save states.
+//!
+//! Every save state is laid out as follows, all numbers are little endian:
+//!
+//! | Offset | Size | Content                                     |
+//! |--------|------|---------------------------------------------|
+//! | 0      | 4    | Magic `C8SS`                                |
+//! | 4      | 2    | Format version                              |
+//! | 6      | 1    | Kind of the saved object, see [`StateKind`] |
+//! | 7      | 4    | Payload length `N`                          |
+//! | 11     | N    | Payload                                     |
+//! | 11 + N | 4    | CRC-32 (IEEE) of everything before it       |
+//!
+//! Payloads are written by [`SaveState::write_state`] implementations. Readers receive the
+//! version of the loaded data through [`StateReader::get_version`], so fields added in later
+//! versions are read only when present and older save states are migrated forward on load.
+//!
+//! Version history:
+//!
+//! - 1: initial layout.
+//! - 2: executed instructions count of the interpreter.
+//! - 3: duration of the running instruction of a controlled interpreter.
+//! - 4: frame and instruction tick counts of a controlled interpreter.
+//! - 5: cause of the crash of a crashed interpreter.
+//! - 6: timing model at the start of a controlled interpreter, loading into another model fails,
+//!   and in the header of a movie.
+//! - 7: state of the random generator of a platform.
+
+use crate::errors::SaveStateError;
+
+pub const SAVE_STATE_MAGIC: [u8; 4] = *b"C8SS";
+pub const SAVE_STATE_VERSION: u16 = 7;
+
+const HEADER_SIZE: usize = 11;
+const CHECKSUM_SIZE: usize = 4;
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum StateKind {
+  Interpreter = 1,
+  Platform = 2,
+  ControlledInterpreter = 3,
+  Movie = 4,
+}
+
+pub trait SaveState {
+  const KIND: StateKind;
+
+  fn write_state(&self, writer: &mut StateWriter);
+  fn read_state(&mut self, reader: &mut StateReader) -> Result<(), SaveStateError>;
+
+  fn save_state(&self) -> Vec<u8> {
+    let mut writer = StateWriter::new();
+    self.write_state(&mut writer);
+
+    let mut data = Vec::with_capacity(HEADER_SIZE + writer.data.len() + CHECKSUM_SIZE);
+    data.extend_from_slice(&SAVE_STATE_MAGIC);
+    data.extend_from_slice(&SAVE_STATE_VERSION.to_le_bytes());
+    data.push(Self::KIND as u8);
+    data.extend_from_slice(&(writer.data.len() as u32).to_le_bytes());
+    data.extend_from_slice(&writer.data);
+    data.extend_from_slice(&crc32(&data).to_le_bytes());
+    data
+  }
+
+  fn load_state(&mut self, data: &[u8]) -> Result<(), SaveStateError> {
+    if data.len() < HEADER_SIZE + CHECKSUM_SIZE {
+      return Err(SaveStateError::UnexpectedEnd);
+    }
+    if data[..4] != SAVE_STATE_MAGIC {
+      return Err(SaveStateError::InvalidMagic);
+    }
+
+    let (content, checksum) = data.split_at(data.len() - CHECKSUM_SIZE);
+    if crc32(content).to_le_bytes() != checksum {
+      return Err(SaveStateError::ChecksumMismatch);
+    }
+
+    let version = u16::from_le_bytes([data[4], data[5]]);
+    if version == 0 || version > SAVE_STATE_VERSION {
+      return Err(SaveStateError::UnsupportedVersion(version));
+    }
+    if data[6] != Self::KIND as u8 {
+      return Err(SaveStateError::WrongKind(data[6]));
+    }
+    let payload_len = u32::from_le_bytes([data[7], data[8], data[9], data[10]]) as usize;
+    if payload_len != content.len() - HEADER_SIZE {
+      return Err(SaveStateError::UnexpectedEnd);
+    }
+
+    // Payloads are read into the object itself, so a payload that fails midway rolls it back.
+    let mut backup = StateWriter::new();
+    self.write_state(&mut backup);
+    let mut reader = StateReader::new(&content[HEADER_SIZE..], version);
+    let res = self
+      .read_state(&mut reader)
+      .and_then(|()| match reader.is_finished() {
+        true => Ok(()),
+        false => Err(SaveStateError::TrailingData),
+      });
+    if res.is_err() {
+      self
+        .read_state(&mut StateReader::new(&backup.data, SAVE_STATE_VERSION))
+        .expect("Backup of the current state has to load");
+    }
+    res
+  }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct StateWriter {
+  data: Vec<u8>,
+}
+
+impl StateWriter {
+  pub fn new() -> Self {
+    Self { data: Vec::new() }
+  }
+
+  pub fn write_u8(&mut self, value: u8) {
+    self.data.push(value);
+  }
+
+  pub fn write_bool(&mut self, value: bool) {
+    self.write_u8(value as u8);
+  }
+
+  pub fn write_u16(&mut self, value: u16) {
+    self.data.extend_from_slice(&value.to_le_bytes());
+  }
+
+  pub fn write_u32(&mut self, value: u32) {
+    self.data.extend_from_slice(&value.to_le_bytes());
+  }
+
+  pub fn write_u64(&mut self, value: u64) {
+    self.data.extend_from_slice(&value.to_le_bytes());
+  }
+
+  pub fn write_bytes(&mut self, value: &[u8]) {
+    self.data.extend_from_slice(value);
+  }
+
+  pub fn into_bytes(self) -> Vec<u8> {
+    self.data
+  }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct StateReader<'a> {
+  data: &'a [u8],
+  position: usize,
+  version: u16,
+}
+
+impl<'a> StateReader<'a> {
+  pub fn new(data: &'a [u8], version: u16) -> Self {
+    Self {
+      data,
+      position: 0,
+      version,
+    }
+  }
+
+  pub fn get_version(&self) -> u16 {
+    self.version
+  }
+
+  pub fn is_finished(&self) -> bool {
+    self.position == self.data.len()
+  }
+
+  pub fn read_u8(&mut self) -> Result<u8, SaveStateError> {
+    Ok(self.read_bytes(1)?[0])
+  }
+
+  pub fn read_bool(&mut self) -> Result<bool, SaveStateError> {
+    match self.read_u8()? {
+      0 => Ok(false),
+      1 => Ok(true),
+      _ => Err(SaveStateError::InvalidValue("bool")),
+    }
+  }
+
+  pub fn read_u16(&mut self) -> Result<u16, SaveStateError> {
+    Ok(u16::from_le_bytes(self.read_array()?))
+  }
+
+  pub fn read_u32(&mut self) -> Result<u32, SaveStateError> {
+    Ok(u32::from_le_bytes(self.read_array()?))
+  }
+
+  pub fn read_u64(&mut self) -> Result<u64, SaveStateError> {
+    Ok(u64::from_le_bytes(self.read_array()?))
+  }
+
+  pub fn read_array<const N: usize>(&mut self) -> Result<[u8; N], SaveStateError> {
+    let mut res = [0; N];
+    res.copy_from_slice(self.read_bytes(N)?);
+    Ok(res)
+  }
+
+  pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], SaveStateError> {
+    let end = self
+      .position
+      .checked_add(len)
+      .filter(|&end| end <= self.data.len())
+      .ok_or(SaveStateError::UnexpectedEnd)?;
+    let res = &self.data[self.position..end];
+    self.position = end;
+    Ok(res)
+  }
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+  !data.iter().fold(!0u32, |crc, &byte| {
+    (0..8).fold(crc ^ byte as u32, |crc, _| {
+      (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg())
+    })
+  })
+}
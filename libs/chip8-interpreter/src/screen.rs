@@ -1,20 +1,143 @@
 pub const SCREEN_WIDTH: usize = 64;
 pub const SCREEN_HEIGHT: usize = 32;
+pub const HIGH_RES_SCREEN_WIDTH: usize = 128;
+pub const HIGH_RES_SCREEN_HEIGHT: usize = 64;
+pub const PLANES_COUNT: usize = 2;
+pub const ALL_PLANES: u8 = (1 << PLANES_COUNT) - 1;
+
+const SCROLL_SIDE_STEP: usize = 4;
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
-pub struct ScreenFrame(pub [[bool; SCREEN_WIDTH]; SCREEN_HEIGHT]);
+pub struct ScreenFrame {
+  pixels: [[u8; HIGH_RES_SCREEN_WIDTH]; HIGH_RES_SCREEN_HEIGHT],
+  is_high_resolution: bool,
+}
 
 impl ScreenFrame {
   pub fn new() -> Self {
-    Self([[false; SCREEN_WIDTH]; SCREEN_HEIGHT])
+    Self {
+      pixels: [[0; HIGH_RES_SCREEN_WIDTH]; HIGH_RES_SCREEN_HEIGHT],
+      is_high_resolution: false,
+    }
+  }
+
+  pub fn is_high_resolution(&self) -> bool {
+    self.is_high_resolution
+  }
+
+  pub fn set_high_resolution(&mut self, is_high_resolution: bool) {
+    self.is_high_resolution = is_high_resolution;
+    self.clear(ALL_PLANES);
+  }
+
+  pub fn get_width(&self) -> usize {
+    match self.is_high_resolution {
+      true => HIGH_RES_SCREEN_WIDTH,
+      false => SCREEN_WIDTH,
+    }
+  }
+
+  pub fn get_height(&self) -> usize {
+    match self.is_high_resolution {
+      true => HIGH_RES_SCREEN_HEIGHT,
+      false => SCREEN_HEIGHT,
+    }
+  }
+
+  pub fn get_pixel(&self, x: usize, y: usize) -> u8 {
+    self.pixels[y][x]
+  }
+
+  pub fn get_pixel_mut(&mut self, x: usize, y: usize) -> &mut u8 {
+    &mut self.pixels[y][x]
+  }
+
+  /// 64-bit FNV-1a hash of the resolution and the visible pixels. It is stable between runs
+  /// and builds.
+  pub fn get_hash(&self) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xCBF2_9CE4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01B3;
+    std::iter::once(self.is_high_resolution as u8)
+      .chain(self.iter_rows().flatten().copied())
+      .fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+      })
+  }
+
+  pub fn clear(&mut self, planes: u8) {
+    self
+      .pixels
+      .iter_mut()
+      .flatten()
+      .for_each(|pixel| *pixel &= !planes);
+  }
+
+  pub fn iter_rows(&self) -> impl Iterator<Item = &[u8]> {
+    let width = self.get_width();
+    self.pixels[..self.get_height()]
+      .iter()
+      .map(move |row| &row[..width])
+  }
+
+  pub fn iter_rows_mut(&mut self) -> impl Iterator<Item = &mut [u8]> {
+    let width = self.get_width();
+    let height = self.get_height();
+    self.pixels[..height]
+      .iter_mut()
+      .map(move |row| &mut row[..width])
+  }
+
+  pub fn scroll_down(&mut self, rows_count: usize, planes: u8) {
+    let height = self.get_height();
+    let rows_count = rows_count.min(height);
+    (0..height).rev().for_each(|y| {
+      let source = y.checked_sub(rows_count).map(|source| self.pixels[source]);
+      self.move_row(y, source.as_ref(), planes);
+    });
+  }
+
+  pub fn scroll_up(&mut self, rows_count: usize, planes: u8) {
+    let height = self.get_height();
+    let rows_count = rows_count.min(height);
+    (0..height).for_each(|y| {
+      let source = Some(y + rows_count)
+        .filter(|&source| source < height)
+        .map(|source| self.pixels[source]);
+      self.move_row(y, source.as_ref(), planes);
+    });
+  }
+
+  pub fn scroll_right(&mut self, planes: u8) {
+    self.iter_rows_mut().for_each(|row| {
+      (0..row.len()).rev().for_each(|x| {
+        let source = x
+          .checked_sub(SCROLL_SIDE_STEP)
+          .map_or(0, |source| row[source]);
+        row[x] = (row[x] & !planes) | (source & planes);
+      });
+    });
   }
 
-  pub fn iter_rows(&self) -> impl Iterator<Item = &[bool; SCREEN_WIDTH]> {
-    self.0.iter()
+  pub fn scroll_left(&mut self, planes: u8) {
+    self.iter_rows_mut().for_each(|row| {
+      let width = row.len();
+      (0..width).for_each(|x| {
+        let source = Some(x + SCROLL_SIDE_STEP)
+          .filter(|&source| source < width)
+          .map_or(0, |source| row[source]);
+        row[x] = (row[x] & !planes) | (source & planes);
+      });
+    });
   }
 
-  pub fn iter_rows_mut(&mut self) -> impl Iterator<Item = &mut [bool; SCREEN_WIDTH]> {
-    self.0.iter_mut()
+  fn move_row(&mut self, y: usize, source: Option<&[u8; HIGH_RES_SCREEN_WIDTH]>, planes: u8) {
+    self.pixels[y]
+      .iter_mut()
+      .enumerate()
+      .for_each(|(x, pixel)| {
+        let value = source.map_or(0, |source| source[x]);
+        *pixel = (*pixel & !planes) | (value & planes);
+      });
   }
 }
 
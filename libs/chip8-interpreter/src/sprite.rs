@@ -5,23 +5,60 @@ pub struct Point {
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Default)]
-pub struct Sprite<'a>(&'a [u8]);
+pub struct Sprite<'a> {
+  data: &'a [u8],
+  row_width: usize,
+}
 
 impl<'a> Sprite<'a> {
+  pub const WIDTH: usize = 8;
+  pub const WIDE_WIDTH: usize = 16;
+  pub const WIDE_SIZE: usize = Self::WIDE_WIDTH * Self::WIDE_WIDTH / 8;
+
   pub fn new(value: &'a [u8]) -> Self {
-    Self(value)
+    Self {
+      data: value,
+      row_width: Self::WIDTH,
+    }
+  }
+
+  pub fn new_wide(value: &'a [u8]) -> Self {
+    Self {
+      data: value,
+      row_width: Self::WIDE_WIDTH,
+    }
+  }
+
+  pub fn split_planes(&self, planes_count: usize) -> impl Iterator<Item = Sprite<'a>> + '_ {
+    let plane_size = self.data.len() / planes_count.max(1);
+    self
+      .data
+      .chunks(plane_size.max(1))
+      .take(planes_count)
+      .map(|data| Sprite {
+        data,
+        row_width: self.row_width,
+      })
   }
 
   pub fn iter_pixels(&self) -> impl Iterator<Item = Point> + '_ {
-    self.0.iter().enumerate().flat_map(|(i, row)| {
-      (0..8).filter_map(move |x| {
-        let y = i as u8;
-        let val = row & (0x80 >> x);
-        if val == 0 {
-          return None;
-        }
-        Some(Point { x, y })
+    let row_bytes = self.row_width / 8;
+    self
+      .data
+      .chunks(row_bytes)
+      .enumerate()
+      .flat_map(move |(i, row)| {
+        row.iter().enumerate().flat_map(move |(byte_index, byte)| {
+          (0..8).filter_map(move |bit| {
+            let y = i as u8;
+            let val = byte & (0x80 >> bit);
+            if val == 0 {
+              return None;
+            }
+            let x = (byte_index * 8) as u8 + bit;
+            Some(Point { x, y })
+          })
+        })
       })
-    })
   }
 }
@@ -3,43 +3,39 @@ use super::{address::Address, errors::InterpreterError};
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
 pub struct Stack<T, const S: usize> {
   items: [T; S],
-  index: usize,
-  is_empty: bool,
+  depth: usize,
 }
 
 impl<const S: usize> Stack<Address, S> {
   pub fn new() -> Self {
     Self {
       items: [Address::default(); S],
-      index: 0,
-      is_empty: true,
+      depth: 0,
     }
   }
 
   pub fn push(&mut self, value: Address) -> Result<(), InterpreterError> {
-    if self.index == S - 1 {
+    if self.depth == S {
       return Err(InterpreterError::StackOverflow);
     }
 
-    self.index += 1;
-    self.is_empty &= false;
-    self.items[self.index] = value;
+    self.items[self.depth] = value;
+    self.depth += 1;
     Ok(())
   }
 
   pub fn pop(&mut self) -> Result<Address, InterpreterError> {
-    if self.is_empty {
+    if self.depth == 0 {
       return Err(InterpreterError::StackUnderflow);
     }
 
-    let value = self.items[self.index];
-    self.items[self.index] = Address::default();
-    if self.index != 0 {
-      self.index -= 1;
-    } else {
-      self.is_empty = true;
-    }
-
+    self.depth -= 1;
+    let value = self.items[self.depth];
+    self.items[self.depth] = Address::default();
     Ok(value)
   }
+
+  pub fn as_slice(&self) -> &[Address] {
+    &self.items[..self.depth]
+  }
 }
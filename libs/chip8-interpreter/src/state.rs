@@ -0,0 +1,98 @@
+use crate::{address::Address, memory::Memory, nibble::Nibble, registers::Registers};
+
+/// Read-only snapshot view of the machine state of an [`Interpreter`](crate::Interpreter).
+///
+/// The view borrows the interpreter, so it always reflects the state between two instructions.
+#[derive(Copy, Clone, Debug)]
+pub struct InterpreterState<'a> {
+  pub(crate) registers: &'a Registers,
+  pub(crate) index_register: Address,
+  pub(crate) instruction_address: Address,
+  pub(crate) stack: &'a [Address],
+  pub(crate) memory: &'a Memory,
+  pub(crate) expecting_key: Option<Nibble>,
+  pub(crate) delay_timer: u8,
+  pub(crate) sound_timer: u8,
+  pub(crate) is_crashed: bool,
+  pub(crate) is_exited: bool,
+  pub(crate) is_waiting_vblank: bool,
+  pub(crate) executed_count: u64,
+}
+
+impl<'a> InterpreterState<'a> {
+  /// Value of the general purpose register `vX`.
+  pub fn get_register(&self, index: Nibble) -> u8 {
+    self.registers[index]
+  }
+
+  /// Values of `v0`..`vF` in register order.
+  pub fn get_registers(&self) -> [u8; Nibble::SIZE] {
+    self.registers.as_array()
+  }
+
+  /// Value of the address register `I`.
+  pub fn get_index_register(&self) -> Address {
+    self.index_register
+  }
+
+  /// Address of the next instruction to execute (`PC`).
+  pub fn get_instruction_address(&self) -> Address {
+    self.instruction_address
+  }
+
+  /// Return addresses on the stack, from the outermost call to the innermost one.
+  pub fn get_stack(&self) -> &'a [Address] {
+    self.stack
+  }
+
+  /// Number of return addresses on the stack.
+  pub fn get_stack_depth(&self) -> usize {
+    self.stack.len()
+  }
+
+  /// Whole memory of the machine.
+  pub fn get_memory(&self) -> &'a Memory {
+    self.memory
+  }
+
+  /// Memory bytes in `start..start + len`, or `None` if the range leaves the memory.
+  pub fn get_memory_slice(&self, start: Address, len: usize) -> Option<&'a [u8]> {
+    self
+      .memory
+      .as_slice()
+      .get(start.as_usize()..start.as_usize().checked_add(len)?)
+  }
+
+  /// Register that receives the key while the interpreter waits on `FX0A`.
+  pub fn get_expecting_key(&self) -> Option<Nibble> {
+    self.expecting_key
+  }
+
+  pub fn get_delay_timer(&self) -> u8 {
+    self.delay_timer
+  }
+
+  pub fn get_sound_timer(&self) -> u8 {
+    self.sound_timer
+  }
+
+  /// `true` after an instruction failed. A crashed interpreter does not run anymore.
+  pub fn is_crashed(&self) -> bool {
+    self.is_crashed
+  }
+
+  /// `true` after `00FD` stopped the program.
+  pub fn is_exited(&self) -> bool {
+    self.is_exited
+  }
+
+  /// `true` while `DXYN` waits for the next vertical blank.
+  pub fn is_waiting_vblank(&self) -> bool {
+    self.is_waiting_vblank
+  }
+
+  /// Number of instructions executed since the program was loaded.
+  pub fn get_executed_count(&self) -> u64 {
+    self.executed_count
+  }
+}
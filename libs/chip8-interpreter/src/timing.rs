@@ -0,0 +1,220 @@
+//! Timing models of the controlled interpreter.
+//!
+//! The frame based model splits every frame of exactly 1/60 s into equal instruction slots.
+//! Boundaries are computed from their index with integer arithmetic, so rounding to whole
+//! nanoseconds never accumulates and the timers stay at 60 Hz over any run.
+//!
+//! The CDP1802 of the VIP runs at 1.76064 MHz and needs 8 clock cycles per machine cycle, so a
+//! 60 Hz frame lasts exactly 3668 machine cycles. At the start of every frame the interrupt
+//! routine runs and the CDP1861 takes 1024 cycles for the display DMA, the interpreter gets the
+//! rest. Instruction costs below are the machine cycles of the fetch loop plus the cycles of the
+//! instruction routine, following the VIP interpreter listing.
+
+use std::time::Duration;
+
+use crate::{
+  address::Address,
+  errors::SaveStateError,
+  interpreter::{Instruction, OpCode},
+  save_state::{StateReader, StateWriter},
+  state::InterpreterState,
+};
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Default)]
+pub enum Timing {
+  /// Every instruction takes the same time.
+  #[default]
+  Fixed,
+  /// Instructions take as many machine cycles as on the COSMAC VIP.
+  CosmacVip,
+  /// Every frame of exactly 1/60 s runs the same number of instructions.
+  FrameBased { instructions_per_frame: u32 },
+}
+
+impl Timing {
+  pub(crate) fn write_state(self, writer: &mut StateWriter) {
+    match self {
+      Timing::Fixed => writer.write_u8(0),
+      Timing::CosmacVip => writer.write_u8(1),
+      Timing::FrameBased {
+        instructions_per_frame,
+      } => {
+        writer.write_u8(2);
+        writer.write_u32(instructions_per_frame);
+      }
+    }
+  }
+
+  pub(crate) fn read_state(reader: &mut StateReader) -> Result<Self, SaveStateError> {
+    let timing = match reader.read_u8()? {
+      0 => Timing::Fixed,
+      1 => Timing::CosmacVip,
+      2 => Timing::FrameBased {
+        instructions_per_frame: reader.read_u32()?,
+      },
+      _ => return Err(SaveStateError::InvalidValue("timing")),
+    };
+    Ok(timing)
+  }
+}
+
+pub const FRAME_RATE: u32 = 60;
+
+pub const VIP_CLOCK_FREQUENCY: u64 = 1_760_640;
+pub const VIP_CLOCKS_PER_MACHINE_CYCLE: u64 = 8;
+pub const VIP_FRAME_CYCLES: u32 = 3668;
+/// Cycles at the start of every frame taken by the interrupt routine and the display DMA.
+pub const VIP_INTERRUPT_CYCLES: u32 = 30 + 1024;
+
+const FETCH_CYCLES: u32 = 40;
+const SKIP_CYCLES: u32 = 4;
+const CLEAR_SCREEN_CYCLES: u32 = 3078;
+const DRAW_SETUP_CYCLES: u32 = 26;
+const DRAW_ROW_CYCLES: u32 = 34;
+const DRAW_SHIFT_CYCLES: u32 = 4;
+const DRAW_SPLIT_ROW_CYCLES: u32 = 14;
+const BCD_DIGIT_CYCLES: u32 = 16;
+const MEMORY_REGISTER_CYCLES: u32 = 14;
+const VIP_SCREEN_WIDTH: u8 = 64;
+const VIP_SCREEN_HEIGHT: u8 = 32;
+const NANOS_PER_SECOND: u128 = 1_000_000_000;
+
+/// Time from the start until the frame with the given index begins.
+pub fn get_frame_start(frame: u64) -> Duration {
+  get_fraction_duration(frame as u128, FRAME_RATE as u128)
+}
+
+/// Duration of the frame with the given index. Frames differ by a nanosecond at most, so that
+/// their boundaries stay exact.
+pub(crate) fn get_exact_frame_duration(frame: u64) -> Duration {
+  let frame = frame as u128;
+  get_fraction_duration(frame + 1, FRAME_RATE as u128)
+    - get_fraction_duration(frame, FRAME_RATE as u128)
+}
+
+/// Time between the instruction with the given index and the one before it, or the start for
+/// the first one, under the frame based timing. Instructions run in the middle of their slots,
+/// so they never coincide with the end of a frame.
+pub(crate) fn get_frame_instruction_interval(index: u64, instructions_per_frame: u32) -> Duration {
+  let slots_per_second = FRAME_RATE as u128 * instructions_per_frame.max(1) as u128;
+  let get_time = |index: u128| get_fraction_duration(2 * index + 1, 2 * slots_per_second);
+  match index as u128 {
+    0 => get_time(0),
+    index => get_time(index) - get_time(index - 1),
+  }
+}
+
+fn get_fraction_duration(numerator: u128, denominator: u128) -> Duration {
+  let nanos = numerator * NANOS_PER_SECOND / denominator;
+  Duration::new(
+    (nanos / NANOS_PER_SECOND) as u64,
+    (nanos % NANOS_PER_SECOND) as u32,
+  )
+}
+
+pub fn get_vip_cycles_duration(cycles: u64) -> Duration {
+  let nanos = cycles as u128 * VIP_CLOCKS_PER_MACHINE_CYCLE as u128 * 1_000_000_000;
+  let clock = VIP_CLOCK_FREQUENCY as u128;
+  Duration::from_nanos(((nanos + clock / 2) / clock) as u64)
+}
+
+pub fn get_vip_frame_duration() -> Duration {
+  get_vip_cycles_duration(VIP_FRAME_CYCLES as u64)
+}
+
+/// Machine cycles that the instruction at the program counter takes, not counting the time
+/// taken by interrupts.
+pub(crate) fn get_vip_instruction_cycles(state: &InterpreterState) -> u32 {
+  let address = state.instruction_address;
+  let read_op_code =
+    |address: Address| OpCode::from_bytes(state.memory[address], state.memory[address + 1]);
+  let Ok(instruction) = Instruction::decode(read_op_code(address), read_op_code(address + 2))
+  else {
+    return FETCH_CYCLES;
+  };
+
+  let reg = |index| state.registers[index];
+  let skip = |is_skipping: bool, cycles: u32| match is_skipping {
+    true => cycles + SKIP_CYCLES,
+    false => cycles,
+  };
+
+  FETCH_CYCLES
+    + match instruction {
+      Instruction::ClearScreen => CLEAR_SCREEN_CYCLES,
+      Instruction::Return => 10,
+      Instruction::Jump(_) => 12,
+      Instruction::Call(_) => 26,
+      Instruction::SkipIfEqual(x, value) => skip(reg(x) == value, 10),
+      Instruction::SkipIfNotEqual(x, value) => skip(reg(x) != value, 10),
+      Instruction::SkipIfRegistersEqual(x, y) => skip(reg(x) == reg(y), 14),
+      Instruction::SkipIfRegistersNotEqual(x, y) => skip(reg(x) != reg(y), 14),
+      Instruction::SetRegister(..) => 6,
+      Instruction::AddRegister(..) => 10,
+      Instruction::CopyRegister(..) => 12,
+      Instruction::OrRegisters(..)
+      | Instruction::AndRegisters(..)
+      | Instruction::XorRegisters(..)
+      | Instruction::AddRegisters(..)
+      | Instruction::SubRegisters(..)
+      | Instruction::RShiftRegisters(..)
+      | Instruction::SubRegisterReversed(..)
+      | Instruction::LShiftRegisters(..) => 44,
+      Instruction::SetAddressRegister(_) => 12,
+      Instruction::JumpV0(_) => 22,
+      Instruction::SetRegisterRandom(..) => 36,
+      Instruction::DrawSprite(x, y, height) => {
+        let x_offset = (reg(x) % VIP_SCREEN_WIDTH % 8) as u32;
+        let rows = (height.as_u8()).min(VIP_SCREEN_HEIGHT - reg(y) % VIP_SCREEN_HEIGHT) as u32;
+        let row_cycles = DRAW_ROW_CYCLES
+          + x_offset * DRAW_SHIFT_CYCLES
+          + match x_offset {
+            0 => 0,
+            _ => DRAW_SPLIT_ROW_CYCLES,
+          };
+        DRAW_SETUP_CYCLES + rows * row_cycles
+      }
+      Instruction::SkipIfKeyDown(_) | Instruction::SkipIfKeyUp(_) => 14,
+      Instruction::GetDelayTimer(_) => 10,
+      Instruction::WaitForKeyDown(_) => 18,
+      Instruction::SetDelayTimer(_) | Instruction::SetSoundTimer(_) => 10,
+      Instruction::AddAddressRegister(_) => 16,
+      Instruction::SetAddressRegisterToDigit(_) => 16,
+      Instruction::RegisterToBCD(x) => {
+        let value = reg(x);
+        let digits_sum = (value / 100 + value / 10 % 10 + value % 10) as u32;
+        80 + digits_sum * BCD_DIGIT_CYCLES
+      }
+      Instruction::WriteRegistersToMem(x) | Instruction::LoadRegistersFromMem(x) => {
+        14 + (x.as_u8() as u32 + 1) * MEMORY_REGISTER_CYCLES
+      }
+      // Not available on the VIP, these only pay for the fetch.
+      _ => 0,
+    }
+}
+
+/// Time from the given point of a frame until `cycles` machine cycles of the interpreter have
+/// passed. Cycles taken by the interrupt routine and the display DMA at the start of every
+/// frame are skipped.
+pub(crate) fn get_vip_instruction_duration(frame_position: Duration, cycles: u32) -> Duration {
+  let cycle_nanos = VIP_CLOCKS_PER_MACHINE_CYCLE as u128 * 1_000_000_000;
+  let position_cycles =
+    (frame_position.as_nanos() * VIP_CLOCK_FREQUENCY as u128 + cycle_nanos / 2) / cycle_nanos;
+  let mut position = (position_cycles as u32).min(VIP_FRAME_CYCLES);
+  let mut remaining = cycles;
+  let mut elapsed = 0;
+  while remaining > 0 {
+    if position >= VIP_FRAME_CYCLES {
+      position = 0;
+    }
+    if position < VIP_INTERRUPT_CYCLES {
+      elapsed += (VIP_INTERRUPT_CYCLES - position) as u64;
+      position = VIP_INTERRUPT_CYCLES;
+    }
+    let taken = remaining.min(VIP_FRAME_CYCLES - position);
+    elapsed += taken as u64;
+    position += taken;
+    remaining -= taken;
+  }
+  get_vip_cycles_duration(elapsed)
+}
@@ -0,0 +1,377 @@
+//! Per-instruction execution trace.
+//!
+//! An [`Interpreter`](crate::Interpreter) with a trace sink passes a [`TraceRecord`] to the sink
+//! after every executed instruction. Failed instructions are not traced.
+//!
+//! [`TextLogSink`] writes one line per instruction with the machine state before it ran:
+//!
+//! ```text
+//! PC:0200 OP:6005 I:0000 V0:00 V1:00 ... VF:00 CYCLE:0 | v0 := 0x05
+//! ```
+//!
+//! [`BinaryLogSink`] writes the magic `C8TR`, a format version `u16` and then for every
+//! instruction, all numbers are little endian:
+//!
+//! | Size    | Content                                                   |
+//! |---------|-----------------------------------------------------------|
+//! | 8       | Cycle                                                     |
+//! | 2       | `PC`                                                      |
+//! | 1       | Instruction size `S`                                      |
+//! | S       | Instruction bytes                                         |
+//! | 2       | Mask of changed registers, bit `X` for `vX`               |
+//! | 1 each  | New values of the changed registers in register order     |
+//! | 1       | `1` if `I` changed, `0` otherwise                         |
+//! | 0 or 2  | New value of `I`                                          |
+//! | 2       | Memory writes count `N`                                   |
+//! | 3 * N   | Address `u16` and value `u8` of every write               |
+
+use std::{
+  cmp::Ordering,
+  collections::VecDeque,
+  fmt,
+  io::{self, Write},
+  sync::{Arc, Mutex},
+};
+
+use crate::{
+  address::Address,
+  disassembler::{format_instruction, Syntax},
+  interpreter::{Instruction, OpCode},
+  nibble::Nibble,
+};
+
+pub const BINARY_TRACE_MAGIC: [u8; 4] = *b"C8TR";
+pub const BINARY_TRACE_VERSION: u16 = 1;
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct TraceRecord {
+  pub(crate) cycle: u64,
+  pub(crate) address: Address,
+  pub(crate) op_code: OpCode,
+  pub(crate) instruction: Instruction,
+  pub(crate) registers: [u8; Nibble::SIZE],
+  pub(crate) registers_after: [u8; Nibble::SIZE],
+  pub(crate) index_register: Address,
+  pub(crate) index_register_after: Address,
+  pub(crate) memory_writes: Vec<(Address, u8)>,
+}
+
+impl TraceRecord {
+  /// Number of instructions executed before this one.
+  pub fn get_cycle(&self) -> u64 {
+    self.cycle
+  }
+
+  /// Address of the instruction.
+  pub fn get_address(&self) -> Address {
+    self.address
+  }
+
+  /// First word of the instruction.
+  pub fn get_op_code(&self) -> OpCode {
+    self.op_code
+  }
+
+  pub fn get_instruction(&self) -> Instruction {
+    self.instruction
+  }
+
+  /// Values of `v0`..`vF` before the instruction ran.
+  pub fn get_registers(&self) -> [u8; Nibble::SIZE] {
+    self.registers
+  }
+
+  /// Values of `v0`..`vF` after the instruction ran.
+  pub fn get_registers_after(&self) -> [u8; Nibble::SIZE] {
+    self.registers_after
+  }
+
+  /// Registers changed by the instruction with their old and new values.
+  pub fn iter_register_changes(&self) -> impl Iterator<Item = (Nibble, u8, u8)> + '_ {
+    self
+      .registers
+      .iter()
+      .zip(self.registers_after)
+      .enumerate()
+      .filter(|(_, (&before, after))| before != *after)
+      .map(|(index, (&before, after))| (Nibble::try_from(index as u8).unwrap(), before, after))
+  }
+
+  /// Value of `I` before the instruction ran.
+  pub fn get_index_register(&self) -> Address {
+    self.index_register
+  }
+
+  /// Old and new values of `I` if the instruction changed it.
+  pub fn get_index_register_change(&self) -> Option<(Address, Address)> {
+    Some((self.index_register, self.index_register_after)).filter(|(before, after)| before != after)
+  }
+
+  /// Bytes written to memory in the order they were written.
+  pub fn get_memory_writes(&self) -> &[(Address, u8)] {
+    &self.memory_writes
+  }
+
+  /// Value of `vF` after the instruction ran.
+  pub fn get_vf(&self) -> u8 {
+    self.registers_after[Nibble::MAX.as_usize()]
+  }
+}
+
+impl fmt::Display for TraceRecord {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "PC:{:04X} OP:{:04X} I:{:04X}",
+      self.address.as_u16(),
+      u16::from(self.op_code),
+      self.index_register.as_u16()
+    )?;
+    for (index, value) in self.registers.iter().enumerate() {
+      write!(f, " V{index:X}:{value:02X}")?;
+    }
+    write!(
+      f,
+      " CYCLE:{} | {}",
+      self.cycle,
+      format_instruction(self.instruction, Syntax::Octo)
+    )
+  }
+}
+
+pub trait TraceSink {
+  fn record(&mut self, record: &TraceRecord);
+}
+
+impl<S: TraceSink> TraceSink for Arc<Mutex<S>> {
+  fn record(&mut self, record: &TraceRecord) {
+    if let Ok(mut sink) = self.lock() {
+      sink.record(record);
+    }
+  }
+}
+
+/// Keeps the last `capacity` records.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct RingBufferSink {
+  records: VecDeque<TraceRecord>,
+  capacity: usize,
+}
+
+impl RingBufferSink {
+  pub fn new(capacity: usize) -> Self {
+    Self {
+      records: VecDeque::with_capacity(capacity),
+      capacity,
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.records.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.records.is_empty()
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &TraceRecord> {
+    self.records.iter()
+  }
+
+  pub fn clear(&mut self) {
+    self.records.clear();
+  }
+}
+
+impl TraceSink for RingBufferSink {
+  fn record(&mut self, record: &TraceRecord) {
+    if self.capacity == 0 {
+      return;
+    }
+    if self.records.len() == self.capacity {
+      self.records.pop_front();
+    }
+    self.records.push_back(record.clone());
+  }
+}
+
+/// Writes records as text lines. Writing stops after the first error.
+#[derive(Debug)]
+pub struct TextLogSink<W: Write> {
+  writer: W,
+  error: Option<io::Error>,
+}
+
+impl<W: Write> TextLogSink<W> {
+  pub fn new(writer: W) -> Self {
+    Self {
+      writer,
+      error: None,
+    }
+  }
+
+  pub fn get_error(&self) -> Option<&io::Error> {
+    self.error.as_ref()
+  }
+
+  pub fn get_writer(&self) -> &W {
+    &self.writer
+  }
+
+  pub fn into_inner(self) -> W {
+    self.writer
+  }
+}
+
+impl<W: Write> TraceSink for TextLogSink<W> {
+  fn record(&mut self, record: &TraceRecord) {
+    if self.error.is_none() {
+      self.error = writeln!(self.writer, "{record}").err();
+    }
+  }
+}
+
+/// Writes records in the compact binary format. Writing stops after the first error.
+#[derive(Debug)]
+pub struct BinaryLogSink<W: Write> {
+  writer: W,
+  error: Option<io::Error>,
+  is_header_written: bool,
+}
+
+impl<W: Write> BinaryLogSink<W> {
+  pub fn new(writer: W) -> Self {
+    Self {
+      writer,
+      error: None,
+      is_header_written: false,
+    }
+  }
+
+  pub fn get_error(&self) -> Option<&io::Error> {
+    self.error.as_ref()
+  }
+
+  pub fn get_writer(&self) -> &W {
+    &self.writer
+  }
+
+  pub fn into_inner(self) -> W {
+    self.writer
+  }
+
+  fn encode(&mut self, record: &TraceRecord) -> Vec<u8> {
+    let mut data = Vec::new();
+    if !self.is_header_written {
+      data.extend_from_slice(&BINARY_TRACE_MAGIC);
+      data.extend_from_slice(&BINARY_TRACE_VERSION.to_le_bytes());
+      self.is_header_written = true;
+    }
+
+    data.extend_from_slice(&record.cycle.to_le_bytes());
+    data.extend_from_slice(&record.address.as_u16().to_le_bytes());
+    let bytes = record.instruction.encode().unwrap_or_default();
+    data.push(bytes.len() as u8);
+    data.extend_from_slice(&bytes);
+
+    let changes = record.iter_register_changes().collect::<Vec<_>>();
+    let mask = changes
+      .iter()
+      .fold(0u16, |mask, (index, ..)| mask | 1 << index.as_u8());
+    data.extend_from_slice(&mask.to_le_bytes());
+    changes.iter().for_each(|&(_, _, after)| data.push(after));
+
+    match record.get_index_register_change() {
+      Some((_, after)) => {
+        data.push(1);
+        data.extend_from_slice(&after.as_u16().to_le_bytes());
+      }
+      None => data.push(0),
+    }
+
+    data.extend_from_slice(&(record.memory_writes.len() as u16).to_le_bytes());
+    record.memory_writes.iter().for_each(|&(address, value)| {
+      data.extend_from_slice(&address.as_u16().to_le_bytes());
+      data.push(value);
+    });
+    data
+  }
+}
+
+impl<W: Write> TraceSink for BinaryLogSink<W> {
+  fn record(&mut self, record: &TraceRecord) {
+    if self.error.is_none() {
+      let data = self.encode(record);
+      self.error = self.writer.write_all(&data).err();
+    }
+  }
+}
+
+pub(crate) type BoxedTraceSink = Box<dyn TraceSink + Send>;
+
+/// Trace sink slot that every comparison treats as equal: the sink observes the machine and is
+/// not part of its state.
+#[derive(Default)]
+struct SinkSlot(Option<BoxedTraceSink>);
+
+impl PartialEq for SinkSlot {
+  fn eq(&self, _: &Self) -> bool {
+    true
+  }
+}
+
+impl Eq for SinkSlot {}
+
+impl PartialOrd for SinkSlot {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for SinkSlot {
+  fn cmp(&self, _: &Self) -> Ordering {
+    Ordering::Equal
+  }
+}
+
+impl fmt::Debug for SinkSlot {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self.0 {
+      Some(_) => f.write_str("Some(TraceSink)"),
+      None => f.write_str("None"),
+    }
+  }
+}
+
+/// Trace sink of an interpreter together with the writes of the current instruction.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Debug, Default)]
+pub(crate) struct Tracer {
+  sink: SinkSlot,
+  memory_writes: Vec<(Address, u8)>,
+}
+
+impl Tracer {
+  pub fn set_sink(&mut self, sink: Option<BoxedTraceSink>) -> Option<BoxedTraceSink> {
+    std::mem::replace(&mut self.sink.0, sink)
+  }
+
+  pub fn is_enabled(&self) -> bool {
+    self.sink.0.is_some()
+  }
+
+  pub fn add_memory_write(&mut self, address: Address, value: u8) {
+    if self.is_enabled() {
+      self.memory_writes.push((address, value));
+    }
+  }
+
+  pub fn take_memory_writes(&mut self) -> Vec<(Address, u8)> {
+    std::mem::take(&mut self.memory_writes)
+  }
+
+  pub fn record(&mut self, record: &TraceRecord) {
+    if let Some(sink) = &mut self.sink.0 {
+      sink.record(record);
+    }
+  }
+}
@@ -1,27 +1,71 @@
-use std::time::Duration;
+use std::{
+  sync::{Arc, Mutex},
+  time::Duration,
+};
 
 use chip8_interpreter::{
-  Address, BaseExecutable, BasePlatform, ControlledInterpreter, Nibble, ScreenFrame,
-  DEFAULT_DELAY_TIMER_DURATION, DEFAULT_INSTRUCTION_DURATION, DEFAULT_SOUND_TIMER_DURATION,
+  assemble, crc32, get_frame_start, get_vip_cycles_duration, get_vip_frame_duration, write_wav,
+  Address, AssemblerErrorKind, AudioBuffer, AudioConfig, BaseExecutable, BasePlatform,
+  BinaryLogSink, Breakpoint, ControlledInterpreter, Disassembly, Font, FontSet, Instruction,
+  Interpreter, InterpreterError, LoaderError, Mode, Movie, MovieConfig, MovieError, MoviePlayer,
+  MovieRecorder, Nibble, OpCode, Platform, Quirks, RandomGenerator, RewindError, RingBufferSink,
+  SaveState, SaveStateError, ScreenFrame, ScriptedRandom, SeededRandom, StopReason, Syntax,
+  TextLogSink, Timing, VipRandom, Watchpoint, DEFAULT_DELAY_TIMER_DURATION,
+  DEFAULT_FONT_LOAD_POINT, DEFAULT_INSTRUCTION_DURATION, DEFAULT_REWIND_CAPACITY,
+  DEFAULT_REWIND_INTERVAL, DEFAULT_SOUND_TIMER_DURATION, FRAME_RATE, MEMORY_SIZE,
+  SAVE_STATE_VERSION, VIP_FRAME_CYCLES, VIP_INTERPRETER_PAGE_SIZE, VIP_INTERRUPT_CYCLES,
+};
+#[cfg(feature = "export")]
+use chip8_interpreter::{
+  write_pbm, write_png, GifRecorder, Palette, DEFAULT_PALETTE, GIF_FRAME_RATE,
 };
 
 const TRUE_PIXEL: &str = "@";
 const FALSE_PIXEL: &str = ".";
 
-fn test_by_instructions_count(
+fn encode_program(instructions: &[Instruction]) -> Vec<u8> {
+  instructions
+    .iter()
+    .flat_map(|instruction| instruction.encode().unwrap())
+    .collect()
+}
+
+fn create_interpreter(
   image: &[u8],
-  load_point: Address,
-  expected: &str,
-  instructions_count: usize,
-) {
-  let base_platform = BasePlatform::new(rand::random);
-  let executable = BaseExecutable::new(image, load_point);
-  let mut interpreter = ControlledInterpreter::new(
-    base_platform,
-    executable,
-    DEFAULT_INSTRUCTION_DURATION,
+  mode: Mode,
+  font: Font,
+  quirks: Quirks,
+  instruction_duration: Duration,
+) -> ControlledInterpreter<BasePlatform<fn() -> u8>> {
+  let base_platform = BasePlatform::new(rand::random as fn() -> u8);
+  let executable = BaseExecutable::new(image, Address::new::<0x200>());
+  let interpreter = Interpreter::new(base_platform, executable, mode, font, quirks).unwrap();
+  ControlledInterpreter::new(
+    interpreter,
+    instruction_duration,
     DEFAULT_DELAY_TIMER_DURATION,
     DEFAULT_SOUND_TIMER_DURATION,
+  )
+}
+
+/// Replaces the payload of `save_state` and sets its version, keeping the checksum valid.
+fn patch_save_state(save_state: &[u8], version: u16, payload: &[u8]) -> Vec<u8> {
+  let mut data = save_state[..4].to_vec();
+  data.extend_from_slice(&version.to_le_bytes());
+  data.push(save_state[6]);
+  data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+  data.extend_from_slice(payload);
+  data.extend_from_slice(&crc32(&data).to_le_bytes());
+  data
+}
+
+fn test_by_instructions_count(image: &[u8], expected: &str, instructions_count: usize) {
+  let mut interpreter = create_interpreter(
+    image,
+    Mode::Chip8,
+    Font::default(),
+    Quirks::default(),
+    DEFAULT_INSTRUCTION_DURATION,
   );
   for _ in 0..instructions_count {
     interpreter.simulate_one_instruction().unwrap();
@@ -43,8 +87,8 @@ fn assert_screen_frame_eq(screen_frame: &ScreenFrame, raw_expected: &str) {
       row
         .iter()
         .map(|value| match *value {
-          true => TRUE_PIXEL,
-          false => FALSE_PIXEL,
+          0 => FALSE_PIXEL,
+          _ => TRUE_PIXEL,
         })
         .collect::<String>()
     })
@@ -63,7 +107,6 @@ fn assert_screen_frame_eq(screen_frame: &ScreenFrame, raw_expected: &str) {
 fn test_chip8_splash_screen() {
   test_by_instructions_count(
     include_bytes!("./images/chip8-splash-screen.ch8"),
-    Address::new::<0x200>(),
     include_str!("./results/chip8-splash-screen.txt"),
     39,
   );
@@ -73,7 +116,6 @@ fn test_chip8_splash_screen() {
 fn test_ibm_logo() {
   test_by_instructions_count(
     include_bytes!("./images/ibm-logo.ch8"),
-    Address::new::<0x200>(),
     include_str!("./results/ibm-logo.txt"),
     20,
   );
@@ -83,7 +125,6 @@ fn test_ibm_logo() {
 fn test_corax_plus() {
   test_by_instructions_count(
     include_bytes!("./images/corax-plus.ch8"),
-    Address::new::<0x200>(),
     include_str!("./results/corax-plus.txt"),
     284,
   );
@@ -93,25 +134,73 @@ fn test_corax_plus() {
 fn test_flags() {
   test_by_instructions_count(
     include_bytes!("./images/flags.ch8"),
-    Address::new::<0x200>(),
     include_str!("./results/flags.txt"),
     1000,
   );
 }
 
+fn test_quirks_by_platform(mode: Mode, quirks: Quirks, menu_keys: &[Nibble], expected: &str) {
+  let mut interpreter = create_interpreter(
+    include_bytes!("./images/quirks.ch8"),
+    mode,
+    Font::default(),
+    quirks,
+    Duration::from_micros(500),
+  );
+  run_quirks_menu(&mut interpreter, menu_keys);
+  assert_screen_frame_eq(interpreter.get_platform_mut().get_screen_frame(), expected);
+}
+
+fn run_quirks_menu(
+  interpreter: &mut ControlledInterpreter<BasePlatform<fn() -> u8>>,
+  menu_keys: &[Nibble],
+) {
+  for &key in menu_keys {
+    interpreter
+      .get_platform_mut()
+      .change_keyboard_state(key, true);
+    interpreter
+      .simulate_duration(Duration::from_millis(1000))
+      .unwrap();
+    interpreter
+      .get_platform_mut()
+      .change_keyboard_state(key, false);
+    interpreter
+      .simulate_duration(Duration::from_millis(1000))
+      .unwrap();
+  }
+  interpreter
+    .simulate_duration(Duration::from_millis(5000))
+    .unwrap();
+}
+
+/// Checks the mark the quirks test draws next to the check with the given index.
+fn is_quirk_check_passed(screen_frame: &ScreenFrame, index: usize) -> bool {
+  const PASSED_MARK: [&str; 4] = ["........", "...@.@..", "...@@...", "...@...."];
+  screen_frame
+    .iter_rows()
+    .skip(index * 5 + 1)
+    .zip(PASSED_MARK)
+    .all(|(row, expected)| {
+      let mark: String = row[56..]
+        .iter()
+        .map(|value| match *value {
+          0 => FALSE_PIXEL,
+          _ => TRUE_PIXEL,
+        })
+        .collect();
+      mark == expected
+    })
+}
+
 #[test]
 fn test_quirks() {
-  let base_platform = BasePlatform::new(rand::random);
-  let executable = BaseExecutable::new(
+  let mut interpreter = create_interpreter(
     include_bytes!("./images/quirks.ch8"),
-    Address::new::<0x200>(),
-  );
-  let mut interpreter = ControlledInterpreter::new(
-    base_platform,
-    executable,
+    Mode::Chip8,
+    Font::default(),
+    Quirks::default(),
     DEFAULT_INSTRUCTION_DURATION,
-    DEFAULT_DELAY_TIMER_DURATION,
-    DEFAULT_SOUND_TIMER_DURATION,
   );
 
   interpreter
@@ -134,18 +223,151 @@ fn test_quirks() {
 }
 
 #[test]
-fn test_keypad() {
-  let base_platform = BasePlatform::new(rand::random);
-  let executable = BaseExecutable::new(
-    include_bytes!("./images/keypad.ch8"),
-    Address::new::<0x200>(),
+fn test_quirks_super_chip() {
+  test_quirks_by_platform(
+    Mode::SuperChip,
+    Quirks::SUPER_CHIP_1_1,
+    &[Nibble::new::<2>(), Nibble::new::<2>()],
+    include_str!("./results/quirks-super-chip.txt"),
   );
-  let mut interpreter = ControlledInterpreter::new(
+}
+
+#[test]
+fn test_quirks_cosmac_vip() {
+  let image = include_bytes!("./images/quirks.ch8");
+  let fixed = create_interpreter(
+    image,
+    Mode::Chip8,
+    Font::default(),
+    Quirks::COSMAC_VIP,
+    Duration::from_micros(500),
+  );
+  let base_platform = BasePlatform::new(rand::random as fn() -> u8);
+  let executable = BaseExecutable::new(image, Address::new::<0x200>());
+  let interpreter = Interpreter::new(
     base_platform,
     executable,
+    Mode::Chip8,
+    Font::default(),
+    Quirks::COSMAC_VIP,
+  )
+  .unwrap();
+  let vip = ControlledInterpreter::new_cosmac_vip(interpreter);
+
+  // Display wait passes both with the fixed instruction duration and with the VIP timing.
+  for mut interpreter in [fixed, vip] {
+    run_quirks_menu(&mut interpreter, &[Nibble::new::<1>()]);
+    let screen_frame = interpreter.get_platform_mut().get_screen_frame();
+    assert!(is_quirk_check_passed(screen_frame, 2));
+    assert_screen_frame_eq(
+      screen_frame,
+      include_str!("./results/quirks-cosmac-vip.txt"),
+    );
+  }
+}
+
+#[test]
+fn test_quirks_chip_48() {
+  test_quirks_by_platform(
+    Mode::Chip8,
+    Quirks::CHIP_48,
+    &[Nibble::new::<1>()],
+    include_str!("./results/quirks-chip-48.txt"),
+  );
+}
+
+#[test]
+fn test_quirks_xo_chip() {
+  test_quirks_by_platform(
+    Mode::XoChip,
+    Quirks::XO_CHIP,
+    &[Nibble::new::<3>()],
+    include_str!("./results/quirks-xo-chip.txt"),
+  );
+}
+
+#[test]
+fn test_quirk_presets() {
+  let v = |index: u8| Nibble::try_from(index).unwrap();
+  let program = encode_program(&[
+    Instruction::SetRegister(v(0), 0x04),
+    Instruction::SetRegister(v(1), 0x81),
+    Instruction::RShiftRegisters(v(0), v(1)),
+    Instruction::SetAddressRegister(Address::new::<0x300>()),
+    Instruction::WriteRegistersToMem(v(1)),
+    Instruction::SetRegister(v(0), 0x10),
+    Instruction::SetRegister(v(2), 0x20),
+    Instruction::JumpV0(Address::new::<0x210>()),
+  ]);
+  // Shift result, `I` after `FX55` and the target of `BNNN` for every preset.
+  let cases = [
+    (Quirks::COSMAC_VIP, 0x40, 0x302, 0x220),
+    (Quirks::CHIP_48, 0x02, 0x301, 0x230),
+    (Quirks::SUPER_CHIP_1_1, 0x02, 0x300, 0x230),
+    (Quirks::XO_CHIP, 0x40, 0x302, 0x220),
+  ];
+  for (quirks, shifted, index_register, jump_target) in cases {
+    let mut interpreter = create_interpreter(
+      &program,
+      Mode::Chip8,
+      Font::default(),
+      quirks,
+      DEFAULT_INSTRUCTION_DURATION,
+    );
+    let mut run = |count: usize| {
+      for _ in 0..count {
+        interpreter.simulate_one_instruction().unwrap();
+      }
+      let state = interpreter.get_state();
+      (
+        state.get_register(v(0)),
+        state.get_index_register(),
+        state.get_instruction_address(),
+      )
+    };
+    assert_eq!(run(3).0, shifted, "{quirks:?}");
+    assert_eq!(run(2).1, Address::from(index_register), "{quirks:?}");
+    assert_eq!(run(3).2, Address::from(jump_target), "{quirks:?}");
+  }
+}
+
+#[test]
+fn test_default_quirks() {
+  assert_eq!(
+    Quirks::default(),
+    Quirks {
+      display_wait: false,
+      ..Quirks::COSMAC_VIP
+    }
+  );
+
+  // Drawing with the default quirks does not wait for the end of the frame.
+  let program = encode_program(&[
+    Instruction::DrawSprite(Nibble::new::<0>(), Nibble::new::<0>(), Nibble::new::<1>()),
+    Instruction::SetRegister(Nibble::new::<0>(), 1),
+  ]);
+  let mut interpreter = create_interpreter(
+    &program,
+    Mode::Chip8,
+    Font::default(),
+    Quirks::default(),
+    DEFAULT_INSTRUCTION_DURATION,
+  );
+  interpreter.simulate_one_instruction().unwrap();
+  assert!(!interpreter.get_state().is_waiting_vblank());
+  interpreter.simulate_one_instruction().unwrap();
+  assert_eq!(interpreter.get_state().get_register(Nibble::new::<0>()), 1);
+  assert_eq!(interpreter.get_state().get_executed_count(), 2);
+}
+
+#[test]
+fn test_keypad() {
+  let mut interpreter = create_interpreter(
+    include_bytes!("./images/keypad.ch8"),
+    Mode::Chip8,
+    Font::default(),
+    Quirks::default(),
     DEFAULT_INSTRUCTION_DURATION,
-    DEFAULT_DELAY_TIMER_DURATION,
-    DEFAULT_SOUND_TIMER_DURATION,
   );
 
   interpreter
@@ -179,3 +401,1605 @@ fn test_keypad() {
     include_str!("./results/keypad.txt"),
   );
 }
+
+#[test]
+fn test_font_sets() {
+  #[rustfmt::skip]
+  let image = [
+    0x60, 0x00, 0x61, 0x00, 0x62, 0x00, 0xF0, 0x29, 0xD1, 0x25, 0x70, 0x01, 0x71, 0x08, 0x30, 0x08,
+    0x12, 0x16, 0x61, 0x00, 0x62, 0x06, 0x30, 0x10, 0x12, 0x06, 0x12, 0x1A,
+  ];
+
+  for (font_set, expected) in [
+    (
+      FontSet::CosmacVip,
+      include_str!("./results/font-cosmac-vip.txt"),
+    ),
+    (
+      FontSet::Dream6800,
+      include_str!("./results/font-dream-6800.txt"),
+    ),
+    (FontSet::Eti660, include_str!("./results/font-eti-660.txt")),
+    (FontSet::Octo, include_str!("./results/font-octo.txt")),
+    (
+      FontSet::SuperChip,
+      include_str!("./results/font-super-chip.txt"),
+    ),
+  ] {
+    let mut interpreter = create_interpreter(
+      &image,
+      Mode::Chip8,
+      Font::from_set(font_set, DEFAULT_FONT_LOAD_POINT),
+      Quirks::COSMAC_VIP,
+      DEFAULT_INSTRUCTION_DURATION,
+    );
+    for _ in 0..200 {
+      interpreter.simulate_one_instruction().unwrap();
+    }
+    assert_screen_frame_eq(interpreter.get_platform_mut().get_screen_frame(), expected);
+  }
+}
+
+#[test]
+fn test_super_chip_display() {
+  #[rustfmt::skip]
+  let image = [
+    0x00, 0xFF, 0x60, 0x07, 0xF0, 0x30, 0x61, 0x00, 0x62, 0x00, 0xD1, 0x2A, 0x00, 0xC4, 0x00, 0xFB,
+    0xA2, 0x18, 0x61, 0x10, 0xD1, 0x20, 0x00, 0xFD, 0xFF, 0xFF, 0x80, 0x01, 0x80, 0x01, 0x80, 0x01,
+    0x80, 0x01, 0x80, 0x01, 0x80, 0x01, 0x80, 0x01, 0x80, 0x01, 0x80, 0x01, 0x80, 0x01, 0x80, 0x01,
+    0x80, 0x01, 0x80, 0x01, 0x80, 0x01, 0xFF, 0xFF,
+  ];
+
+  let mut interpreter = create_interpreter(
+    &image,
+    Mode::SuperChip,
+    Font::from_set(FontSet::SuperChip, DEFAULT_FONT_LOAD_POINT),
+    Quirks::SUPER_CHIP_1_1,
+    DEFAULT_INSTRUCTION_DURATION,
+  );
+  for _ in 0..20 {
+    interpreter.simulate_one_instruction().unwrap();
+  }
+  assert_screen_frame_eq(
+    interpreter.get_platform_mut().get_screen_frame(),
+    include_str!("./results/super-chip-display.txt"),
+  );
+}
+
+#[test]
+fn test_xo_chip_planes() {
+  #[rustfmt::skip]
+  let image = [
+    0x60, 0x00, 0xF0, 0x00, 0x02, 0x20, 0xF3, 0x01, 0xD0, 0x01, 0x30, 0x00, 0xF0, 0x00, 0x00, 0x00,
+    0x61, 0x05, 0xF1, 0x01, 0xD1, 0x01, 0x12, 0x16, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xC0, 0xA0,
+  ];
+
+  let mut interpreter = create_interpreter(
+    &image,
+    Mode::XoChip,
+    Font::default(),
+    Quirks::XO_CHIP,
+    DEFAULT_INSTRUCTION_DURATION,
+  );
+  for _ in 0..10 {
+    interpreter.simulate_one_instruction().unwrap();
+  }
+
+  let screen_frame = interpreter.get_platform_mut().get_screen_frame();
+  let first_row: Vec<u8> = (0..8).map(|x| screen_frame.get_pixel(x, 0)).collect();
+  assert_eq!(first_row, [3, 1, 2, 0, 0, 1, 1, 0]);
+}
+
+#[test]
+fn test_address_wrapping() {
+  let program = encode_program(&[
+    Instruction::SetAddressRegister(Address::new::<0xFFE>()),
+    Instruction::SetRegister(Nibble::new::<0>(), 5),
+    Instruction::AddAddressRegister(Nibble::new::<0>()),
+    Instruction::Jump(Address::new::<0xFFE>()),
+    Instruction::ClearScreen,
+  ]);
+  for (mode, index_register, instruction_address) in [
+    (Mode::Chip8, 0x003, 0x000),
+    (Mode::SuperChip, 0x003, 0x000),
+    (Mode::XoChip, 0x1003, 0x1000),
+  ] {
+    let mut interpreter = create_interpreter(
+      &program,
+      mode,
+      Font::default(),
+      Quirks::default(),
+      DEFAULT_INSTRUCTION_DURATION,
+    );
+    interpreter
+      .get_interpreter_mut()
+      .write_memory(Address::new::<0xFFE>(), &[0x00, 0xE0]);
+    for _ in 0..5 {
+      interpreter.simulate_one_instruction().unwrap();
+    }
+    let state = interpreter.get_state();
+    assert_eq!(
+      state.get_index_register(),
+      Address::from(index_register),
+      "{mode:?}"
+    );
+    assert_eq!(
+      state.get_instruction_address(),
+      Address::from(instruction_address),
+      "{mode:?}"
+    );
+  }
+  assert_eq!(Address::new::<0xABC>().to_string(), "0ABC");
+}
+
+#[test]
+fn test_state_inspection() {
+  let image = [0x22, 0x04, 0x00, 0x00, 0x6A, 0x42, 0xA3, 0x00, 0xF5, 0x0A];
+  let mut interpreter = create_interpreter(
+    &image,
+    Mode::Chip8,
+    Font::default(),
+    Quirks::COSMAC_VIP,
+    DEFAULT_INSTRUCTION_DURATION,
+  );
+  for _ in 0..4 {
+    interpreter.simulate_one_instruction().unwrap();
+  }
+
+  let state = interpreter.get_state();
+  assert_eq!(state.get_register(Nibble::new::<0xA>()), 0x42);
+  assert_eq!(state.get_index_register(), Address::new::<0x300>());
+  assert_eq!(state.get_instruction_address(), Address::new::<0x20A>());
+  assert_eq!(state.get_stack(), [Address::new::<0x202>()]);
+  assert_eq!(state.get_stack_depth(), 1);
+  assert_eq!(state.get_expecting_key(), Some(Nibble::new::<5>()));
+  assert_eq!(
+    state.get_memory_slice(Address::new::<0x204>(), 2),
+    Some([0x6A, 0x42].as_slice())
+  );
+  assert!(!state.is_crashed());
+
+  let interpreter = interpreter.get_interpreter_mut();
+  interpreter.set_register(Nibble::new::<0>(), 7);
+  interpreter.write_memory(Address::new::<0x300>(), &[1, 2, 3]);
+  let state = interpreter.get_state();
+  assert_eq!(state.get_registers()[0], 7);
+  assert_eq!(
+    state.get_memory_slice(Address::new::<0x300>(), 3),
+    Some([1, 2, 3].as_slice())
+  );
+}
+
+#[test]
+fn test_stack_depth() {
+  let image = [0x22, 0x00];
+  let mut interpreter = create_interpreter(
+    &image,
+    Mode::Chip8,
+    Font::default(),
+    Quirks::COSMAC_VIP,
+    DEFAULT_INSTRUCTION_DURATION,
+  );
+  for _ in 0..16 {
+    interpreter.simulate_one_instruction().unwrap();
+  }
+  assert_eq!(interpreter.get_state().get_stack_depth(), 16);
+  assert!(interpreter.simulate_one_instruction().is_err());
+  assert!(interpreter.get_state().is_crashed());
+}
+
+#[test]
+fn test_save_state_round_trip() {
+  let image = include_bytes!("./images/ibm-logo.ch8");
+  let quirks = Quirks {
+    display_wait: false,
+    ..Quirks::COSMAC_VIP
+  };
+  let mut interpreter = create_interpreter(
+    image,
+    Mode::Chip8,
+    Font::default(),
+    quirks,
+    DEFAULT_INSTRUCTION_DURATION,
+  );
+  for _ in 0..10 {
+    interpreter.simulate_one_instruction().unwrap();
+  }
+  let save_state = interpreter.save_state();
+  for _ in 0..10 {
+    interpreter.simulate_one_instruction().unwrap();
+  }
+
+  let mut restored = create_interpreter(
+    &[0x00, 0xE0],
+    Mode::Chip8,
+    Font::default(),
+    quirks,
+    DEFAULT_INSTRUCTION_DURATION,
+  );
+  restored.load_state(&save_state).unwrap();
+  for _ in 0..10 {
+    restored.simulate_one_instruction().unwrap();
+  }
+
+  assert_eq!(restored.save_state(), interpreter.save_state());
+  assert_screen_frame_eq(
+    restored.get_platform_mut().get_screen_frame(),
+    include_str!("./results/ibm-logo.txt"),
+  );
+}
+
+#[test]
+fn test_save_state_validation() {
+  let mut interpreter = create_interpreter(
+    include_bytes!("./images/ibm-logo.ch8"),
+    Mode::Chip8,
+    Font::default(),
+    Quirks::COSMAC_VIP,
+    DEFAULT_INSTRUCTION_DURATION,
+  );
+  let save_state = interpreter.save_state();
+
+  let mut corrupted = save_state.clone();
+  corrupted[20] ^= 0xFF;
+  assert!(matches!(
+    interpreter.load_state(&corrupted),
+    Err(SaveStateError::ChecksumMismatch)
+  ));
+  assert!(matches!(
+    interpreter.load_state(&save_state[..save_state.len() - 1]),
+    Err(SaveStateError::ChecksumMismatch)
+  ));
+  assert!(matches!(
+    interpreter.load_state(b"not a save state"),
+    Err(SaveStateError::InvalidMagic)
+  ));
+  assert!(matches!(
+    interpreter.get_platform_mut().load_state(&save_state),
+    Err(SaveStateError::WrongKind(_))
+  ));
+
+  let mut xo_chip_interpreter = create_interpreter(
+    &[0x00, 0xE0],
+    Mode::XoChip,
+    Font::default(),
+    Quirks::XO_CHIP,
+    DEFAULT_INSTRUCTION_DURATION,
+  );
+  assert!(matches!(
+    xo_chip_interpreter.load_state(&save_state),
+    Err(SaveStateError::ModeMismatch)
+  ));
+
+  let create_vip_interpreter = || {
+    let platform = BasePlatform::new(rand::random as fn() -> u8);
+    let executable = BaseExecutable::new(&[0x00, 0xE0], Address::new::<0x200>());
+    Interpreter::new(
+      platform,
+      executable,
+      Mode::Chip8,
+      Font::default(),
+      Quirks::COSMAC_VIP,
+    )
+    .unwrap()
+  };
+  let mut vip_interpreter = ControlledInterpreter::new_cosmac_vip(create_vip_interpreter());
+  assert!(matches!(
+    vip_interpreter.load_state(&save_state),
+    Err(SaveStateError::TimingMismatch)
+  ));
+  let mut frame_based = ControlledInterpreter::new_frame_based(create_vip_interpreter(), 10);
+  let frame_based_state = frame_based.save_state();
+  assert!(matches!(
+    ControlledInterpreter::new_frame_based(create_vip_interpreter(), 20)
+      .load_state(&frame_based_state),
+    Err(SaveStateError::TimingMismatch)
+  ));
+  frame_based.load_state(&frame_based_state).unwrap();
+
+  // Crashed states of version 4 have no cause and load with an unknown one.
+  let program = encode_program(&[
+    Instruction::SetRegister(Nibble::new::<5>(), 0x20),
+    Instruction::SkipIfKeyDown(Nibble::new::<5>()),
+  ]);
+  let mut crashed = create_interpreter(
+    &program,
+    Mode::Chip8,
+    Font::default(),
+    Quirks::default(),
+    DEFAULT_INSTRUCTION_DURATION,
+  );
+  crashed
+    .simulate_duration(Duration::from_millis(100))
+    .unwrap_err();
+  let crashed_state = crashed.get_interpreter().save_state();
+  // The interpreter payload ends with the one byte cause `NibbleOverflow`.
+  let crashed_payload = &crashed_state[11..crashed_state.len() - 5];
+  let v4_state = patch_save_state(&crashed_state, 4, crashed_payload);
+  let mut restored = create_interpreter(
+    &program,
+    Mode::Chip8,
+    Font::default(),
+    Quirks::default(),
+    DEFAULT_INSTRUCTION_DURATION,
+  );
+  restored
+    .get_interpreter_mut()
+    .load_state(&v4_state)
+    .unwrap();
+  let Some(InterpreterError::Fault { cause, report }) = restored.get_interpreter().get_crash()
+  else {
+    panic!("Crashed state has to load as a fault");
+  };
+  assert_eq!(**cause, InterpreterError::UnknownCause);
+  assert_eq!(report.address, Address::new::<0x202>());
+  assert_eq!(report.op_code, OpCode::from(0xE59E));
+  assert!(matches!(
+    restored.simulate_one_instruction(),
+    Err(InterpreterError::Crashed(_))
+  ));
+}
+
+#[test]
+fn test_save_state_failed_load() {
+  let v0 = Nibble::new::<0>();
+  let image = encode_program(&[
+    Instruction::SetAddressRegister(Address::new::<0x300>()),
+    Instruction::AddRegister(v0, 1),
+    Instruction::WriteRegistersToMem(v0),
+    Instruction::Jump(Address::new::<0x202>()),
+  ]);
+  let mut interpreter = create_interpreter(
+    &image,
+    Mode::Chip8,
+    Font::default(),
+    Quirks::CHIP_48,
+    DEFAULT_INSTRUCTION_DURATION,
+  );
+  interpreter
+    .simulate_duration(Duration::from_millis(100))
+    .unwrap();
+  let save_state = interpreter.save_state();
+  let payload = &save_state[11..save_state.len() - 4];
+  interpreter
+    .simulate_duration(Duration::from_millis(100))
+    .unwrap();
+
+  let registers = interpreter.get_state().get_registers();
+  let memory = interpreter.get_state().get_memory().clone();
+  let elapsed = interpreter.get_elapsed();
+  let current_state = interpreter.save_state();
+
+  // Both payloads fail after the interpreter part was already read.
+  let truncated = patch_save_state(
+    &save_state,
+    SAVE_STATE_VERSION,
+    &payload[..payload.len() - 1],
+  );
+  assert!(matches!(
+    interpreter.load_state(&truncated),
+    Err(SaveStateError::UnexpectedEnd)
+  ));
+  let mut trailing_payload = payload.to_vec();
+  trailing_payload.push(0);
+  let trailing = patch_save_state(&save_state, SAVE_STATE_VERSION, &trailing_payload);
+  assert!(matches!(
+    interpreter.load_state(&trailing),
+    Err(SaveStateError::TrailingData)
+  ));
+
+  assert_eq!(interpreter.get_state().get_registers(), registers);
+  assert_eq!(interpreter.get_state().get_memory(), &memory);
+  assert_eq!(interpreter.get_elapsed(), elapsed);
+  assert_eq!(interpreter.save_state(), current_state);
+
+  interpreter.load_state(&save_state).unwrap();
+  assert_ne!(interpreter.get_state().get_registers(), registers);
+}
+
+#[test]
+fn test_breakpoints_and_watchpoints() {
+  let v0 = Nibble::new::<0>();
+  let image = encode_program(&[
+    Instruction::SetRegister(v0, 5),
+    Instruction::SetAddressRegister(Address::new::<0x300>()),
+    Instruction::AddRegister(v0, 1),
+    Instruction::WriteRegistersToMem(v0),
+    Instruction::Jump(Address::new::<0x204>()),
+  ]);
+  let mut interpreter = create_interpreter(
+    &image,
+    Mode::Chip8,
+    Font::default(),
+    Quirks::COSMAC_VIP,
+    DEFAULT_INSTRUCTION_DURATION,
+  );
+  let second = Duration::from_millis(1000);
+  let loop_start = Breakpoint::Address(Address::new::<0x204>());
+
+  interpreter.add_breakpoint(loop_start);
+  assert_eq!(
+    interpreter.simulate_duration(second).unwrap(),
+    StopReason::BreakpointHit(loop_start)
+  );
+  assert_eq!(
+    interpreter.get_state().get_instruction_address(),
+    Address::new::<0x204>()
+  );
+  assert_eq!(interpreter.get_state().get_register(v0), 5);
+  assert_eq!(
+    interpreter.simulate_duration(second).unwrap(),
+    StopReason::BreakpointHit(loop_start)
+  );
+  assert_eq!(interpreter.get_state().get_register(v0), 6);
+  assert!(interpreter.remove_breakpoint(loop_start));
+
+  let store = Breakpoint::OpCodePattern {
+    value: 0xF055,
+    mask: 0xF0FF,
+  };
+  interpreter.add_breakpoint(store);
+  assert_eq!(
+    interpreter.simulate_duration(second).unwrap(),
+    StopReason::BreakpointHit(store)
+  );
+  assert_eq!(
+    interpreter.get_state().get_instruction_address(),
+    Address::new::<0x206>()
+  );
+  interpreter.clear_breakpoints();
+
+  interpreter.add_watchpoint(Watchpoint::IndexRegister);
+  assert_eq!(
+    interpreter.simulate_duration(second).unwrap(),
+    StopReason::WatchpointHit(Watchpoint::IndexRegister)
+  );
+  assert_eq!(
+    interpreter.get_state().get_index_register(),
+    Address::new::<0x302>()
+  );
+  interpreter.clear_watchpoints();
+
+  let register = Watchpoint::Register(v0);
+  interpreter.add_watchpoint(register);
+  assert_eq!(
+    interpreter.simulate_duration(second).unwrap(),
+    StopReason::WatchpointHit(register)
+  );
+  assert_eq!(interpreter.get_state().get_register(v0), 8);
+  assert!(interpreter.remove_watchpoint(register));
+
+  let memory = Watchpoint::Memory {
+    start: Address::new::<0x303>(),
+    len: 2,
+  };
+  interpreter.add_watchpoint(memory);
+  assert_eq!(
+    interpreter.simulate_duration(second).unwrap(),
+    StopReason::WatchpointHit(memory)
+  );
+  assert_eq!(
+    interpreter
+      .get_state()
+      .get_memory_slice(Address::new::<0x302>(), 2),
+    Some([8, 9].as_slice())
+  );
+  interpreter.clear_watchpoints();
+
+  assert_eq!(
+    interpreter.simulate_duration(second).unwrap(),
+    StopReason::TimeElapsed
+  );
+}
+
+#[test]
+fn test_disassembler() {
+  let image = [
+    0x00, 0xE0, 0xA2, 0x0E, 0x22, 0x08, 0x12, 0x06, 0x60, 0x00, 0xD0, 0x05, 0x00, 0xEE, 0x3C, 0x42,
+    0x42, 0x3C,
+  ];
+  let disassembly = Disassembly::new(
+    &image,
+    Address::new::<0x200>(),
+    Address::new::<0x200>(),
+    Mode::Chip8,
+  );
+
+  assert_eq!(
+    disassembly.to_listing(Syntax::Cowgod),
+    include_str!("./results/disassembly-cowgod.txt")
+  );
+  assert_eq!(
+    disassembly.to_listing(Syntax::Octo),
+    include_str!("./results/disassembly-octo.txt")
+  );
+  assert_eq!(
+    disassembly.get_label(Address::new::<0x208>()),
+    Some("sub_0208")
+  );
+  assert!(!disassembly.get_lines().last().unwrap().is_code());
+}
+
+#[test]
+fn test_assembler() {
+  let source = "
+    :alias x v1
+    :const SPEED 2
+    :macro move reg amount { reg += amount }
+
+    : main
+      clear
+      x := 0
+      loop
+        i := sprite
+        sprite x x 4
+        move x SPEED
+        while x != 8
+      again
+      if x == 8 then v2 := 1
+      if v2 key begin
+        v3 := 1
+      else
+        v3 := 2
+      end
+      :call wait
+    : halt
+      jump halt
+    : wait
+      return
+
+    :org 0x230
+    : sprite
+      0x3C 0x42 0b01000010 60
+  ";
+  let program = assemble(source).unwrap();
+
+  let mut expected = vec![
+    0x00, 0xE0, 0x61, 0x00, 0xA2, 0x30, 0xD1, 0x14, 0x71, 0x02, 0x41, 0x08, 0x12, 0x10, 0x12, 0x04,
+    0x41, 0x08, 0x62, 0x01, 0xE2, 0x9E, 0x12, 0x1C, 0x63, 0x01, 0x12, 0x1E, 0x63, 0x02, 0x22, 0x22,
+    0x12, 0x20, 0x00, 0xEE,
+  ];
+  expected.resize(0x30, 0);
+  expected.extend_from_slice(&[0x3C, 0x42, 0x42, 0x3C]);
+  assert_eq!(program.get_image(), expected);
+  assert_eq!(program.get_load_point(), Address::new::<0x200>());
+  assert_eq!(program.get_label("wait"), Some(Address::new::<0x222>()));
+  assert_eq!(program.get_label("sprite"), Some(Address::new::<0x230>()));
+  let source_lines = program.get_source_lines();
+  assert_eq!(source_lines.get(&Address::new::<0x200>()), Some(&7));
+  assert_eq!(source_lines.get(&Address::new::<0x202>()), Some(&8));
+  assert_eq!(source_lines.get(&Address::new::<0x222>()), Some(&25));
+  assert_eq!(source_lines.get(&Address::new::<0x230>()), None);
+
+  let errors = [
+    (
+      ": main\n  v0 := 300",
+      2,
+      AssemblerErrorKind::ValueOutOfRange(String::from("300")),
+    ),
+    (
+      "clear\n\n  jump nowhere",
+      3,
+      AssemblerErrorKind::UnknownIdentifier(String::from("nowhere")),
+    ),
+    (
+      "loop\n  clear",
+      1,
+      AssemblerErrorKind::UnbalancedBlock(String::from("loop")),
+    ),
+    (
+      ": a\n: a",
+      2,
+      AssemblerErrorKind::DuplicateSymbol(String::from("a")),
+    ),
+  ];
+  for (source, line, kind) in errors {
+    let error = assemble(source).unwrap_err();
+    assert_eq!(error.get_line(), line);
+    assert_eq!(error.get_kind(), &kind);
+  }
+}
+
+#[test]
+fn test_instruction_encoding() {
+  for value in 0..=u16::MAX {
+    let code = OpCode::from(value);
+    if let Ok(instruction) = Instruction::try_from(code) {
+      assert_eq!(OpCode::try_from(instruction).unwrap(), code);
+      assert_eq!(instruction.encode().unwrap(), code.to_bytes());
+    }
+  }
+
+  let long = Instruction::SetLongAddressRegister(Address::new::<0xABCD>());
+  let bytes = long.encode().unwrap();
+  assert_eq!(bytes, [0xF0, 0x00, 0xAB, 0xCD]);
+  assert_eq!(
+    Instruction::decode(
+      OpCode::from_bytes(bytes[0], bytes[1]),
+      OpCode::from_bytes(bytes[2], bytes[3])
+    )
+    .unwrap(),
+    long
+  );
+
+  assert!(matches!(
+    Instruction::Jump(Address::new::<0x1000>()).encode(),
+    Err(InterpreterError::AddressOverflow(_))
+  ));
+}
+
+#[test]
+fn test_trace() {
+  let (v0, v1) = (Nibble::new::<0>(), Nibble::new::<1>());
+  let image = encode_program(&[
+    Instruction::SetRegister(v0, 123),
+    Instruction::SetAddressRegister(Address::new::<0x300>()),
+    Instruction::RegisterToBCD(v0),
+    Instruction::SetRegister(v1, 0xFF),
+    Instruction::AddRegisters(v1, v1),
+    Instruction::Jump(Address::new::<0x20A>()),
+  ]);
+  let mut interpreter = create_interpreter(
+    &image,
+    Mode::Chip8,
+    Font::default(),
+    Quirks::COSMAC_VIP,
+    DEFAULT_INSTRUCTION_DURATION,
+  );
+
+  let ring_buffer = Arc::new(Mutex::new(RingBufferSink::new(4)));
+  interpreter
+    .get_interpreter_mut()
+    .set_trace_sink(ring_buffer.clone());
+  for _ in 0..6 {
+    interpreter.simulate_one_instruction().unwrap();
+  }
+
+  let ring_buffer = ring_buffer.lock().unwrap();
+  let records = ring_buffer.iter().collect::<Vec<_>>();
+  assert_eq!(records.len(), 4);
+  assert_eq!(records[0].get_cycle(), 2);
+  assert_eq!(records[0].get_instruction(), Instruction::RegisterToBCD(v0));
+  assert_eq!(
+    records[0].get_memory_writes(),
+    [
+      (Address::new::<0x300>(), 1),
+      (Address::new::<0x301>(), 2),
+      (Address::new::<0x302>(), 3)
+    ]
+  );
+  assert_eq!(records[0].iter_register_changes().count(), 0);
+  assert_eq!(
+    records[2].iter_register_changes().collect::<Vec<_>>(),
+    [(v1, 0xFF, 0xFE), (Nibble::MAX, 0, 1)]
+  );
+  assert_eq!(records[2].get_vf(), 1);
+  assert_eq!(records[3].get_address(), Address::new::<0x20A>());
+  assert_eq!(u16::from(records[3].get_op_code()), 0x120A);
+
+  let text_log = Arc::new(Mutex::new(TextLogSink::new(Vec::new())));
+  interpreter
+    .get_interpreter_mut()
+    .set_trace_sink(text_log.clone());
+  interpreter.simulate_one_instruction().unwrap();
+  assert_eq!(
+    String::from_utf8(text_log.lock().unwrap().get_writer().clone()).unwrap(),
+    "PC:020A OP:120A I:0300 V0:7B V1:FE V2:00 V3:00 V4:00 V5:00 V6:00 V7:00 V8:00 V9:00 VA:00 \
+     VB:00 VC:00 VD:00 VE:00 VF:01 CYCLE:6 | jump 0x20A\n"
+  );
+
+  let binary_log = Arc::new(Mutex::new(BinaryLogSink::new(Vec::new())));
+  interpreter
+    .get_interpreter_mut()
+    .set_trace_sink(binary_log.clone());
+  interpreter.simulate_one_instruction().unwrap();
+  let mut expected = b"C8TR\x01\x00".to_vec();
+  expected.extend_from_slice(&7u64.to_le_bytes());
+  expected.extend_from_slice(&[0x0A, 0x02, 2, 0x12, 0x0A, 0, 0, 0, 0, 0]);
+  assert_eq!(binary_log.lock().unwrap().get_writer(), &expected);
+
+  // The trace sink is not part of the compared machine state.
+  let create_traced_interpreter = || {
+    let platform = BasePlatform::new(SeededRandom::new(1));
+    let executable = BaseExecutable::new(&image, Address::new::<0x200>());
+    Interpreter::new(
+      platform,
+      executable,
+      Mode::Chip8,
+      Font::default(),
+      Quirks::COSMAC_VIP,
+    )
+    .unwrap()
+  };
+  let mut traced = create_traced_interpreter();
+  let mut untraced = create_traced_interpreter();
+  traced.set_trace_sink(RingBufferSink::new(4));
+  assert!(traced == untraced);
+  traced.run_next().unwrap();
+  assert!(traced != untraced);
+  untraced.run_next().unwrap();
+  assert!(traced == untraced);
+
+  // Writes past the end of the memory are traced at the addresses they wrap to.
+  let image = encode_program(&[
+    Instruction::SetAddressRegister(Address::new::<0xFFE>()),
+    Instruction::SetRegister(v0, 123),
+    Instruction::RegisterToBCD(v0),
+  ]);
+  let mut interpreter = create_interpreter(
+    &image,
+    Mode::Chip8,
+    Font::default(),
+    Quirks::COSMAC_VIP,
+    DEFAULT_INSTRUCTION_DURATION,
+  );
+  let ring_buffer = Arc::new(Mutex::new(RingBufferSink::new(1)));
+  interpreter
+    .get_interpreter_mut()
+    .set_trace_sink(ring_buffer.clone());
+  for _ in 0..3 {
+    interpreter.simulate_one_instruction().unwrap();
+  }
+  assert_eq!(
+    ring_buffer
+      .lock()
+      .unwrap()
+      .iter()
+      .next()
+      .unwrap()
+      .get_memory_writes(),
+    [
+      (Address::new::<0xFFE>(), 1),
+      (Address::new::<0xFFF>(), 2),
+      (Address::new::<0x000>(), 3)
+    ]
+  );
+  assert_eq!(
+    interpreter.get_state().get_memory()[Address::new::<0x000>()],
+    3
+  );
+}
+
+fn assert_send<T: Send>() {}
+
+#[test]
+fn test_send() {
+  assert_send::<Interpreter<BasePlatform<SeededRandom>>>();
+  assert_send::<ControlledInterpreter<BasePlatform<SeededRandom>>>();
+}
+
+#[test]
+fn test_rewind() {
+  let (v0, v1) = (Nibble::new::<0>(), Nibble::new::<1>());
+  let image = encode_program(&[
+    Instruction::SetRegister(v1, 0xFF),
+    Instruction::SetDelayTimer(v1),
+    Instruction::AddRegister(v0, 1),
+    Instruction::Jump(Address::new::<0x204>()),
+  ]);
+  let mut interpreter = create_interpreter(
+    &image,
+    Mode::Chip8,
+    Font::default(),
+    Quirks::COSMAC_VIP,
+    DEFAULT_INSTRUCTION_DURATION,
+  );
+  assert!(matches!(
+    interpreter.step_back_instruction(),
+    Err(RewindError::Disabled)
+  ));
+
+  interpreter.enable_rewind(DEFAULT_REWIND_INTERVAL, DEFAULT_REWIND_CAPACITY);
+  interpreter
+    .simulate_duration(Duration::from_millis(700))
+    .unwrap();
+  let get_snapshot = |interpreter: &ControlledInterpreter<_>| {
+    let state = interpreter.get_state();
+    (
+      state.get_registers(),
+      state.get_delay_timer(),
+      state.get_executed_count(),
+      state.get_instruction_address(),
+    )
+  };
+  let snapshot = get_snapshot(&interpreter);
+
+  interpreter
+    .simulate_duration(Duration::from_millis(300))
+    .unwrap();
+  let executed_count = interpreter.get_state().get_executed_count();
+  let counter = interpreter.get_state().get_register(v0);
+
+  interpreter.step_back_instruction().unwrap();
+  assert_eq!(
+    interpreter.get_state().get_executed_count(),
+    executed_count - 1
+  );
+  interpreter.step_back_instruction().unwrap();
+  assert_eq!(
+    interpreter.get_state().get_executed_count(),
+    executed_count - 2
+  );
+  assert_eq!(interpreter.get_state().get_register(v0), counter - 1);
+
+  interpreter
+    .rewind(interpreter.get_elapsed() - Duration::from_millis(700))
+    .unwrap();
+  assert_eq!(interpreter.get_elapsed(), Duration::from_millis(700));
+  assert_eq!(get_snapshot(&interpreter), snapshot);
+
+  let delay_timer = interpreter.get_state().get_delay_timer();
+  interpreter.step_back_frame().unwrap();
+  assert_eq!(interpreter.get_state().get_delay_timer(), delay_timer + 1);
+
+  assert!(matches!(
+    interpreter.rewind(Duration::from_secs(1)),
+    Err(RewindError::OutOfRange)
+  ));
+
+  // Snapshots keep the state of the random generator, so replays draw the same values.
+  let image = encode_program(&[
+    Instruction::SetRegisterRandom(v0, 0xFF),
+    Instruction::AddRegisters(v1, v0),
+    Instruction::Jump(Address::new::<0x200>()),
+  ]);
+  let executable = BaseExecutable::new(&image, Address::new::<0x200>());
+  let interpreter = Interpreter::new(
+    BasePlatform::new(SeededRandom::new(1)),
+    executable,
+    Mode::Chip8,
+    Font::default(),
+    Quirks::COSMAC_VIP,
+  )
+  .unwrap();
+  let mut interpreter = ControlledInterpreter::new(
+    interpreter,
+    DEFAULT_INSTRUCTION_DURATION,
+    DEFAULT_DELAY_TIMER_DURATION,
+    DEFAULT_SOUND_TIMER_DURATION,
+  );
+  interpreter.enable_rewind(DEFAULT_REWIND_INTERVAL, DEFAULT_REWIND_CAPACITY);
+  interpreter
+    .simulate_duration(Duration::from_millis(1000))
+    .unwrap();
+  let registers = interpreter.get_state().get_registers();
+  interpreter
+    .rewind(interpreter.get_elapsed() - Duration::from_millis(700))
+    .unwrap();
+  interpreter
+    .simulate_duration(Duration::from_millis(300))
+    .unwrap();
+  assert_eq!(interpreter.get_elapsed(), Duration::from_millis(1000));
+  assert_eq!(interpreter.get_state().get_registers(), registers);
+}
+
+#[cfg(feature = "export")]
+#[test]
+fn test_export() {
+  let program = assemble(
+    "
+    : main
+      i := 0x050
+      v0 := 0
+      v1 := 0
+      sprite v0 v1 5
+      v2 := 30
+      delay := v2
+      loop
+        v2 := delay
+        while v2 != 0
+      again
+      clear
+    : halt
+      jump halt
+    ",
+  )
+  .unwrap();
+  let mut interpreter = create_interpreter(
+    program.get_image(),
+    Mode::Chip8,
+    Font::default(),
+    Quirks::COSMAC_VIP,
+    DEFAULT_INSTRUCTION_DURATION,
+  );
+
+  let mut recorder = GifRecorder::new(Vec::new(), 1, &DEFAULT_PALETTE).unwrap();
+  recorder.record_frame(&mut interpreter).unwrap();
+  let screen_frame = *interpreter.get_platform().get_screen_frame();
+
+  let mut png_data = Vec::new();
+  write_png(&mut png_data, &screen_frame, 2, &DEFAULT_PALETTE).unwrap();
+  let mut decoder = png::Decoder::new(&png_data[..]);
+  decoder.set_transformations(png::Transformations::IDENTITY);
+  let mut reader = decoder.read_info().unwrap();
+  assert_eq!((reader.info().width, reader.info().height), (128, 64));
+  let mut pixels = vec![0; reader.output_buffer_size()];
+  reader.next_frame(&mut pixels).unwrap();
+  assert_eq!(pixels[..10], [1, 1, 1, 1, 1, 1, 1, 1, 0, 0]);
+
+  let mut pbm_data = Vec::new();
+  let paper = Palette::monochrome([0xFF, 0xFF, 0xFF], [0x00, 0x00, 0x00]);
+  write_pbm(&mut pbm_data, &screen_frame, 1, &paper).unwrap();
+  let header = b"P4\n64 32\n";
+  assert_eq!(&pbm_data[..header.len()], header);
+  assert_eq!(pbm_data.len(), header.len() + 8 * 32);
+  assert_eq!(pbm_data[header.len()..header.len() + 2], [0xF0, 0x00]);
+
+  for _ in 1..GIF_FRAME_RATE {
+    recorder.record_frame(&mut interpreter).unwrap();
+  }
+  assert_eq!(recorder.get_captured_count(), GIF_FRAME_RATE as u64);
+  let gif_data = recorder.finish().unwrap();
+
+  let mut decoder = gif::DecodeOptions::new().read_info(&gif_data[..]).unwrap();
+  assert_eq!((decoder.width(), decoder.height()), (128, 64));
+  let mut delays = Vec::new();
+  while let Some(frame) = decoder.read_next_frame().unwrap() {
+    delays.push(frame.delay);
+  }
+  assert_eq!(delays.len(), 2);
+  assert_eq!(delays.iter().sum::<u16>(), 100);
+}
+
+#[test]
+fn test_audio() {
+  let program = assemble(
+    "
+    : main
+      v0 := 6
+      buzzer := v0
+    : halt
+      jump halt
+    ",
+  )
+  .unwrap();
+  let mut interpreter = create_interpreter(
+    program.get_image(),
+    Mode::Chip8,
+    Font::default(),
+    Quirks::COSMAC_VIP,
+    DEFAULT_INSTRUCTION_DURATION,
+  );
+  interpreter.enable_audio(AudioConfig {
+    sample_rate: 8000,
+    frequency: 1000.0,
+    volume: 0.5,
+    envelope: Duration::from_millis(1),
+    buffer_duration: Duration::from_secs(1),
+  });
+  interpreter
+    .simulate_duration(Duration::from_millis(200))
+    .unwrap();
+
+  let audio = interpreter.get_audio_mut().unwrap();
+  assert_eq!(audio.get_generated_count(), 1600);
+  let samples = audio.get_buffer_mut().drain();
+  assert_eq!(samples.len(), 1600);
+
+  let peak = i16::MAX / 2;
+  let fade_in = samples.iter().position(|sample| *sample != 0).unwrap();
+  assert_eq!(fade_in, 32);
+  assert!(samples[fade_in..fade_in + 7]
+    .iter()
+    .all(|sample| sample.abs() < peak));
+  assert!(samples[100..700].iter().all(|sample| sample.abs() == peak));
+  assert_eq!(
+    samples[96..104],
+    [peak, peak, peak, peak, -peak, -peak, -peak, -peak]
+  );
+  assert!(samples[800..].iter().all(|sample| *sample == 0));
+  let fade_out = samples.iter().rposition(|sample| *sample != 0).unwrap();
+  assert!(samples[fade_out - 4..=fade_out]
+    .iter()
+    .all(|sample| sample.abs() < peak));
+
+  let mut wav_data = Vec::new();
+  write_wav(&mut wav_data, 8000, &samples).unwrap();
+  assert_eq!(wav_data.len(), 44 + 1600 * 2);
+  assert_eq!(&wav_data[..4], b"RIFF");
+  assert_eq!(&wav_data[8..16], b"WAVEfmt ");
+  assert_eq!(wav_data[24..28], 8000u32.to_le_bytes());
+  assert_eq!(&wav_data[36..40], b"data");
+
+  let mut buffer = AudioBuffer::new(4);
+  (1..=6).for_each(|sample| buffer.push(sample));
+  let mut output = [-1; 6];
+  assert_eq!(buffer.pull(&mut output), 4);
+  assert_eq!(output, [3, 4, 5, 6, 0, 0]);
+  assert!(buffer.is_empty());
+}
+
+#[test]
+fn test_movie() {
+  let program = assemble(
+    "
+    : main
+      v0 := key
+      i := 0x050
+      loop
+        v1 := random 0x3F
+        v2 := random 0x1F
+        clear
+        sprite v1 v2 5
+      again
+    ",
+  )
+  .unwrap();
+  let image = program.get_image();
+  let config = MovieConfig {
+    seed: 0x0123_4567_89AB_CDEF,
+    ..MovieConfig::default()
+  };
+
+  let mut recorder = MovieRecorder::new(image, config).unwrap();
+  recorder
+    .simulate_duration(Duration::from_millis(100))
+    .unwrap();
+  recorder.change_keyboard_state(Nibble::try_from(5).unwrap(), true);
+  recorder
+    .simulate_duration(Duration::from_millis(50))
+    .unwrap();
+  recorder.change_keyboard_state(Nibble::try_from(5).unwrap(), false);
+  recorder
+    .simulate_duration(Duration::from_millis(205))
+    .unwrap();
+  let recorded_screen = *recorder.get_interpreter().get_platform().get_screen_frame();
+  let movie = recorder.finish();
+  assert_eq!(movie.get_duration(), Duration::from_millis(355));
+  assert_eq!(movie.get_events().len(), 2);
+  assert_eq!(movie.get_events()[0].time, Duration::from_millis(100));
+  assert_eq!(movie.get_frame_hashes().len(), 355 / 16);
+
+  let mut data = movie.save_state();
+  let movie = Movie::from_bytes(&data).unwrap();
+  assert_eq!(movie.get_config(), &config);
+
+  let mut player = MoviePlayer::new(movie.clone(), image).unwrap();
+  player.play().unwrap();
+  assert!(player.is_finished());
+  assert_eq!(player.get_frame(), movie.get_frame_hashes().len());
+  assert_eq!(
+    player.get_interpreter().get_platform().get_screen_frame(),
+    &recorded_screen
+  );
+
+  // The player seeds the platform from the movie, so a movie with another seed desyncs.
+  let seed_offset = data
+    .windows(8)
+    .position(|bytes| bytes == config.seed.to_le_bytes())
+    .unwrap();
+  data[seed_offset..seed_offset + 8].copy_from_slice(&7u64.to_le_bytes());
+  let checksum_offset = data.len() - 4;
+  let checksum = crc32(&data[..checksum_offset]);
+  data[checksum_offset..].copy_from_slice(&checksum.to_le_bytes());
+  let reseeded_movie = Movie::from_bytes(&data).unwrap();
+  assert_eq!(reseeded_movie.get_config().seed, 7);
+
+  let mut player = MoviePlayer::new(reseeded_movie, image).unwrap();
+  let Err(MovieError::Desync { frame, .. }) = player.play() else {
+    panic!("Replay with another seed has to desync");
+  };
+  assert_eq!(player.get_frame(), frame);
+  assert_eq!(frame, 150 / 16);
+
+  assert!(matches!(
+    MoviePlayer::new(movie, &image[1..]),
+    Err(MovieError::ImageMismatch)
+  ));
+}
+
+#[test]
+fn test_movie_timing() {
+  let program = assemble(
+    "
+    : main
+      i := 0x050
+      loop
+        v1 := random 0x3F
+        clear
+        sprite v1 v1 5
+      again
+    ",
+  )
+  .unwrap();
+  let image = program.get_image();
+  let record = |timing, duration| {
+    let config = MovieConfig {
+      seed: 0x0123_4567_89AB_CDEF,
+      timing,
+      ..MovieConfig::default()
+    };
+    let mut recorder = MovieRecorder::new(image, config).unwrap();
+    assert_eq!(recorder.get_interpreter().get_timing(), timing);
+    recorder.simulate_duration(duration).unwrap();
+    (config, recorder.finish().save_state())
+  };
+
+  let timings = [
+    (Timing::CosmacVip, get_vip_frame_duration() * FRAME_RATE),
+    (
+      Timing::FrameBased {
+        instructions_per_frame: 11,
+      },
+      get_frame_start(FRAME_RATE as u64),
+    ),
+  ];
+  for (timing, duration) in timings {
+    let (config, data) = record(timing, duration);
+    let movie = Movie::from_bytes(&data).unwrap();
+    assert_eq!(movie.get_config(), &config);
+    assert_eq!(movie.get_frame_hashes().len(), FRAME_RATE as usize);
+
+    let mut player = MoviePlayer::new(movie, image).unwrap();
+    assert_eq!(player.get_interpreter().get_timing(), timing);
+    player.play().unwrap();
+    assert_eq!(player.get_frame(), FRAME_RATE as usize);
+  }
+
+  // A movie of the VIP timing relabeled as fixed has fewer frames than its duration needs.
+  let (config, mut data) = record(Timing::CosmacVip, Duration::from_secs(1));
+  let timing_offset = data
+    .windows(8)
+    .position(|bytes| bytes == config.seed.to_le_bytes())
+    .unwrap()
+    + 8;
+  assert_eq!(data[timing_offset], 1);
+  data[timing_offset] = 0;
+  let checksum_offset = data.len() - 4;
+  let checksum = crc32(&data[..checksum_offset]);
+  data[checksum_offset..].copy_from_slice(&checksum.to_le_bytes());
+  assert!(matches!(
+    Movie::from_bytes(&data),
+    Err(SaveStateError::InvalidValue("frame count"))
+  ));
+
+  data[timing_offset] = 3;
+  let checksum = crc32(&data[..checksum_offset]);
+  data[checksum_offset..].copy_from_slice(&checksum.to_le_bytes());
+  assert!(matches!(
+    Movie::from_bytes(&data),
+    Err(SaveStateError::InvalidValue("timing"))
+  ));
+}
+
+#[test]
+fn test_random_generators() {
+  let sequence =
+    |mut rand: SeededRandom| (0..16).map(|_| rand.get_random_byte()).collect::<Vec<_>>();
+  assert_eq!(
+    sequence(SeededRandom::new(1)),
+    sequence(SeededRandom::new(1))
+  );
+  assert_ne!(
+    sequence(SeededRandom::new(1)),
+    sequence(SeededRandom::new(2))
+  );
+
+  let program = encode_program(&[
+    Instruction::SetRegisterRandom(Nibble::try_from(0).unwrap(), 0xFF),
+    Instruction::SetRegisterRandom(Nibble::try_from(1).unwrap(), 0x0F),
+    Instruction::SetRegisterRandom(Nibble::try_from(2).unwrap(), 0xFF),
+  ]);
+  let platform = BasePlatform::new(ScriptedRandom::new(vec![0xAB, 0xCD]));
+  let executable = BaseExecutable::new(&program, Address::new::<0x200>());
+  let mut interpreter = Interpreter::new(
+    platform,
+    executable,
+    Mode::Chip8,
+    Font::default(),
+    Quirks::COSMAC_VIP,
+  )
+  .unwrap();
+  (0..3).for_each(|_| interpreter.run_next().unwrap());
+  assert_eq!(
+    interpreter.get_state().get_registers()[..3],
+    [0xAB, 0x0D, 0xAB]
+  );
+  assert_eq!(
+    interpreter
+      .get_platform()
+      .get_random_generator()
+      .get_position(),
+    3
+  );
+
+  let mut interpreter_page = [0; VIP_INTERPRETER_PAGE_SIZE];
+  interpreter_page
+    .iter_mut()
+    .enumerate()
+    .for_each(|(index, value)| *value = index as u8);
+  let mut platform = BasePlatform::new(VipRandom::new(interpreter_page, 0x1234));
+  assert_eq!(platform.get_random_byte(), 0x6A);
+  assert_eq!(platform.get_random_byte(), 0xF0);
+  assert_eq!(platform.get_random_byte(), 0xBA);
+  assert_eq!(platform.get_random_generator().get_r9(), 0xBA37);
+  platform.notify_vblank();
+  assert_eq!(platform.get_random_byte(), 0x6C);
+
+  // Wraps `R9` and carries out of the addition, with a frame after the fourth byte.
+  interpreter_page
+    .iter_mut()
+    .enumerate()
+    .for_each(|(index, value)| *value = (index as u8).wrapping_mul(0x25).wrapping_add(0x0B));
+  let mut rand = VipRandom::new(interpreter_page, 0xFFFE);
+  let mut sequence = (0..4).map(|_| rand.get_random_byte()).collect::<Vec<_>>();
+  rand.notify_vblank();
+  sequence.extend((0..4).map(|_| rand.get_random_byte()));
+  assert_eq!(sequence, [0xD7, 0x54, 0xC6, 0xA8, 0xEA, 0x85, 0x25, 0x4C]);
+  assert_eq!(rand.get_r9(), 0x4C07);
+}
+
+#[test]
+fn test_cosmac_vip_timing() {
+  let program = encode_program(&[
+    Instruction::SetRegister(Nibble::try_from(0).unwrap(), 0),
+    Instruction::SetRegister(Nibble::try_from(1).unwrap(), 0),
+    Instruction::SetAddressRegister(DEFAULT_FONT_LOAD_POINT),
+    Instruction::DrawSprite(
+      Nibble::try_from(0).unwrap(),
+      Nibble::try_from(1).unwrap(),
+      Nibble::try_from(5).unwrap(),
+    ),
+    Instruction::SetRegister(Nibble::try_from(0).unwrap(), 3),
+    Instruction::DrawSprite(
+      Nibble::try_from(0).unwrap(),
+      Nibble::try_from(1).unwrap(),
+      Nibble::try_from(5).unwrap(),
+    ),
+    Instruction::AddRegister(Nibble::try_from(2).unwrap(), 1),
+    Instruction::Jump(Address::from(0x20C)),
+  ]);
+  let platform = BasePlatform::new(rand::random as fn() -> u8);
+  let executable = BaseExecutable::new(&program, Address::new::<0x200>());
+  let quirks = Quirks {
+    display_wait: false,
+    ..Quirks::COSMAC_VIP
+  };
+  let interpreter =
+    Interpreter::new(platform, executable, Mode::Chip8, Font::default(), quirks).unwrap();
+  let mut interpreter = ControlledInterpreter::new_cosmac_vip(interpreter);
+  assert_eq!(interpreter.get_timing(), Timing::CosmacVip);
+  assert_eq!(interpreter.get_frame_duration(), get_vip_frame_duration());
+  assert_eq!(
+    get_vip_frame_duration(),
+    get_vip_cycles_duration(VIP_FRAME_CYCLES as u64)
+  );
+
+  let mut step = || {
+    let start = interpreter.get_elapsed();
+    interpreter.simulate_one_instruction().unwrap();
+    interpreter.get_elapsed() - start
+  };
+  // The first instruction waits for the interrupt and the display DMA of the first frame.
+  assert_eq!(
+    step(),
+    get_vip_cycles_duration(VIP_INTERRUPT_CYCLES as u64 + 46)
+  );
+  assert_eq!(step(), get_vip_cycles_duration(46));
+  assert_eq!(step(), get_vip_cycles_duration(52));
+  let aligned_draw = step();
+  step();
+  let shifted_draw = step();
+  assert_eq!(aligned_draw, get_vip_cycles_duration(236));
+  assert_eq!(shifted_draw, get_vip_cycles_duration(366));
+
+  // The loop alternates 50 and 52 cycle instructions in 2614 free cycles of 60 frames.
+  let executed_count = interpreter.get_state().get_executed_count();
+  interpreter
+    .simulate_duration(Duration::from_secs(1))
+    .unwrap();
+  let count = interpreter.get_state().get_executed_count() - executed_count;
+  let free_cycles = 60 * (VIP_FRAME_CYCLES - VIP_INTERRUPT_CYCLES) as u64;
+  assert!((free_cycles / 52..=free_cycles / 50).contains(&count));
+}
+
+#[test]
+fn test_frame_based_timing() {
+  let program = encode_program(&[
+    Instruction::SetRegister(Nibble::try_from(0).unwrap(), 255),
+    Instruction::SetDelayTimer(Nibble::try_from(0).unwrap()),
+    Instruction::AddRegister(Nibble::try_from(1).unwrap(), 1),
+    Instruction::Jump(Address::from(0x204)),
+  ]);
+  let platform = BasePlatform::new(rand::random as fn() -> u8);
+  let executable = BaseExecutable::new(&program, Address::new::<0x200>());
+  let interpreter = Interpreter::new(
+    platform,
+    executable,
+    Mode::Chip8,
+    Font::default(),
+    Quirks::default(),
+  )
+  .unwrap();
+  let mut interpreter = ControlledInterpreter::new_frame_based(interpreter, 10);
+  assert_eq!(
+    interpreter.get_timing(),
+    Timing::FrameBased {
+      instructions_per_frame: 10
+    }
+  );
+
+  let events = Arc::new(Mutex::new(Vec::new()));
+  let handler_events = events.clone();
+  interpreter.set_frame_handler(move |event| handler_events.lock().unwrap().push(*event));
+
+  assert_eq!(interpreter.run_frame().unwrap(), StopReason::TimeElapsed);
+  assert_eq!(interpreter.get_state().get_executed_count(), 10);
+  assert_eq!(interpreter.get_platform().get_delay_timer(), 254);
+  assert_eq!(interpreter.get_frame_count(), 1);
+
+  // Frames of 1/60 s add up to exactly one second without drifting.
+  for _ in 1..FRAME_RATE {
+    interpreter.run_frame().unwrap();
+  }
+  assert_eq!(interpreter.get_elapsed(), Duration::from_secs(1));
+  assert_eq!(get_frame_start(FRAME_RATE as u64), Duration::from_secs(1));
+  assert_eq!(interpreter.get_state().get_executed_count(), 600);
+  assert_eq!(interpreter.get_platform().get_delay_timer(), 255 - 60);
+
+  let events = events.lock().unwrap();
+  assert_eq!(events.len(), 60);
+  for (index, event) in events.iter().enumerate() {
+    assert_eq!(event.index, index as u64);
+    assert_eq!(event.elapsed, get_frame_start(index as u64 + 1));
+    assert_eq!(event.executed_count, (index as u64 + 1) * 10);
+  }
+}
+
+#[test]
+fn test_display_wait() {
+  let program = encode_program(&[
+    Instruction::SetRegister(Nibble::try_from(0).unwrap(), 100),
+    Instruction::SetDelayTimer(Nibble::try_from(0).unwrap()),
+    Instruction::SetAddressRegister(DEFAULT_FONT_LOAD_POINT),
+    Instruction::DrawSprite(
+      Nibble::try_from(2).unwrap(),
+      Nibble::try_from(2).unwrap(),
+      Nibble::try_from(5).unwrap(),
+    ),
+    Instruction::AddRegister(Nibble::try_from(1).unwrap(), 1),
+    Instruction::Jump(Address::from(0x206)),
+  ]);
+  let create_interpreter = || {
+    let platform = BasePlatform::new(rand::random as fn() -> u8);
+    let executable = BaseExecutable::new(&program, Address::new::<0x200>());
+    Interpreter::new(
+      platform,
+      executable,
+      Mode::Chip8,
+      Font::default(),
+      Quirks::COSMAC_VIP,
+    )
+    .unwrap()
+  };
+  let get_counter =
+    |interpreter: &ControlledInterpreter<_>| interpreter.get_state().get_registers()[1];
+
+  // Stepping over the wait simulates the rest of the frame.
+  let mut interpreter = ControlledInterpreter::new(
+    create_interpreter(),
+    DEFAULT_INSTRUCTION_DURATION,
+    DEFAULT_DELAY_TIMER_DURATION,
+    DEFAULT_SOUND_TIMER_DURATION,
+  );
+  for _ in 0..4 {
+    interpreter.simulate_one_instruction().unwrap();
+  }
+  assert!(interpreter.get_state().is_waiting_vblank());
+  assert_eq!(interpreter.get_platform().get_delay_timer(), 100);
+  interpreter.simulate_one_instruction().unwrap();
+  assert!(!interpreter.get_state().is_waiting_vblank());
+  assert_eq!(interpreter.get_state().get_executed_count(), 5);
+  assert_eq!(get_counter(&interpreter), 1);
+  assert_eq!(interpreter.get_platform().get_delay_timer(), 99);
+  assert_eq!(interpreter.get_elapsed(), DEFAULT_DELAY_TIMER_DURATION);
+
+  // Draws are capped at one per frame.
+  interpreter
+    .simulate_duration(Duration::from_secs(1))
+    .unwrap();
+  assert_eq!(interpreter.get_frame_count(), 63);
+  assert_eq!(
+    get_counter(&interpreter) as u64,
+    interpreter.get_frame_count()
+  );
+
+  // The VIP resumes right after the display interrupt of the next frame.
+  let mut interpreter = ControlledInterpreter::new_cosmac_vip(create_interpreter());
+  for _ in 0..5 {
+    interpreter.simulate_one_instruction().unwrap();
+  }
+  assert_eq!(get_counter(&interpreter), 1);
+  assert_eq!(
+    interpreter.get_elapsed(),
+    get_vip_frame_duration() + get_vip_cycles_duration(VIP_INTERRUPT_CYCLES as u64 + 50)
+  );
+}
+
+#[test]
+fn test_loader_errors() {
+  let load = |image: &[u8], load_point: u16, font: Font| {
+    let platform = BasePlatform::new(rand::random as fn() -> u8);
+    let executable = BaseExecutable::new(image, Address::from(load_point));
+    Interpreter::new(platform, executable, Mode::Chip8, font, Quirks::default())
+  };
+
+  assert!(matches!(
+    load(&[], 0x200, Font::default()),
+    Err(LoaderError::EmptyImage)
+  ));
+  assert!(load(&[0; MEMORY_SIZE - 0x200], 0x200, Font::default()).is_ok());
+  assert!(matches!(
+    load(&[0; MEMORY_SIZE - 0x1FF], 0x200, Font::default()),
+    Err(LoaderError::ImageTooLarge { size, .. }) if size == MEMORY_SIZE - 0x1FF
+  ));
+  assert!(matches!(
+    load(&[0x00, 0xE0], 0xFFFF, Font::default()),
+    Err(LoaderError::ImageTooLarge { .. })
+  ));
+  let font = Font::from_set(FontSet::default(), Address::from(0xFF0));
+  assert!(matches!(
+    load(&[0x00, 0xE0], 0x200, font),
+    Err(LoaderError::FontOutOfMemory(_))
+  ));
+}
+
+#[test]
+fn test_panic_free_execution() {
+  // Sprite data at the end of memory wraps around to its start.
+  let program = encode_program(&[
+    Instruction::SetAddressRegister(Address::from(0xFFE)),
+    Instruction::DrawSprite(
+      Nibble::try_from(0).unwrap(),
+      Nibble::try_from(0).unwrap(),
+      Nibble::try_from(4).unwrap(),
+    ),
+  ]);
+  let mut interpreter = create_interpreter(
+    &program,
+    Mode::Chip8,
+    Font::default(),
+    Quirks::default(),
+    DEFAULT_INSTRUCTION_DURATION,
+  );
+  interpreter
+    .get_interpreter_mut()
+    .write_memory(Address::from(0xFFE), &[0x80, 0x80]);
+  interpreter.simulate_one_instruction().unwrap();
+  interpreter.simulate_one_instruction().unwrap();
+  let rows = interpreter
+    .get_platform()
+    .get_screen_frame()
+    .iter_rows()
+    .take(2)
+    .map(|row| row[0])
+    .collect::<Vec<_>>();
+  assert_eq!(rows, [1, 1]);
+
+  // Random images either run or stop with an error.
+  let mut rand = SeededRandom::new(0xC8);
+  for case in 0..300 {
+    let len = 1 + (rand.next_u64() % 256) as usize;
+    let image = (0..len).map(|_| rand.get_random_byte()).collect::<Vec<_>>();
+    let mode = [Mode::Chip8, Mode::SuperChip, Mode::XoChip][case % 3];
+    let quirks = [Quirks::COSMAC_VIP, Quirks::SUPER_CHIP_1_1, Quirks::XO_CHIP][case % 3];
+    let mut interpreter = create_interpreter(
+      &image,
+      mode,
+      Font::default(),
+      quirks,
+      DEFAULT_INSTRUCTION_DURATION,
+    );
+    for _ in 0..500 {
+      if interpreter.simulate_one_instruction().is_err() {
+        break;
+      }
+    }
+  }
+}
+
+#[test]
+fn test_crash_report() {
+  let program = encode_program(&[
+    Instruction::SetRegister(Nibble::try_from(3).unwrap(), 0x42),
+    Instruction::SetAddressRegister(Address::from(0x300)),
+    Instruction::Call(Address::from(0x208)),
+    Instruction::ClearScreen,
+    Instruction::HighResolution,
+  ]);
+  let mut interpreter = create_interpreter(
+    &program,
+    Mode::Chip8,
+    Font::default(),
+    Quirks::default(),
+    DEFAULT_INSTRUCTION_DURATION,
+  );
+  let err = interpreter
+    .simulate_duration(Duration::from_millis(100))
+    .unwrap_err();
+  let InterpreterError::Fault { cause, report } = &err else {
+    panic!("Unexpected error: {err}");
+  };
+  assert_eq!(
+    **cause,
+    InterpreterError::UnknownOpCode(OpCode::from_bytes(0x00, 0xFF))
+  );
+  assert_eq!(report.address, Address::from(0x208));
+  assert_eq!(report.instruction, Some(Instruction::HighResolution));
+  assert_eq!(report.registers[3], 0x42);
+  assert_eq!(report.index_register, Address::from(0x300));
+  assert_eq!(report.backtrace, [Address::from(0x206)]);
+  assert_eq!(
+    err.to_string().lines().collect::<Vec<_>>(),
+    [
+      "Unknown opcode: 00FF",
+      "PC:0208 OP:00FF (HIGH)",
+      "I:0300 V0:00 V1:00 V2:00 V3:42 V4:00 V5:00 V6:00 V7:00 V8:00 V9:00 VA:00 VB:00 VC:00 \
+       VD:00 VE:00 VF:00",
+      "Backtrace: 0206",
+    ]
+  );
+
+  // Later runs keep returning the original failure.
+  let crashed = interpreter.simulate_one_instruction().unwrap_err();
+  assert_eq!(crashed, InterpreterError::Crashed(Box::new(err.clone())));
+  assert_eq!(interpreter.get_interpreter().get_crash(), Some(&err));
+
+  // Save states keep the cause of the failure.
+  let mut restored = create_interpreter(
+    &[0x00, 0xE0],
+    Mode::Chip8,
+    Font::default(),
+    Quirks::default(),
+    DEFAULT_INSTRUCTION_DURATION,
+  );
+  restored.load_state(&interpreter.save_state()).unwrap();
+  assert_eq!(restored.get_interpreter().get_crash(), Some(&err));
+
+  let mut interpreter = create_interpreter(
+    &encode_program(&[Instruction::Return]),
+    Mode::Chip8,
+    Font::default(),
+    Quirks::default(),
+    DEFAULT_INSTRUCTION_DURATION,
+  );
+  let err = interpreter.simulate_one_instruction().unwrap_err();
+  assert!(matches!(
+    err,
+    InterpreterError::Fault { cause, report }
+      if *cause == InterpreterError::StackUnderflow && report.backtrace.is_empty()
+  ));
+}
+
+#[test]
+fn test_crash_cause_save_state() {
+  let program = encode_program(&[
+    Instruction::SetRegister(Nibble::new::<5>(), 0x20),
+    Instruction::SkipIfKeyDown(Nibble::new::<5>()),
+  ]);
+  let mut interpreter = create_interpreter(
+    &program,
+    Mode::Chip8,
+    Font::default(),
+    Quirks::default(),
+    DEFAULT_INSTRUCTION_DURATION,
+  );
+  let err = interpreter
+    .simulate_duration(Duration::from_millis(100))
+    .unwrap_err();
+  assert!(matches!(
+    &err,
+    InterpreterError::Fault { cause, .. } if **cause == InterpreterError::NibbleOverflow
+  ));
+
+  let mut restored = create_interpreter(
+    &program,
+    Mode::Chip8,
+    Font::default(),
+    Quirks::default(),
+    DEFAULT_INSTRUCTION_DURATION,
+  );
+  restored.load_state(&interpreter.save_state()).unwrap();
+  assert_eq!(restored.get_interpreter().get_crash(), Some(&err));
+}